@@ -112,6 +112,9 @@ fn serialize_error(builder: &mut String, error: &ParseError) {
         &ParseError::IncorrectUTF16NoLowSurrogate(ref _x) => {
             builder.push_str("IncorrectUTF16NoLowSurrogate")
         }
+        &ParseError::InconsistentIndentation(ref _x) => {
+            builder.push_str("InconsistentIndentation")
+        }
     }
     //builder.push_str(error);
     builder.push_str("\", \"position\": ");
@@ -164,7 +167,7 @@ fn serialize_symbol(builder: &mut String, symbol: Symbol) {
     builder.push_str("{\"id\": ");
     builder.push_str(&symbol.id.to_string());
     builder.push_str(", \"name\": \"");
-    builder.push_str(&escape_str(symbol.name));
+    builder.push_str(&escape_str(&symbol.name));
     builder.push_str("\"}");
 }
 
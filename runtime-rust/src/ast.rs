@@ -20,12 +20,14 @@
 use std::fmt::Display;
 use std::fmt::Error;
 use std::fmt::Formatter;
+use std::ops::Range;
 
 use super::symbols::SemanticElementTrait;
 use super::symbols::Symbol;
 use super::text::TextContext;
 use super::text::TextPosition;
 use super::text::TextSpan;
+use super::tokens::HiddenToken;
 use super::tokens::Token;
 use super::tokens::TokenRepository;
 use super::utils::biglist::BigList;
@@ -140,6 +142,12 @@ impl AstImpl {
     pub fn has_root(&self) -> bool {
         self.root.is_some()
     }
+
+    /// Removes all the nodes and the root, keeping the allocated storage
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.root = None;
+    }
 }
 
 /// Represents a simple AST with a tree structure
@@ -149,9 +157,9 @@ pub struct Ast<'a> {
     /// The table of tokens
     tokens: Option<TokenRepository<'a>>,
     /// The table of variables
-    variables: &'static [Symbol],
+    variables: &'a [Symbol],
     /// The table of virtuals
-    virtuals: &'static [Symbol],
+    virtuals: &'a [Symbol],
     /// The data of the implementation
     data: EitherMut<'a, AstImpl>
 }
@@ -160,8 +168,8 @@ impl<'a> Ast<'a> {
     /// Creates a new AST proxy structure
     pub fn new(
         tokens: TokenRepository<'a>,
-        variables: &'static [Symbol],
-        virtuals: &'static [Symbol],
+        variables: &'a [Symbol],
+        virtuals: &'a [Symbol],
         data: &'a AstImpl
     ) -> Ast<'a> {
         Ast {
@@ -174,8 +182,8 @@ impl<'a> Ast<'a> {
 
     /// Creates a new AST proxy structure
     pub fn new_mut(
-        variables: &'static [Symbol],
-        virtuals: &'static [Symbol],
+        variables: &'a [Symbol],
+        virtuals: &'a [Symbol],
         data: &'a mut AstImpl
     ) -> Ast<'a> {
         Ast {
@@ -195,13 +203,13 @@ impl<'a> Ast<'a> {
     }
 
     /// Gets the grammar variables for this AST
-    pub fn get_variables(&self) -> &'static [Symbol] {
-        &self.variables
+    pub fn get_variables(&self) -> &'a [Symbol] {
+        self.variables
     }
 
     /// Gets the grammar virtuals for this AST
-    pub fn get_virtuals(&self) -> &'static [Symbol] {
-        &self.virtuals
+    pub fn get_virtuals(&self) -> &'a [Symbol] {
+        self.virtuals
     }
 
     /// Gets whether a root has been defined for this AST
@@ -258,7 +266,7 @@ impl<'a> Ast<'a> {
     /// a token label that contains the specified index in the input text
     pub fn find_node_at_position(&self, position: TextPosition) -> Option<AstNode> {
         let tokens = self.tokens.as_ref().unwrap();
-        let index = tokens.get_input().get_line_index(position.line) + position.column - 1;
+        let index = tokens.get_input().get_index_at(position);
         let token = tokens.find_token_at(index);
         match token {
             None => None,
@@ -338,6 +346,84 @@ impl<'a> AstNode<'a> {
         }
     }
 
+    /// Gets the trivia retained before the first token of this node
+    pub fn get_leading_trivia(&self) -> Vec<HiddenToken<'a>> {
+        match self.get_first_token() {
+            None => Vec::new(),
+            Some(token) => token.get_leading_trivia()
+        }
+    }
+
+    /// Gets the trivia retained after the last token of this node, on the same line
+    pub fn get_trailing_trivia(&self) -> Vec<HiddenToken<'a>> {
+        match self.get_last_token() {
+            None => Vec::new(),
+            Some(token) => token.get_trailing_trivia()
+        }
+    }
+
+    /// Gets the first token in the sub-tree of this node, if any
+    pub fn get_first_token(&self) -> Option<Token<'a>> {
+        self.find_first_token()
+            .map(|index| self.tree.get_token(index))
+    }
+
+    /// Gets the last token in the sub-tree of this node, if any
+    pub fn get_last_token(&self) -> Option<Token<'a>> {
+        self.find_last_token()
+            .map(|index| self.tree.get_token(index))
+    }
+
+    /// Gets the index of the first token in the sub-tree of this node, if any
+    fn find_first_token(&self) -> Option<usize> {
+        self.find_token_bounds().map(|(first, _)| first)
+    }
+
+    /// Gets the index of the last token in the sub-tree of this node, if any
+    fn find_last_token(&self) -> Option<usize> {
+        self.find_token_bounds().map(|(_, last)| last)
+    }
+
+    /// Gets the indices of the first and last tokens in the sub-tree of this node, if any
+    /// A promoted token is not necessarily the first of its sub-tree, so the whole sub-tree is visited.
+    fn find_token_bounds(&self) -> Option<(usize, usize)> {
+        let cell = self.tree.data.get().nodes[self.index];
+        let mut result = if cell.label.get_type() == TableType::Token {
+            Some((cell.label.get_index(), cell.label.get_index()))
+        } else {
+            None
+        };
+        for i in 0..cell.count as usize {
+            let child = AstNode {
+                tree: self.tree,
+                index: cell.first as usize + i
+            };
+            if let Some((first, last)) = child.find_token_bounds() {
+                result = match result {
+                    None => Some((first, last)),
+                    Some((start, end)) => Some((start.min(first), end.max(last)))
+                };
+            }
+        }
+        result
+    }
+
+    /// Gets whether the input text has been edited since this node has been produced
+    pub fn is_stale(&self) -> bool {
+        match self.tree.tokens {
+            None => false,
+            Some(ref tokens) => tokens.is_stale()
+        }
+    }
+
+    /// Gets the range in the UTF-8 encoding of the input text covered by this node, if any
+    /// The range goes from the start of the first token to the end of the last token in the sub-tree.
+    pub fn get_utf8_range(&self) -> Option<Range<usize>> {
+        let first = self.get_first_token()?;
+        let last = self.get_last_token()?;
+        Some(first.get_utf8_range().start..last.get_utf8_range().end)
+    }
+
     /// Gets the parent of this node, if any
     pub fn parent(&self) -> Option<AstNode> {
         self.tree.find_parent_of(self.index)
@@ -397,12 +483,12 @@ impl<'a> SemanticElementTrait for AstNode<'a> {
                 let token = self.tree.get_token(cell.label.get_index());
                 token.get_symbol()
             }
-            TableType::Variable => self.tree.variables[cell.label.get_index()],
-            TableType::Virtual => self.tree.virtuals[cell.label.get_index()],
+            TableType::Variable => self.tree.variables[cell.label.get_index()].clone(),
+            TableType::Virtual => self.tree.virtuals[cell.label.get_index()].clone(),
             TableType::None => {
                 match self.tree.tokens {
                     None => panic!("Missing token repository"),
-                    Some(ref repository) => repository.get_terminals()[0] // terminal epsilon
+                    Some(ref repository) => repository.get_terminals()[0].clone() // terminal epsilon
                 }
             }
         }
@@ -432,17 +518,17 @@ impl<'a> Display for AstNode<'a> {
                 write!(f, "{} = {}", symbol.name, value.unwrap())
             }
             TableType::Variable => {
-                let symbol = self.tree.variables[cell.label.get_index()];
+                let symbol = self.tree.variables[cell.label.get_index()].clone();
                 write!(f, "{}", symbol.name)
             }
             TableType::Virtual => {
-                let symbol = self.tree.virtuals[cell.label.get_index()];
+                let symbol = self.tree.virtuals[cell.label.get_index()].clone();
                 write!(f, "{}", symbol.name)
             }
             TableType::None => match self.tree.tokens {
                 None => panic!("Missing token repository"),
                 Some(ref repository) => {
-                    let symbol = repository.get_terminals()[0];
+                    let symbol = repository.get_terminals()[0].clone();
                     write!(f, "{}", symbol.name)
                 }
             }
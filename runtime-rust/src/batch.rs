@@ -0,0 +1,250 @@
+/*******************************************************************************
+ * Copyright (c) 2017 Association Cénotélie (cenotelie.fr)
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Lesser General Public License as
+ * published by the Free Software Foundation, either version 3
+ * of the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General
+ * Public License along with this program.
+ * If not, see <http://www.gnu.org/licenses/>.
+ ******************************************************************************/
+
+//! Module for parsing batches of inputs in parallel
+//! The inputs are parsed on a pool of threads sharing the same parser,
+//! usually the `parse_utf8` function of a generated parser or a closure over a `LoadedParser`.
+//! The automata of the parser are deserialized once and shared between the threads.
+//! The results are returned in the order of the inputs.
+
+use std::fs::File;
+use std::io;
+use std::io::BufReader;
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::sync::Mutex;
+use std::thread;
+
+use super::result::ParseResult;
+
+/// An input in a batch
+pub enum BatchInput {
+    /// A string to be parsed
+    String(String),
+    /// The path to a file to be parsed, in UTF-8
+    Path(PathBuf),
+    /// A reader of UTF-8 to be parsed
+    Reader(Box<dyn Read + Send>)
+}
+
+impl From<String> for BatchInput {
+    fn from(input: String) -> BatchInput {
+        BatchInput::String(input)
+    }
+}
+
+impl<'a> From<&'a str> for BatchInput {
+    fn from(input: &'a str) -> BatchInput {
+        BatchInput::String(input.to_string())
+    }
+}
+
+impl From<PathBuf> for BatchInput {
+    fn from(input: PathBuf) -> BatchInput {
+        BatchInput::Path(input)
+    }
+}
+
+impl From<Box<dyn Read + Send>> for BatchInput {
+    fn from(input: Box<dyn Read + Send>) -> BatchInput {
+        BatchInput::Reader(input)
+    }
+}
+
+impl BatchInput {
+    /// Parses this input with the specified parser
+    fn parse<P>(self, parser: &P) -> Result<ParseResult, io::Error>
+    where
+        P: Fn(&mut dyn Read) -> ParseResult
+    {
+        match self {
+            BatchInput::String(input) => Ok(parser(&mut input.as_bytes())),
+            BatchInput::Path(path) => {
+                let mut reader = BufReader::new(File::open(path)?);
+                Ok(parser(&mut reader))
+            }
+            BatchInput::Reader(mut reader) => Ok(parser(&mut reader))
+        }
+    }
+}
+
+/// Parses batches of inputs on a pool of threads
+#[derive(Copy, Clone)]
+pub struct BatchParser {
+    /// The number of threads in the pool
+    threads: usize
+}
+
+impl Default for BatchParser {
+    fn default() -> BatchParser {
+        BatchParser::new(
+            thread::available_parallelism()
+                .map(|count| count.get())
+                .unwrap_or(1)
+        )
+    }
+}
+
+impl BatchParser {
+    /// Creates a batch parser with the specified number of threads (at least one)
+    pub fn new(threads: usize) -> BatchParser {
+        BatchParser {
+            threads: if threads == 0 { 1 } else { threads }
+        }
+    }
+
+    /// Gets the number of threads in the pool
+    pub fn get_threads(&self) -> usize {
+        self.threads
+    }
+
+    /// Parses the inputs with the specified parser, e.g. the `parse_utf8` function of a generated parser
+    /// The results are in the order of the inputs.
+    /// An input is in error when it could not be read.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the parser panics on an input.
+    pub fn parse<P>(
+        &self,
+        inputs: Vec<BatchInput>,
+        parser: P
+    ) -> Vec<Result<ParseResult, io::Error>>
+    where
+        P: Fn(&mut dyn Read) -> ParseResult + Sync
+    {
+        self.parse_with(inputs, parser, |result| result)
+    }
+
+    /// Parses the inputs with the specified parser and summarizes each result
+    /// The summaries are computed on the pool's threads, so that the full results need not be kept.
+    /// The summaries are in the order of the inputs.
+    /// An input is in error when it could not be read.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the parser or the summary panics on an input.
+    pub fn parse_with<P, S, T>(
+        &self,
+        inputs: Vec<BatchInput>,
+        parser: P,
+        summary: S
+    ) -> Vec<Result<T, io::Error>>
+    where
+        P: Fn(&mut dyn Read) -> ParseResult + Sync,
+        S: Fn(ParseResult) -> T + Sync,
+        T: Send
+    {
+        let count = inputs.len();
+        let queue = Mutex::new(inputs.into_iter().enumerate());
+        let (sender, receiver) = mpsc::channel::<(usize, Result<T, io::Error>)>();
+        thread::scope(|scope| {
+            for _ in 0..self.threads.min(count) {
+                let sender = sender.clone();
+                let queue = &queue;
+                let parser = &parser;
+                let summary = &summary;
+                scope.spawn(move || loop {
+                    let next = match queue.lock() {
+                        Ok(mut queue) => queue.next(),
+                        Err(poisoned) => poisoned.into_inner().next()
+                    };
+                    match next {
+                        None => break,
+                        Some((index, input)) => {
+                            let result = input.parse(parser).map(summary);
+                            if sender.send((index, result)).is_err() {
+                                break;
+                            }
+                        }
+                    }
+                });
+            }
+        });
+        drop(sender);
+        let mut results: Vec<Option<Result<T, io::Error>>> = (0..count).map(|_| None).collect();
+        for (index, result) in receiver.iter() {
+            results[index] = Some(result);
+        }
+        results
+            .into_iter()
+            .map(|result| result.expect("missing result in batch"))
+            .collect()
+    }
+}
+
+#[test]
+fn test_batch_parser_order() {
+    use super::symbols::Symbol;
+    use super::text::Text;
+    const SYMBOLS: &[Symbol] = &[];
+    let inputs: Vec<BatchInput> = (0..50)
+        .map(|i| BatchInput::from("x".repeat(i)))
+        .chain(Some(BatchInput::Path(PathBuf::from("/nonexistent/input"))))
+        .collect();
+    let parser = |input: &mut dyn Read| {
+        ParseResult::new(SYMBOLS, SYMBOLS, SYMBOLS, Text::from_utf8_stream(input))
+    };
+    let results = BatchParser::new(4).parse_with(inputs, parser, |result| result.get_input().len());
+    assert_eq!(results.len(), 51);
+    for (i, result) in results.iter().take(50).enumerate() {
+        assert_eq!(*result.as_ref().unwrap(), i);
+    }
+    assert!(results[50].is_err());
+}
+
+#[test]
+fn test_batch_parser_loaded_parser() {
+    use super::loader::LoadedParser;
+    use super::symbols::SemanticElementTrait;
+    fn assert_send<T: Send>() {}
+    assert_send::<ParseResult>();
+    let parser = LoadedParser::load(
+        include_bytes!("../../sdk-net/Sources/Input/HimeGrammarLexer.bin"),
+        include_bytes!("../../sdk-net/Sources/Input/HimeGrammarParser.bin"),
+        include_str!("../../sdk-net/Sources/Input/HimeGrammarSymbols.txt")
+    )
+    .unwrap();
+    let inputs: Vec<BatchInput> = (0..20)
+        .map(|i| {
+            if i % 5 == 4 {
+                BatchInput::from(format!("grammar G{} {{ options {{ }} rules {{ ", i))
+            } else {
+                BatchInput::from(format!(
+                    "grammar G{} {{ options {{ }} rules {{ a -> 'x'; }} }}",
+                    i
+                ))
+            }
+        })
+        .collect();
+    let results = BatchParser::new(4).parse(inputs, |input| parser.parse_utf8(input));
+    assert_eq!(results.len(), 20);
+    for (i, result) in results.into_iter().enumerate() {
+        let result = result.unwrap();
+        // the results are returned in the order of the inputs
+        assert_eq!(result.is_success(), i % 5 != 4);
+        if result.is_success() {
+            let ast = result.get_ast();
+            let root = ast.get_root();
+            let grammars = root.children();
+            let grammar = grammars.at(0);
+            let parts = grammar.children();
+            assert_eq!(parts.at(0).get_value().unwrap(), format!("G{}", i));
+        }
+    }
+}
@@ -25,6 +25,7 @@ use std::ops::Index;
 use symbols::Symbol;
 use text::TextPosition;
 use text::Utf16C;
+use utils::bin::AutomatonKind;
 use utils::iterable::Iterable;
 
 /// Common trait for data about an error
@@ -37,13 +38,61 @@ pub trait ParseErrorDataTrait {
 
     /// Gets the error's message
     fn get_message(&self) -> String;
+
+    /// Gets the corrections applied to the input at this error to recover from it, in order
+    fn get_corrections(&self) -> &[LexicalCorrection] {
+        &[]
+    }
 }
 
-/// Represents the unexpected of the input text while more characters were expected
+/// The kind of a correction applied to the input by a lexer to recover from a lexical error
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum LexicalCorrectionKind {
+    /// A character has been inserted in the input
+    Insertion,
+    /// A character of the input has been deleted
+    Deletion,
+    /// A character of the input has been substituted by another one
+    Substitution
+}
+
+/// Represents a correction applied to the input by a lexer to recover from a lexical error
 #[derive(Copy, Clone)]
+pub struct LexicalCorrection {
+    /// The kind of correction
+    pub kind: LexicalCorrectionKind,
+    /// The position of the correction in the input
+    pub position: TextPosition,
+    /// The index of the correction in the input
+    pub index: usize,
+    /// The inserted or substituted character, or the deleted character
+    pub value: Utf16C
+}
+
+impl Display for LexicalCorrection {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        let value = String::from_utf16_lossy(&[self.value]);
+        match self.kind {
+            LexicalCorrectionKind::Insertion => {
+                write!(f, "inserted '{}' at {}", value, self.position)
+            }
+            LexicalCorrectionKind::Deletion => {
+                write!(f, "deleted '{}' at {}", value, self.position)
+            }
+            LexicalCorrectionKind::Substitution => {
+                write!(f, "substituted '{}' at {}", value, self.position)
+            }
+        }
+    }
+}
+
+/// Represents the unexpected of the input text while more characters were expected
+#[derive(Clone)]
 pub struct ParseErrorEndOfInput {
     /// The error's position in the input text
-    position: TextPosition
+    position: TextPosition,
+    /// The corrections applied to recover from this error, if any
+    corrections: Vec<LexicalCorrection>
 }
 
 impl ParseErrorDataTrait for ParseErrorEndOfInput {
@@ -61,22 +110,38 @@ impl ParseErrorDataTrait for ParseErrorEndOfInput {
     fn get_message(&self) -> String {
         String::from("Unexpected end of input")
     }
+
+    /// Gets the corrections applied to the input at this error to recover from it, in order
+    fn get_corrections(&self) -> &[LexicalCorrection] {
+        &self.corrections
+    }
 }
 
 impl ParseErrorEndOfInput {
     /// Creates a new error
     pub fn new(position: TextPosition) -> ParseErrorEndOfInput {
-        ParseErrorEndOfInput { position }
+        ParseErrorEndOfInput {
+            position,
+            corrections: Vec::new()
+        }
+    }
+
+    /// Sets the corrections applied to recover from this error
+    pub fn with_corrections(mut self, corrections: Vec<LexicalCorrection>) -> ParseErrorEndOfInput {
+        self.corrections = corrections;
+        self
     }
 }
 
 /// Represents an unexpected character error in the input stream of a lexer
-#[derive(Copy, Clone)]
+#[derive(Clone)]
 pub struct ParseErrorUnexpectedChar {
     /// The error's position in the input text
     position: TextPosition,
     /// The unexpected character
-    unexpected: [Utf16C; 2]
+    unexpected: [Utf16C; 2],
+    /// The corrections applied to recover from this error, if any
+    corrections: Vec<LexicalCorrection>
 }
 
 impl ParseErrorDataTrait for ParseErrorUnexpectedChar {
@@ -113,6 +178,11 @@ impl ParseErrorDataTrait for ParseErrorUnexpectedChar {
         result.push_str(")");
         result
     }
+
+    /// Gets the corrections applied to the input at this error to recover from it, in order
+    fn get_corrections(&self) -> &[LexicalCorrection] {
+        &self.corrections
+    }
 }
 
 impl ParseErrorUnexpectedChar {
@@ -120,20 +190,32 @@ impl ParseErrorUnexpectedChar {
     pub fn new(position: TextPosition, unexpected: [Utf16C; 2]) -> ParseErrorUnexpectedChar {
         ParseErrorUnexpectedChar {
             position,
-            unexpected
+            unexpected,
+            corrections: Vec::new()
         }
     }
+
+    /// Sets the corrections applied to recover from this error
+    pub fn with_corrections(
+        mut self,
+        corrections: Vec<LexicalCorrection>
+    ) -> ParseErrorUnexpectedChar {
+        self.corrections = corrections;
+        self
+    }
 }
 
 /// Represents an incorrect encoding sequence error in the input of a lexer
-#[derive(Copy, Clone)]
+#[derive(Clone)]
 pub struct ParseErrorIncorrectEncodingSequence {
     /// The error's position in the input text
     position: TextPosition,
     /// The precise error type
     missing_high: bool,
     /// The incorrect sequence
-    sequence: Utf16C
+    sequence: Utf16C,
+    /// The corrections applied to recover from this error, if any
+    corrections: Vec<LexicalCorrection>
 }
 
 impl ParseErrorDataTrait for ParseErrorIncorrectEncodingSequence {
@@ -163,6 +245,11 @@ impl ParseErrorDataTrait for ParseErrorIncorrectEncodingSequence {
         result.push_str("]");
         result
     }
+
+    /// Gets the corrections applied to the input at this error to recover from it, in order
+    fn get_corrections(&self) -> &[LexicalCorrection] {
+        &self.corrections
+    }
 }
 
 impl ParseErrorIncorrectEncodingSequence {
@@ -175,9 +262,19 @@ impl ParseErrorIncorrectEncodingSequence {
         ParseErrorIncorrectEncodingSequence {
             position,
             missing_high,
-            sequence
+            sequence,
+            corrections: Vec::new()
         }
     }
+
+    /// Sets the corrections applied to recover from this error
+    pub fn with_corrections(
+        mut self,
+        corrections: Vec<LexicalCorrection>
+    ) -> ParseErrorIncorrectEncodingSequence {
+        self.corrections = corrections;
+        self
+    }
 }
 
 /// Represents an unexpected token error in a parser
@@ -218,7 +315,7 @@ impl ParseErrorDataTrait for ParseErrorUnexpectedToken {
                 if i != 0 {
                     result.push_str(", ");
                 }
-                result.push_str(x.name);
+                result.push_str(&x.name);
             }
         }
         result
@@ -244,6 +341,56 @@ impl ParseErrorUnexpectedToken {
     }
 }
 
+/// Represents an inconsistent indentation error in the input of an indentation-sensitive lexer
+#[derive(Copy, Clone)]
+pub struct ParseErrorInconsistentIndentation {
+    /// The error's position in the input text
+    position: TextPosition,
+    /// The error's length in the input, i.e. the length of the indentation
+    length: usize,
+    /// The width of the found indentation
+    found: usize,
+    /// The width of the enclosing indentation level
+    expected: usize
+}
+
+impl ParseErrorDataTrait for ParseErrorInconsistentIndentation {
+    /// Gets the error's position in the input
+    fn get_position(&self) -> TextPosition {
+        self.position
+    }
+
+    /// Gets the error's length in the input (in number of characters)
+    fn get_length(&self) -> usize {
+        self.length
+    }
+
+    /// Gets the error's message
+    fn get_message(&self) -> String {
+        format!(
+            "Inconsistent indentation: found width {} that does not match the enclosing level of width {}",
+            self.found, self.expected
+        )
+    }
+}
+
+impl ParseErrorInconsistentIndentation {
+    /// Initializes this error
+    pub fn new(
+        position: TextPosition,
+        length: usize,
+        found: usize,
+        expected: usize
+    ) -> ParseErrorInconsistentIndentation {
+        ParseErrorInconsistentIndentation {
+            position,
+            length,
+            found,
+            expected
+        }
+    }
+}
+
 /// Represents a lexical or syntactic error
 #[derive(Clone)]
 pub enum ParseError {
@@ -256,40 +403,57 @@ pub enum ParseError {
     /// Lexical error occurring when the low surrogate encoding point is missing in a UTF-16 encoding sequence with an expected high and low surrogate pair
     IncorrectUTF16NoLowSurrogate(ParseErrorIncorrectEncodingSequence),
     /// Lexical error occurring when the high surrogate encoding point is missing in a UTF-16 encoding sequence with an expected high and low surrogate pair
-    IncorrectUTF16NoHighSurrogate(ParseErrorIncorrectEncodingSequence)
+    IncorrectUTF16NoHighSurrogate(ParseErrorIncorrectEncodingSequence),
+    /// Lexical error occurring when the indentation of a line does not match any enclosing indentation level
+    InconsistentIndentation(ParseErrorInconsistentIndentation)
 }
 
 impl ParseErrorDataTrait for ParseError {
     /// Gets the error's position in the input
     fn get_position(&self) -> TextPosition {
-        match self {
-            &ParseError::UnexpectedEndOfInput(ref x) => x.get_position(),
-            &ParseError::UnexpectedChar(ref x) => x.get_position(),
-            &ParseError::UnexpectedToken(ref x) => x.get_position(),
-            &ParseError::IncorrectUTF16NoLowSurrogate(ref x) => x.get_position(),
-            &ParseError::IncorrectUTF16NoHighSurrogate(ref x) => x.get_position()
+        match *self {
+            ParseError::UnexpectedEndOfInput(ref x) => x.get_position(),
+            ParseError::UnexpectedChar(ref x) => x.get_position(),
+            ParseError::UnexpectedToken(ref x) => x.get_position(),
+            ParseError::IncorrectUTF16NoLowSurrogate(ref x) => x.get_position(),
+            ParseError::IncorrectUTF16NoHighSurrogate(ref x) => x.get_position(),
+            ParseError::InconsistentIndentation(ref x) => x.get_position()
         }
     }
 
     /// Gets the error's length in the input (in number of characters)
     fn get_length(&self) -> usize {
-        match self {
-            &ParseError::UnexpectedEndOfInput(ref x) => x.get_length(),
-            &ParseError::UnexpectedChar(ref x) => x.get_length(),
-            &ParseError::UnexpectedToken(ref x) => x.get_length(),
-            &ParseError::IncorrectUTF16NoLowSurrogate(ref x) => x.get_length(),
-            &ParseError::IncorrectUTF16NoHighSurrogate(ref x) => x.get_length()
+        match *self {
+            ParseError::UnexpectedEndOfInput(ref x) => x.get_length(),
+            ParseError::UnexpectedChar(ref x) => x.get_length(),
+            ParseError::UnexpectedToken(ref x) => x.get_length(),
+            ParseError::IncorrectUTF16NoLowSurrogate(ref x) => x.get_length(),
+            ParseError::IncorrectUTF16NoHighSurrogate(ref x) => x.get_length(),
+            ParseError::InconsistentIndentation(ref x) => x.get_length()
         }
     }
 
     /// Gets the error's message
     fn get_message(&self) -> String {
-        match self {
-            &ParseError::UnexpectedEndOfInput(ref x) => x.get_message(),
-            &ParseError::UnexpectedChar(ref x) => x.get_message(),
-            &ParseError::UnexpectedToken(ref x) => x.get_message(),
-            &ParseError::IncorrectUTF16NoLowSurrogate(ref x) => x.get_message(),
-            &ParseError::IncorrectUTF16NoHighSurrogate(ref x) => x.get_message()
+        match *self {
+            ParseError::UnexpectedEndOfInput(ref x) => x.get_message(),
+            ParseError::UnexpectedChar(ref x) => x.get_message(),
+            ParseError::UnexpectedToken(ref x) => x.get_message(),
+            ParseError::IncorrectUTF16NoLowSurrogate(ref x) => x.get_message(),
+            ParseError::IncorrectUTF16NoHighSurrogate(ref x) => x.get_message(),
+            ParseError::InconsistentIndentation(ref x) => x.get_message()
+        }
+    }
+
+    /// Gets the corrections applied to the input at this error to recover from it, in order
+    fn get_corrections(&self) -> &[LexicalCorrection] {
+        match *self {
+            ParseError::UnexpectedEndOfInput(ref x) => x.get_corrections(),
+            ParseError::UnexpectedChar(ref x) => x.get_corrections(),
+            ParseError::UnexpectedToken(ref x) => x.get_corrections(),
+            ParseError::IncorrectUTF16NoLowSurrogate(ref x) => x.get_corrections(),
+            ParseError::IncorrectUTF16NoHighSurrogate(ref x) => x.get_corrections(),
+            ParseError::InconsistentIndentation(ref x) => x.get_corrections()
         }
     }
 }
@@ -314,6 +478,11 @@ impl ParseErrors {
         }
     }
 
+    /// Removes all the errors, keeping the allocated storage
+    pub fn clear(&mut self) {
+        self.errors.clear();
+    }
+
     /// Handles the end-of-input error
     pub fn push_error_eoi(&mut self, error: ParseErrorEndOfInput) {
         self.errors.push(ParseError::UnexpectedEndOfInput(error));
@@ -347,6 +516,14 @@ impl ParseErrors {
             .push(ParseError::IncorrectUTF16NoHighSurrogate(error));
     }
 
+    /// Handles the inconsistent indentation error
+    pub fn push_error_inconsistent_indentation(
+        &mut self,
+        error: ParseErrorInconsistentIndentation
+    ) {
+        self.errors.push(ParseError::InconsistentIndentation(error));
+    }
+
     /// Gets the number of errors
     pub fn get_count(&self) -> usize {
         self.errors.len()
@@ -394,3 +571,310 @@ impl<'a> Iterable<'a> for ParseErrors {
         }
     }
 }
+
+/// Represents an error in the binary data of a lexer's or a parser's automaton
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum AutomatonError {
+    /// The data ends before the end of a table
+    TruncatedTable {
+        /// The name of the truncated table
+        table: &'static str,
+        /// The offset of the table in the data
+        offset: usize,
+        /// The number of bytes required by the table
+        required: usize,
+        /// The number of bytes available in the data
+        available: usize
+    },
+    /// The data has a header for a version of the binary format that is not supported
+    UnsupportedVersion {
+        /// The version of the data
+        version: u16
+    },
+    /// The data has a header for another kind of automaton
+    WrongKind {
+        /// The expected kind
+        expected: AutomatonKind,
+        /// The kind in the header
+        found: u16
+    },
+    /// The checksum of the data does not match the one in its header
+    ChecksumMismatch {
+        /// The checksum in the header
+        expected: u32,
+        /// The checksum of the data
+        found: u32
+    },
+    /// The number of states in the data does not match the one in its header
+    StatesCountMismatch {
+        /// The number of states in the header
+        expected: u32,
+        /// The number of states in the data
+        found: u32
+    },
+    /// The data goes on after the last table
+    TrailingData {
+        /// The offset of the first byte after the last table
+        offset: usize,
+        /// The number of trailing bytes
+        length: usize
+    },
+    /// A state or a transition refers to a state outside of the automaton
+    StateOutOfRange {
+        /// The referring state
+        from: u32,
+        /// The referred state
+        state: u32,
+        /// The number of states in the automaton
+        count: usize
+    },
+    /// An action in a parser's table has an unknown code
+    UnknownActionCode {
+        /// The state of the action
+        state: u32,
+        /// The column of the action
+        column: usize,
+        /// The unknown code
+        code: u16
+    },
+    /// A reduction in a parser's table refers to a production outside of the automaton
+    ProductionOutOfRange {
+        /// The state of the reduction
+        state: u32,
+        /// The column of the reduction
+        column: usize,
+        /// The referred production
+        production: u16,
+        /// The number of productions in the automaton
+        count: usize
+    },
+    /// An op-code of a production's bytecode is unknown
+    UnknownOpCode {
+        /// The index of the production
+        production: usize,
+        /// The offset of the op-code in the production's bytecode
+        offset: usize,
+        /// The unknown op-code
+        op_code: u16
+    },
+    /// An op-code of a production's bytecode expects an argument past the end of the bytecode
+    BytecodeOverrun {
+        /// The index of the production
+        production: usize,
+        /// The offset of the op-code in the production's bytecode
+        offset: usize
+    },
+    /// A column of a parser's table is for an unknown symbol
+    UnknownSymbol {
+        /// The column in the table
+        column: usize,
+        /// The unknown symbol identifier
+        identifier: u16
+    },
+    /// The data refers to a terminal outside of the table of terminals
+    TerminalOutOfRange {
+        /// The referring state
+        state: u32,
+        /// The referred terminal index
+        terminal: usize,
+        /// The number of terminals
+        count: usize
+    },
+    /// The data refers to a variable outside of the table of variables
+    VariableOutOfRange {
+        /// The referring production, or `None` for the axiom
+        production: Option<usize>,
+        /// The referred variable index
+        variable: usize,
+        /// The number of variables
+        count: usize
+    },
+    /// The nullable production of a variable is outside of the automaton
+    NullableOutOfRange {
+        /// The nullable variable
+        variable: usize,
+        /// The referred production
+        production: u16,
+        /// The number of productions in the automaton
+        count: usize
+    },
+    /// A production refers to a virtual outside of the table of virtuals
+    VirtualOutOfRange {
+        /// The referring production
+        production: usize,
+        /// The referred virtual index
+        virtual_index: usize,
+        /// The number of virtuals
+        count: usize
+    }
+}
+
+impl Display for AutomatonError {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        match *self {
+            AutomatonError::TruncatedTable {
+                table,
+                offset,
+                required,
+                available
+            } => write!(
+                f,
+                "Truncated table {} at offset {}: {} bytes required, {} available",
+                table, offset, required, available
+            ),
+            AutomatonError::UnsupportedVersion { version } => {
+                write!(f, "Unsupported version {} of the binary format", version)
+            }
+            AutomatonError::WrongKind { expected, found } => {
+                write!(f, "Expected a {} automaton, found kind {}", expected, found)
+            }
+            AutomatonError::ChecksumMismatch { expected, found } => write!(
+                f,
+                "Checksum mismatch, expected {:08X}, found {:08X}",
+                expected, found
+            ),
+            AutomatonError::StatesCountMismatch { expected, found } => write!(
+                f,
+                "Expected {} states as in the header, found {}",
+                expected, found
+            ),
+            AutomatonError::TrailingData { offset, length } => {
+                write!(f, "{} trailing bytes at offset {}", length, offset)
+            }
+            AutomatonError::StateOutOfRange { from, state, count } => write!(
+                f,
+                "State {} refers to state {} out of {} states",
+                from, state, count
+            ),
+            AutomatonError::UnknownActionCode {
+                state,
+                column,
+                code
+            } => write!(
+                f,
+                "Unknown action code {} in state {} for column {}",
+                code, state, column
+            ),
+            AutomatonError::ProductionOutOfRange {
+                state,
+                column,
+                production,
+                count
+            } => write!(
+                f,
+                "State {} for column {} refers to production {} out of {} productions",
+                state, column, production, count
+            ),
+            AutomatonError::UnknownOpCode {
+                production,
+                offset,
+                op_code
+            } => write!(
+                f,
+                "Unknown op-code {} at {} in production {}",
+                op_code, offset, production
+            ),
+            AutomatonError::BytecodeOverrun { production, offset } => write!(
+                f,
+                "Op-code at {} in production {} overruns the bytecode",
+                offset, production
+            ),
+            AutomatonError::UnknownSymbol { column, identifier } => {
+                write!(f, "Column {} is for unknown symbol {}", column, identifier)
+            }
+            AutomatonError::TerminalOutOfRange {
+                state,
+                terminal,
+                count
+            } => write!(
+                f,
+                "State {} refers to terminal {} out of {} terminals",
+                state, terminal, count
+            ),
+            AutomatonError::VariableOutOfRange {
+                production,
+                variable,
+                count
+            } => match production {
+                Some(production) => write!(
+                    f,
+                    "Production {} refers to variable {} out of {} variables",
+                    production, variable, count
+                ),
+                None => write!(
+                    f,
+                    "Axiom is variable {} out of {} variables",
+                    variable, count
+                )
+            },
+            AutomatonError::NullableOutOfRange {
+                variable,
+                production,
+                count
+            } => write!(
+                f,
+                "Nullable variable {} refers to production {} out of {} productions",
+                variable, production, count
+            ),
+            AutomatonError::VirtualOutOfRange {
+                production,
+                virtual_index,
+                count
+            } => write!(
+                f,
+                "Production {} refers to virtual {} out of {} virtuals",
+                production, virtual_index, count
+            )
+        }
+    }
+}
+
+/// Represents an error when a token refers to a terminal that is unknown to the lexer
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct UnknownTerminalError {
+    /// The identifier of the unknown terminal
+    pub terminal_id: u32
+}
+
+impl Display for UnknownTerminalError {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        write!(f, "Unknown terminal 0x{:04X}", self.terminal_id)
+    }
+}
+
+/// Represents an error when loading a parser at runtime
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum LoaderError {
+    /// The binary data of an automaton is invalid
+    Automaton(AutomatonError),
+    /// A line of the symbol file is invalid
+    Symbols {
+        /// The number of the invalid line (starting at 1)
+        line: usize,
+        /// The message describing the error
+        message: String
+    },
+    /// The table of terminals does not start with the ε and $ terminals
+    /// The lexers rely on the terminals at index 0 and 1 being ε and $.
+    Terminals
+}
+
+impl From<AutomatonError> for LoaderError {
+    fn from(error: AutomatonError) -> LoaderError {
+        LoaderError::Automaton(error)
+    }
+}
+
+impl Display for LoaderError {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        match *self {
+            LoaderError::Automaton(ref error) => write!(f, "Invalid automaton: {}", error),
+            LoaderError::Symbols { line, ref message } => {
+                write!(f, "Invalid symbol file at line {}: {}", line, message)
+            }
+            LoaderError::Terminals => {
+                write!(f, "Invalid terminals: the first terminals must be ε and $")
+            }
+        }
+    }
+}
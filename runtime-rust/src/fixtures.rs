@@ -0,0 +1,82 @@
+/*******************************************************************************
+ * Copyright (c) 2017 Association Cénotélie (cenotelie.fr)
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Lesser General Public License as
+ * published by the Free Software Foundation, either version 3
+ * of the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General
+ * Public License along with this program.
+ * If not, see <http://www.gnu.org/licenses/>.
+ ******************************************************************************/
+
+//! Fixtures shared by the tests of the runtime
+
+use super::errors::ParseErrors;
+use super::lexers::automaton::Automaton;
+use super::lexers::impls::ContextFreeLexer;
+use super::lexers::impls::ContextSensitiveLexer;
+use super::lexers::run_lexer;
+use super::loader::GrammarSymbols;
+use super::result::TokenizeResult;
+use super::text::Text;
+use super::tokens::TokenRepository;
+
+/// The lexer for Hime grammars, as built by the SDK
+pub struct HimeGrammarLexer {
+    /// The symbols of the grammar
+    pub symbols: GrammarSymbols,
+    /// The automaton of the lexer
+    pub automaton: Automaton
+}
+
+impl HimeGrammarLexer {
+    /// Loads the lexer for Hime grammars
+    pub fn load() -> HimeGrammarLexer {
+        HimeGrammarLexer {
+            symbols: GrammarSymbols::parse(include_str!(
+                "../../sdk-net/Sources/Input/HimeGrammarSymbols.txt"
+            ))
+            .unwrap(),
+            automaton: Automaton::new(include_bytes!(
+                "../../sdk-net/Sources/Input/HimeGrammarLexer.bin"
+            ))
+        }
+    }
+
+    /// Creates an empty result for the tokenization of the specified input
+    pub fn new_result(&self, input: &str) -> TokenizeResult {
+        TokenizeResult::new(self.symbols.terminals.clone(), Text::new(input))
+    }
+
+    /// Creates a context-free lexer filling the specified lexing data of a result
+    pub fn new_lexer<'a>(
+        &'a self,
+        data: (TokenRepository<'a>, &'a mut ParseErrors)
+    ) -> ContextFreeLexer<'a> {
+        ContextFreeLexer::new(data.0, data.1, &self.automaton, self.symbols.separator)
+    }
+
+    /// Creates a context-sensitive lexer filling the specified lexing data of a result
+    pub fn new_context_sensitive_lexer<'a>(
+        &'a self,
+        data: (TokenRepository<'a>, &'a mut ParseErrors)
+    ) -> ContextSensitiveLexer<'a> {
+        ContextSensitiveLexer::new(data.0, data.1, &self.automaton, self.symbols.separator)
+    }
+
+    /// Tokenizes the specified input with a context-free lexer
+    pub fn tokenize(&self, input: &str) -> TokenizeResult {
+        let mut result = self.new_result(input);
+        {
+            let mut lexer = self.new_lexer(result.get_lexing_data());
+            run_lexer(&mut lexer);
+        }
+        result
+    }
+}
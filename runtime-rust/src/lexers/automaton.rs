@@ -17,9 +17,17 @@
 
 //! Module for lexers' automata
 
+use std::borrow::Cow;
+use std::io::Error;
+use std::io::Write;
+
+use super::super::errors::AutomatonError;
+use super::super::symbols::Symbol;
 use super::super::text::Text;
 use super::super::text::Utf16C;
 use super::super::utils::bin::*;
+use super::super::utils::escape_dot;
+use super::DEFAULT_CONTEXT;
 
 /// Identifier of an invalid state in an automaton
 pub const DEAD_STATE: u32 = 0xFFFF;
@@ -70,7 +78,7 @@ impl AutomatonTransition {
 #[derive(Copy, Clone)]
 pub struct AutomatonState<'a> {
     /// The automaton table
-    table: &'a [u16],
+    table: TableU16<'a>,
     /// The offset of this state within the table
     offset: usize
 }
@@ -78,39 +86,41 @@ pub struct AutomatonState<'a> {
 impl<'a> AutomatonState<'a> {
     /// Gets the number of matched terminals in this state
     pub fn get_terminals_count(&self) -> usize {
-        self.table[self.offset] as usize
+        self.table.get(self.offset) as usize
     }
 
     /// Gets the i-th matched terminal in this state
     pub fn get_terminal(&self, index: usize) -> MatchedTerminal {
         MatchedTerminal {
-            context: self.table[self.offset + index * 2 + 3],
-            index: self.table[self.offset + index * 2 + 4]
+            context: self.table.get(self.offset + index * 2 + 3),
+            index: self.table.get(self.offset + index * 2 + 4)
         }
     }
 
     /// Gets whether this state is a dead end (no more transition)
     pub fn is_dead_end(&self) -> bool {
-        self.table[self.offset + 1] == 0
+        self.table.get(self.offset + 1) == 0
     }
 
     /// Gets the number of non-cached transitions in this state
     pub fn get_bulk_transitions_count(&self) -> usize {
-        self.table[self.offset + 2] as usize
+        self.table.get(self.offset + 2) as usize
     }
 
     /// Gets the target of the cached transition for the specified value
     pub fn get_cached_transition(&self, value: Utf16C) -> u32 {
-        self.table[self.offset + 3 + self.table[self.offset] as usize * 2 + value as usize] as u32
+        self.table
+            .get(self.offset + 3 + self.table.get(self.offset) as usize * 2 + value as usize)
+            as u32
     }
 
     /// Gets the i-th non-cached transition in this state
     pub fn get_bulk_transition(&self, index: usize) -> AutomatonTransition {
-        let offset = self.offset + 3 + self.table[self.offset] as usize * 2 + 256 + index * 3;
+        let offset = self.offset + 3 + self.table.get(self.offset) as usize * 2 + 256 + index * 3;
         AutomatonTransition {
-            start: self.table[offset],
-            end: self.table[offset + 1],
-            target: self.table[offset + 2] as u32
+            start: self.table.get(offset),
+            end: self.table.get(offset + 1),
+            target: self.table.get(offset + 2) as u32
         }
     }
 
@@ -136,27 +146,145 @@ impl<'a> AutomatonState<'a> {
 /// each entry is of the form:
 /// u32: offset of the state from the beginning of the states table in number of u16
 /// -- states table
+/// The tables are read in place from the binary data.
 pub struct Automaton {
-    /// Table of indices in the states table
-    table: Vec<u32>,
-    /// Lexer's DFA table of states
-    states: Vec<u16>,
+    /// The binary data of the automaton, without header
+    data: Cow<'static, [u8]>,
     /// The number of states in the automaton
     states_count: usize
 }
 
 impl Automaton {
     /// Initializes a new automaton from the given binary data
+    /// The data is trusted as is, use `try_new` for data from untrusted sources.
+    /// The header of the data, if any, is verified.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the header of the data does not match the data.
     pub fn new(data: &[u8]) -> Automaton {
+        let automaton = strip_header(data, AutomatonKind::Lexer)
+            .and_then(|(header, data)| Automaton::load(Cow::Owned(data.to_vec()), header));
+        match automaton {
+            Ok(automaton) => automaton,
+            Err(error) => panic!("{}", error)
+        }
+    }
+
+    /// Initializes a new automaton that reads its tables in place from the given static data
+    /// This runs in constant time, so the data is trusted as is and the checksum of its header, if any, is not verified.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the header of the data does not match the data.
+    pub fn from_static(data: &'static [u8]) -> Automaton {
+        let automaton = split_header(data, AutomatonKind::Lexer)
+            .and_then(|(header, data)| Automaton::load(Cow::Borrowed(data), header));
+        match automaton {
+            Ok(automaton) => automaton,
+            Err(error) => panic!("{}", error)
+        }
+    }
+
+    /// Loads an automaton from the given binary data, without a header
+    fn load(
+        data: Cow<'static, [u8]>,
+        header: Option<AutomatonHeader>
+    ) -> Result<Automaton, AutomatonError> {
+        let states_count = read_u32(&data, 0) as usize;
+        check_states_count(header, states_count)?;
+        Ok(Automaton { data, states_count })
+    }
+
+    /// Initializes a new automaton from the given binary data, checked against the lexer's terminals
+    pub fn try_new(data: &[u8], terminals: &[Symbol]) -> Result<Automaton, AutomatonError> {
+        let (header, data) = strip_header(data, AutomatonKind::Lexer)?;
+        check_table(data, "lexer counters", 0, 4)?;
         let states_count = read_u32(data, 0) as usize;
-        let table = read_table_u32(data, 4, states_count);
-        let rest = (data.len() - 4 - states_count * 4) / 2;
-        let states = read_table_u16(data, 4 + states_count * 4, rest);
-        Automaton {
-            table,
-            states,
-            states_count
+        let index_length = check_array(data, "lexer states index", 4, states_count, 4)?;
+        let rest = data.len() - 4 - index_length;
+        if rest % 2 == 1 {
+            return Err(AutomatonError::TruncatedTable {
+                table: "lexer states",
+                offset: data.len() - 1,
+                required: 2,
+                available: 1
+            });
+        }
+        if states_count == 0 {
+            // the initial state is required
+            return Err(AutomatonError::StateOutOfRange {
+                from: 0,
+                state: 0,
+                count: 0
+            });
+        }
+        let automaton = Automaton::load(Cow::Owned(data.to_vec()), header)?;
+        for state in 0..states_count {
+            automaton.check_state(state as u32, terminals.len())?;
+        }
+        Ok(automaton)
+    }
+
+    /// Checks the data of the specified state
+    fn check_state(&self, state: u32, terminals_count: usize) -> Result<(), AutomatonError> {
+        let offset = self.get_state_offset(state);
+        let available = self.get_states().len().saturating_sub(offset);
+        let truncated = |required: usize| AutomatonError::TruncatedTable {
+            table: "lexer state",
+            offset: 4 + self.states_count * 4 + offset * 2,
+            required: required * 2,
+            available: available * 2
+        };
+        if available < 3 {
+            return Err(truncated(3));
+        }
+        let state_data = self.get_state(state);
+        let required = 3
+            + state_data.get_terminals_count() * 2
+            + 256
+            + state_data.get_bulk_transitions_count() * 3;
+        if available < required {
+            return Err(truncated(required));
         }
+        for i in 0..state_data.get_terminals_count() {
+            let terminal = state_data.get_terminal(i).index as usize;
+            if terminal >= terminals_count {
+                return Err(AutomatonError::TerminalOutOfRange {
+                    state,
+                    terminal,
+                    count: terminals_count
+                });
+            }
+        }
+        let targets = (0..256).map(|i| state_data.get_cached_transition(i)).chain(
+            (0..state_data.get_bulk_transitions_count())
+                .map(|i| state_data.get_bulk_transition(i).target)
+        );
+        for target in targets {
+            if target != DEAD_STATE && target as usize >= self.states_count {
+                return Err(AutomatonError::StateOutOfRange {
+                    from: state,
+                    state: target,
+                    count: self.states_count
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes this automaton in its binary form, without a header
+    pub fn write(&self) -> Vec<u8> {
+        self.data.to_vec()
+    }
+
+    /// Writes this automaton in its binary form, with a header
+    pub fn write_with_header(&self) -> Vec<u8> {
+        write_header(
+            &self.write(),
+            AutomatonKind::Lexer,
+            self.states_count as u32
+        )
     }
 
     /// Gets the number of states in the automaton
@@ -164,12 +292,175 @@ impl Automaton {
         self.states_count
     }
 
+    /// Gets the offset of the specified state in the states table
+    fn get_state_offset(&self, state: u32) -> usize {
+        read_u32(&self.data, 4 + state as usize * 4) as usize
+    }
+
+    /// Gets the table of states
+    fn get_states(&self) -> TableU16<'_> {
+        let start = 4 + self.states_count * 4;
+        TableU16::new(&self.data, start, (self.data.len() - start) / 2)
+    }
+
     /// Get the data of the specified state
     pub fn get_state(&self, state: u32) -> AutomatonState {
         AutomatonState {
-            table: &self.states,
-            offset: self.table[state as usize] as usize
+            table: self.get_states(),
+            offset: self.get_state_offset(state)
+        }
+    }
+}
+
+/// Gets the readable representation of a character in a dump
+fn get_char_name(c: Utf16C) -> String {
+    if c > 0x20 && c < 0x7F && c != '"' as Utf16C && c != '\\' as Utf16C {
+        format!("'{}'", c as u8 as char)
+    } else {
+        format!("U+{:04X}", c)
+    }
+}
+
+/// Gets the readable representation of a range of characters in a dump
+fn get_range_name(start: Utf16C, end: Utf16C) -> String {
+    if start == end {
+        get_char_name(start)
+    } else {
+        format!("{}..{}", get_char_name(start), get_char_name(end))
+    }
+}
+
+impl<'a> AutomatonState<'a> {
+    /// Gets the transitions of this state, as ranges of characters with their target
+    /// The cached transitions are grouped into ranges of consecutive characters with the same target.
+    pub fn get_transitions(&self) -> Vec<AutomatonTransition> {
+        let mut result = Vec::<AutomatonTransition>::new();
+        for i in 0..256 {
+            let target = self.get_cached_transition(i);
+            if target == DEAD_STATE {
+                continue;
+            }
+            let extended = match result.last_mut() {
+                Some(ref mut last) if last.target == target && last.end + 1 == i => {
+                    last.end = i;
+                    true
+                }
+                _ => false
+            };
+            if !extended {
+                result.push(AutomatonTransition {
+                    start: i,
+                    end: i,
+                    target
+                });
+            }
+        }
+        for i in 0..self.get_bulk_transitions_count() {
+            result.push(self.get_bulk_transition(i));
         }
+        result
+    }
+}
+
+impl Automaton {
+    /// Dumps this automaton in a human-readable text form
+    /// The terminals are those of the lexer, as given by the generated `TERMINALS` table.
+    pub fn dump_text(&self, output: &mut dyn Write, terminals: &[Symbol]) -> Result<(), Error> {
+        for state in 0..self.states_count {
+            let state_data = self.get_state(state as u32);
+            writeln!(output, "State {}", state)?;
+            for i in 0..state_data.get_terminals_count() {
+                let matched = state_data.get_terminal(i);
+                writeln!(
+                    output,
+                    "    matches {} in context {}",
+                    get_terminal_name(terminals, matched.index as usize),
+                    matched.context
+                )?;
+            }
+            for i in 0..256 {
+                let target = state_data.get_cached_transition(i);
+                if target != DEAD_STATE {
+                    writeln!(output, "    cached {} -> {}", get_char_name(i), target)?;
+                }
+            }
+            for i in 0..state_data.get_bulk_transitions_count() {
+                let transition = state_data.get_bulk_transition(i);
+                writeln!(
+                    output,
+                    "    bulk {} -> {}",
+                    get_range_name(transition.start, transition.end),
+                    transition.target
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Dumps this automaton in the GraphViz DOT format
+    /// The terminals are those of the lexer, as given by the generated `TERMINALS` table.
+    /// The terminals matched outside of the default context are labelled with their context.
+    pub fn dump_dot(&self, output: &mut dyn Write, terminals: &[Symbol]) -> Result<(), Error> {
+        writeln!(output, "digraph lexer {{")?;
+        writeln!(output, "    rankdir=LR;")?;
+        for state in 0..self.states_count {
+            let state_data = self.get_state(state as u32);
+            if state_data.get_terminals_count() == 0 {
+                writeln!(
+                    output,
+                    "    s{} [shape=circle, label=\"{}\"];",
+                    state, state
+                )?;
+            } else {
+                let names: Vec<String> = (0..state_data.get_terminals_count())
+                    .map(|i| {
+                        let matched = state_data.get_terminal(i);
+                        let name = get_terminal_name(terminals, matched.index as usize);
+                        if matched.context == DEFAULT_CONTEXT {
+                            name
+                        } else {
+                            format!("{} in context {}", name, matched.context)
+                        }
+                    })
+                    .collect();
+                writeln!(
+                    output,
+                    "    s{} [shape=doublecircle, label=\"{}\\n{}\"];",
+                    state,
+                    state,
+                    escape_dot(&names.join(", "))
+                )?;
+            }
+        }
+        for state in 0..self.states_count {
+            let transitions = self.get_state(state as u32).get_transitions();
+            let mut targets: Vec<u32> = transitions.iter().map(|x| x.target).collect();
+            targets.sort();
+            targets.dedup();
+            for target in targets {
+                let ranges: Vec<String> = transitions
+                    .iter()
+                    .filter(|x| x.target == target)
+                    .map(|x| get_range_name(x.start, x.end))
+                    .collect();
+                writeln!(
+                    output,
+                    "    s{} -> s{} [label=\"{}\"];",
+                    state,
+                    target,
+                    escape_dot(&ranges.join(", "))
+                )?;
+            }
+        }
+        writeln!(output, "}}")
+    }
+}
+
+/// Gets the name of the terminal at the specified index for a dump
+fn get_terminal_name(terminals: &[Symbol], index: usize) -> String {
+    match terminals.get(index) {
+        Some(terminal) => terminal.name.to_string(),
+        None => format!("#{}", index)
     }
 }
 
@@ -217,3 +508,164 @@ pub fn run_dfa(automaton: &Automaton, input: &Text, index: usize) -> Option<Toke
     }
     result
 }
+
+/// Builds the data of an automaton where a keyword overlaps an identifier
+/// The terminals are ε, $, NAME (0x0003), the keyword (0x0004) and a separator (0x0005).
+/// The input `a` matches both NAME in the default context and the keyword in the context 1.
+#[cfg(test)]
+pub fn build_keyword_automaton() -> Vec<u8> {
+    // the matched terminals of each state as (context, index)
+    let states: [&[(u16, u16)]; 3] = [&[], &[(1, 3), (0, 2)], &[(0, 4)]];
+    let mut table = Vec::<u16>::new();
+    let mut offsets = Vec::<u32>::new();
+    for (i, terminals) in states.iter().enumerate() {
+        offsets.push(table.len() as u32);
+        table.extend_from_slice(&[terminals.len() as u16, if i == 0 { 2 } else { 0 }, 0]);
+        for &(context, index) in terminals.iter() {
+            table.extend_from_slice(&[context, index]);
+        }
+        for c in 0..256 {
+            table.push(match (i, c as u8) {
+                (0, b'a') => 1,
+                (0, b' ') => 2,
+                _ => DEAD_STATE as u16
+            });
+        }
+    }
+    let mut data = Vec::<u8>::new();
+    data.extend_from_slice(&(states.len() as u32).to_le_bytes());
+    for offset in offsets.iter() {
+        data.extend_from_slice(&offset.to_le_bytes());
+    }
+    for value in table.iter() {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    data
+}
+
+#[test]
+fn test_automaton_checked() {
+    // a single state matching the terminal at index 1, with a transition to itself on 'a'
+    let mut data = vec![1, 0, 0, 0, 0, 0, 0, 0];
+    for value in [1u16, 1, 0, 0, 1].iter() {
+        data.extend_from_slice(&[*value as u8, (*value >> 8) as u8]);
+    }
+    for i in 0..256 {
+        let target = if i == 'a' as usize {
+            0
+        } else {
+            DEAD_STATE as u16
+        };
+        data.extend_from_slice(&[target as u8, (target >> 8) as u8]);
+    }
+    let terminals = [Symbol::new_static(1, "ε"), Symbol::new_static(2, "$")];
+    assert!(Automaton::try_new(&data, &terminals).is_ok());
+    assert_eq!(Automaton::new(&data).write(), data);
+    let mut dot = Vec::<u8>::new();
+    Automaton::new(&data)
+        .dump_dot(&mut dot, &terminals)
+        .unwrap();
+    let dot = String::from_utf8(dot).unwrap();
+    assert!(dot.contains("s0 [shape=doublecircle, label=\"0\\n$\"];"));
+    assert!(dot.contains("s0 -> s0 [label=\"'a'\"];"));
+    match Automaton::try_new(&data, &terminals[..1]) {
+        Err(AutomatonError::TerminalOutOfRange { terminal: 1, .. }) => {}
+        _ => panic!("expected a terminal out of range")
+    }
+    match Automaton::try_new(&data[..data.len() - 2], &terminals) {
+        Err(AutomatonError::TruncatedTable { .. }) => {}
+        _ => panic!("expected a truncated table")
+    }
+    // with a header
+    let mut with_header = write_header(&data, AutomatonKind::Lexer, 1);
+    assert!(Automaton::try_new(&with_header, &terminals).is_ok());
+    assert_eq!(Automaton::new(&with_header).get_states_count(), 1);
+    match Automaton::try_new(&write_header(&data, AutomatonKind::LRk, 1), &terminals) {
+        Err(AutomatonError::WrongKind { found: 2, .. }) => {}
+        _ => panic!("expected a wrong kind")
+    }
+    match Automaton::try_new(&write_header(&data, AutomatonKind::Lexer, 2), &terminals) {
+        Err(AutomatonError::StatesCountMismatch {
+            expected: 2,
+            found: 1
+        }) => {}
+        _ => panic!("expected a mismatch of the number of states")
+    }
+    let last = with_header.len() - 1;
+    with_header[last] = 1;
+    match Automaton::try_new(&with_header, &terminals) {
+        Err(AutomatonError::ChecksumMismatch { .. }) => {}
+        _ => panic!("expected a checksum mismatch")
+    }
+    data[8 + 10 + 'a' as usize * 2] = 5;
+    match Automaton::try_new(&data, &terminals) {
+        Err(AutomatonError::StateOutOfRange { state: 5, .. }) => {}
+        _ => panic!("expected a state out of range")
+    }
+}
+
+#[test]
+fn test_automaton_from_static() {
+    // a single state matching the terminal at index 1, with a transition to itself on 'a'
+    let mut data = vec![1, 0, 0, 0, 0, 0, 0, 0];
+    for value in [1u16, 1, 0, 0, 1].iter() {
+        data.extend_from_slice(&[*value as u8, (*value >> 8) as u8]);
+    }
+    for i in 0..256 {
+        let target = if i == 'a' as usize {
+            0
+        } else {
+            DEAD_STATE as u16
+        };
+        data.extend_from_slice(&[target as u8, (target >> 8) as u8]);
+    }
+    let with_header = write_header(&data, AutomatonKind::Lexer, 1);
+    let data: &'static [u8] = Box::leak(with_header.into_boxed_slice());
+    let automaton = Automaton::from_static(data);
+    assert_eq!(automaton.get_states_count(), 1);
+    assert_eq!(automaton.get_state(0).get_terminal(0).index, 1);
+    assert_eq!(automaton.get_state(0).get_target_by('a' as Utf16C), 0);
+    assert_eq!(
+        automaton.get_state(0).get_target_by('b' as Utf16C),
+        DEAD_STATE
+    );
+    assert_eq!(automaton.write_with_header(), data);
+}
+
+#[test]
+fn test_automaton_write() {
+    let data = include_bytes!("../../../sdk-net/Sources/Input/HimeGrammarLexer.bin");
+    let automaton = Automaton::new(data);
+    assert_eq!(&automaton.write()[..], &data[..]);
+    let with_header = automaton.write_with_header();
+    assert_eq!(&Automaton::new(&with_header).write()[..], &data[..]);
+    assert_eq!(
+        Automaton::from_static(data).write_with_header(),
+        with_header
+    );
+}
+
+#[test]
+fn test_automaton_dump() {
+    let terminals = [
+        Symbol::new_static(0x0001, "ε"),
+        Symbol::new_static(0x0002, "$"),
+        Symbol::new_static(0x0003, "NAME"),
+        Symbol::new_static(0x0004, "a"),
+        Symbol::new_static(0x0005, "SEPARATOR")
+    ];
+    let automaton = Automaton::new(&build_keyword_automaton());
+    let mut text = Vec::<u8>::new();
+    automaton.dump_text(&mut text, &terminals).unwrap();
+    assert_eq!(
+        String::from_utf8(text).unwrap(),
+        "State 0\n    cached U+0020 -> 2\n    cached 'a' -> 1\nState 1\n    matches a in context 1\n    matches NAME in context 0\nState 2\n    matches SEPARATOR in context 0\n"
+    );
+    // the matches outside of the default context are labelled with their context
+    let mut dot = Vec::<u8>::new();
+    automaton.dump_dot(&mut dot, &terminals).unwrap();
+    assert_eq!(
+        String::from_utf8(dot).unwrap(),
+        "digraph lexer {\n    rankdir=LR;\n    s0 [shape=circle, label=\"0\"];\n    s1 [shape=doublecircle, label=\"1\\na in context 1, NAME\"];\n    s2 [shape=doublecircle, label=\"2\\nSEPARATOR\"];\n    s0 -> s1 [label=\"'a'\"];\n    s0 -> s2 [label=\"U+0020\"];\n}\n"
+    );
+}
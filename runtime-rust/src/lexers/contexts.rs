@@ -0,0 +1,339 @@
+/*******************************************************************************
+ * Copyright (c) 2017 Association Cénotélie (cenotelie.fr)
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Lesser General Public License as
+ * published by the Free Software Foundation, either version 3
+ * of the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General
+ * Public License along with this program.
+ * If not, see <http://www.gnu.org/licenses/>.
+ ******************************************************************************/
+
+//! Module for the user-defined lexical contexts on top of the contexts provided by parsers
+
+use super::super::errors::ParseErrors;
+use super::super::symbols::Symbol;
+use super::super::text::Text;
+use super::super::tokens::TokenRepository;
+use super::ContextProvider;
+use super::Lexer;
+use super::LexicalAmbiguity;
+use super::TokenKernel;
+
+/// A user-defined layer of context information on top of the provider below it
+/// The provider below a layer is either the parser or another layer.
+/// A layer may grant, deny or re-prioritize contexts,
+/// for example depending on a symbol table filled by semantic actions.
+pub trait ContextLayer {
+    /// Gets the priority of the specified context required by the specified terminal
+    /// The priority is an unsigned integer. The lesser the value the higher the priority.
+    /// The absence of value represents the unavailability of the required context.
+    fn get_context_priority(
+        &self,
+        below: &dyn ContextProvider,
+        token_count: usize,
+        context: u16,
+        terminal_id: u32
+    ) -> Option<usize>;
+
+    /// Gets the contexts that are currently active, for debugging purposes
+    fn get_active_contexts(&self, below: &dyn ContextProvider) -> Vec<u16> {
+        below.get_active_contexts()
+    }
+}
+
+/// A context provider that composes a base provider with layers of user-defined contexts
+/// The last layer is the outermost one.
+pub struct ContextProviderChain<'c> {
+    /// The base provider, usually the parser
+    base: &'c dyn ContextProvider,
+    /// The layers on top of the base provider
+    layers: &'c [&'c dyn ContextLayer]
+}
+
+impl<'c> ContextProviderChain<'c> {
+    /// Creates a new chain of context providers
+    pub fn new(
+        base: &'c dyn ContextProvider,
+        layers: &'c [&'c dyn ContextLayer]
+    ) -> ContextProviderChain<'c> {
+        ContextProviderChain { base, layers }
+    }
+}
+
+impl<'c> ContextProvider for ContextProviderChain<'c> {
+    /// Gets the priority of the specified context required by the specified terminal
+    /// The priority is an unsigned integer. The lesser the value the higher the priority.
+    /// The absence of value represents the unavailability of the required context.
+    fn get_context_priority(
+        &self,
+        token_count: usize,
+        context: u16,
+        terminal_id: u32
+    ) -> Option<usize> {
+        match self.layers.split_last() {
+            None => self
+                .base
+                .get_context_priority(token_count, context, terminal_id),
+            Some((layer, rest)) => layer.get_context_priority(
+                &ContextProviderChain::new(self.base, rest),
+                token_count,
+                context,
+                terminal_id
+            )
+        }
+    }
+
+    /// Gets the contexts that are currently active, for debugging purposes
+    fn get_active_contexts(&self) -> Vec<u16> {
+        match self.layers.split_last() {
+            None => self.base.get_active_contexts(),
+            Some((layer, rest)) => {
+                layer.get_active_contexts(&ContextProviderChain::new(self.base, rest))
+            }
+        }
+    }
+}
+
+/// A lexer that resolves the contexts of another lexer through layers of user-defined contexts
+pub struct LayeredLexer<'a, L: Lexer<'a>> {
+    /// The lexer producing the tokens
+    lexer: L,
+    /// The layers on top of the contexts provided by the parser
+    layers: Vec<&'a dyn ContextLayer>
+}
+
+impl<'a, L: Lexer<'a>> LayeredLexer<'a, L> {
+    /// Creates a new layered lexer, without layers
+    pub fn new(lexer: L) -> LayeredLexer<'a, L> {
+        LayeredLexer {
+            lexer,
+            layers: Vec::new()
+        }
+    }
+
+    /// Adds a layer of contexts on top of the existing ones
+    pub fn add_layer(&mut self, layer: &'a dyn ContextLayer) {
+        self.layers.push(layer);
+    }
+}
+
+impl<'a, L: Lexer<'a>> Lexer<'a> for LayeredLexer<'a, L> {
+    /// Gets the terminals matched by this lexer
+    fn get_terminals(&self) -> &'a [Symbol] {
+        self.lexer.get_terminals()
+    }
+
+    /// Gets the lexer's input text
+    fn get_input(&self) -> &Text {
+        self.lexer.get_input()
+    }
+
+    /// Gets the lexer's output stream of tokens
+    fn get_output(&self) -> &TokenRepository<'a> {
+        self.lexer.get_output()
+    }
+
+    /// Gets the lexer's errors
+    fn get_errors(&mut self) -> &mut ParseErrors {
+        self.lexer.get_errors()
+    }
+
+    /// Gets the maximum Levenshtein distance to go to for the recovery of a matching failure.
+    /// A distance of 0 indicates no recovery.
+    fn get_recovery_distance(&self) -> usize {
+        self.lexer.get_recovery_distance()
+    }
+
+    /// Sets the maximum Levenshtein distance to go to for the recovery of a matching failure.
+    /// A distance of 0 indicates no recovery.
+    fn set_recovery_distance(&mut self, distance: usize) {
+        self.lexer.set_recovery_distance(distance);
+    }
+
+    /// Gets the recorded lexical ambiguities
+    fn get_ambiguities(&self) -> &[LexicalAmbiguity] {
+        self.get_output().get_ambiguities()
+    }
+
+    /// Gets the lexer wrapped by this one, if any
+    fn get_wrapped(&self) -> Option<&dyn Lexer<'a>> {
+        Some(&self.lexer)
+    }
+
+    /// Gets the lexer wrapped by this one, if any, for modification
+    fn get_wrapped_mut(&mut self) -> Option<&mut dyn Lexer<'a>> {
+        Some(&mut self.lexer)
+    }
+
+    /// Gets the next token in the input
+    fn get_next_token(&mut self, contexts: &dyn ContextProvider) -> Option<TokenKernel> {
+        let chain = ContextProviderChain::new(contexts, &self.layers);
+        self.lexer.get_next_token(&chain)
+    }
+}
+
+/// A provider of contexts where each context has its own value as priority
+#[cfg(test)]
+struct IdentityProvider {}
+
+#[cfg(test)]
+impl ContextProvider for IdentityProvider {
+    fn get_context_priority(
+        &self,
+        _token_count: usize,
+        context: u16,
+        _terminal_id: u32
+    ) -> Option<usize> {
+        Some(context as usize)
+    }
+
+    fn get_active_contexts(&self) -> Vec<u16> {
+        vec![0]
+    }
+}
+
+/// A layer that denies the contexts for a terminal
+#[cfg(test)]
+struct DenyLayer {
+    terminal_id: u32
+}
+
+#[cfg(test)]
+impl ContextLayer for DenyLayer {
+    fn get_context_priority(
+        &self,
+        below: &dyn ContextProvider,
+        token_count: usize,
+        context: u16,
+        terminal_id: u32
+    ) -> Option<usize> {
+        if terminal_id == self.terminal_id {
+            return None;
+        }
+        below.get_context_priority(token_count, context, terminal_id)
+    }
+}
+
+/// A layer that grants a context with the highest priority
+#[cfg(test)]
+struct GrantLayer {
+    context: u16
+}
+
+#[cfg(test)]
+impl ContextLayer for GrantLayer {
+    fn get_context_priority(
+        &self,
+        below: &dyn ContextProvider,
+        token_count: usize,
+        context: u16,
+        terminal_id: u32
+    ) -> Option<usize> {
+        if context == self.context {
+            return Some(0);
+        }
+        below.get_context_priority(token_count, context, terminal_id)
+    }
+
+    fn get_active_contexts(&self, below: &dyn ContextProvider) -> Vec<u16> {
+        let mut result = below.get_active_contexts();
+        result.push(self.context);
+        result
+    }
+}
+
+#[test]
+fn test_context_provider_chain_no_layer() {
+    let base = IdentityProvider {};
+    let chain = ContextProviderChain::new(&base, &[]);
+    assert_eq!(chain.get_context_priority(0, 3, 0x0009), Some(3));
+    assert_eq!(chain.get_active_contexts(), vec![0]);
+}
+
+#[test]
+fn test_context_provider_chain_outermost_last() {
+    let base = IdentityProvider {};
+    let deny = DenyLayer {
+        terminal_id: 0x0009
+    };
+    let grant = GrantLayer { context: 3 };
+    // the grant is the outermost layer and answers first
+    let layers: [&dyn ContextLayer; 2] = [&deny, &grant];
+    let chain = ContextProviderChain::new(&base, &layers);
+    assert_eq!(chain.get_context_priority(0, 3, 0x0009), Some(0));
+    assert_eq!(chain.get_context_priority(0, 4, 0x0009), None);
+    assert_eq!(chain.get_context_priority(0, 4, 0x000A), Some(4));
+    // the deny is the outermost layer and answers first
+    let layers: [&dyn ContextLayer; 2] = [&grant, &deny];
+    let chain = ContextProviderChain::new(&base, &layers);
+    assert_eq!(chain.get_context_priority(0, 3, 0x0009), None);
+    assert_eq!(chain.get_context_priority(0, 3, 0x000A), Some(0));
+}
+
+#[test]
+fn test_context_provider_chain_active_contexts() {
+    let base = IdentityProvider {};
+    let deny = DenyLayer {
+        terminal_id: 0x0009
+    };
+    let grant1 = GrantLayer { context: 3 };
+    let grant2 = GrantLayer { context: 5 };
+    let layers: [&dyn ContextLayer; 3] = [&grant1, &deny, &grant2];
+    let chain = ContextProviderChain::new(&base, &layers);
+    assert_eq!(chain.get_active_contexts(), vec![0, 3, 5]);
+}
+
+#[test]
+fn test_layered_lexer() {
+    use super::super::result::TokenizeResult;
+    use super::super::symbols::SemanticElementTrait;
+    use super::super::utils::iterable::Iterable;
+    use super::automaton::build_keyword_automaton;
+    use super::automaton::Automaton;
+    use super::impls::ContextSensitiveLexer;
+    use super::run_lexer;
+    let terminals = vec![
+        Symbol::new_static(0x0001, "ε"),
+        Symbol::new_static(0x0002, "$"),
+        Symbol::new_static(0x0003, "NAME"),
+        Symbol::new_static(0x0004, "a"),
+        Symbol::new_static(0x0005, "SEPARATOR")
+    ];
+    let automaton = Automaton::new(&build_keyword_automaton());
+    let lex = |layers: &[&dyn ContextLayer]| -> Vec<u32> {
+        let mut result = TokenizeResult::new(terminals.clone(), Text::new("a a"));
+        {
+            let (repository, errors) = result.get_lexing_data();
+            let mut lexer = LayeredLexer::new(ContextSensitiveLexer::new(
+                repository, errors, &automaton, 0x0005
+            ));
+            for layer in layers.iter() {
+                lexer.add_layer(*layer);
+            }
+            run_lexer(&mut lexer);
+        }
+        assert_eq!(result.get_errors().get_count(), 0);
+        result
+            .get_tokens()
+            .iter()
+            .map(|token| token.get_symbol().id)
+            .collect()
+    };
+    // without layer, the keyword's context is available and preferred
+    assert_eq!(lex(&[]), vec![0x0004, 0x0004, 0x0002]);
+    // the keyword is denied and matched as a name
+    let deny = DenyLayer {
+        terminal_id: 0x0004
+    };
+    assert_eq!(lex(&[&deny]), vec![0x0003, 0x0003, 0x0002]);
+    // the default context is granted the highest priority on top of the deny
+    let grant = GrantLayer { context: 0 };
+    assert_eq!(lex(&[&deny, &grant]), vec![0x0003, 0x0003, 0x0002]);
+}
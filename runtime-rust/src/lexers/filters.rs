@@ -0,0 +1,268 @@
+/*******************************************************************************
+ * Copyright (c) 2017 Association Cénotélie (cenotelie.fr)
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Lesser General Public License as
+ * published by the Free Software Foundation, either version 3
+ * of the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General
+ * Public License along with this program.
+ * If not, see <http://www.gnu.org/licenses/>.
+ ******************************************************************************/
+
+//! Module for the filters that rewrite the stream of tokens between a lexer and a parser
+
+use std::collections::VecDeque;
+
+use super::super::errors::ParseErrors;
+use super::super::errors::UnknownTerminalError;
+use super::super::symbols::Symbol;
+use super::super::text::Text;
+use super::super::tokens::TokenRepository;
+use super::ContextProvider;
+use super::Lexer;
+use super::LexicalAmbiguity;
+use super::TokenKernel;
+
+/// The output of a token filter, i.e. the tokens that will be given to the parser
+pub struct TokenFilterOutput<'r, 'a: 'r> {
+    /// The repository of tokens
+    repository: &'r mut TokenRepository<'a>,
+    /// The tokens to be given to the parser, in order
+    queue: &'r mut VecDeque<TokenKernel>
+}
+
+impl<'r, 'a: 'r> TokenFilterOutput<'r, 'a> {
+    /// Gets the repository of tokens
+    pub fn get_repository(&self) -> &TokenRepository<'a> {
+        self.repository
+    }
+
+    /// Gives the specified token to the parser
+    pub fn push(&mut self, token: TokenKernel) {
+        self.queue.push_back(token);
+    }
+
+    /// Records a new synthetic token in the repository and gives it to the parser
+    /// The synthetic span of the token (index and length in the input) is free, for example:
+    /// a part of an existing token, or an empty span for an injected token.
+    /// Synthetic tokens are kept out of the span order of the tokens matched by the lexer,
+    /// so that the lookups by position in the repository (`find_token_at`, `find_tokens_in`) ignore them.
+    pub fn push_synthetic(
+        &mut self,
+        terminal_id: u32,
+        index: usize,
+        length: usize
+    ) -> Result<TokenKernel, UnknownTerminalError> {
+        let terminal = match self
+            .repository
+            .get_terminals()
+            .iter()
+            .position(|symbol| symbol.id == terminal_id)
+        {
+            None => return Err(UnknownTerminalError { terminal_id }),
+            Some(terminal) => terminal
+        };
+        let token_index = self.repository.add_synthetic(terminal, index, length);
+        let kernel = TokenKernel {
+            terminal_id,
+            index: token_index as u32
+        };
+        self.queue.push_back(kernel);
+        Ok(kernel)
+    }
+}
+
+/// A filter that rewrites the stream of tokens given by a lexer before the parser sees it
+/// For each token given by the lexer, a filter may:
+/// * give it as is to the parser by pushing it to the output,
+/// * drop it by not pushing anything,
+/// * replace it or insert new tokens by pushing synthetic tokens.
+pub trait TokenFilter {
+    /// Filters the next token given by the lexer
+    /// The last token given by a lexer is always the token for the `$` terminal.
+    fn filter(&mut self, token: TokenKernel, output: &mut TokenFilterOutput);
+}
+
+/// A lexer that rewrites the tokens of another lexer with a filter
+pub struct FilteredLexer<'a, L: Lexer<'a>> {
+    /// The lexer producing the tokens
+    lexer: L,
+    /// The filter to apply
+    filter: &'a mut dyn TokenFilter,
+    /// The tokens produced by the filter but not yet given to the parser
+    queue: VecDeque<TokenKernel>
+}
+
+impl<'a, L: Lexer<'a>> FilteredLexer<'a, L> {
+    /// Creates a new filtered lexer
+    pub fn new(lexer: L, filter: &'a mut dyn TokenFilter) -> FilteredLexer<'a, L> {
+        FilteredLexer {
+            lexer,
+            filter,
+            queue: VecDeque::new()
+        }
+    }
+}
+
+impl<'a, L: Lexer<'a>> Lexer<'a> for FilteredLexer<'a, L> {
+    /// Gets the terminals matched by this lexer
+    fn get_terminals(&self) -> &'a [Symbol] {
+        self.lexer.get_terminals()
+    }
+
+    /// Gets the lexer's input text
+    fn get_input(&self) -> &Text {
+        self.lexer.get_input()
+    }
+
+    /// Gets the lexer's output stream of tokens
+    fn get_output(&self) -> &TokenRepository<'a> {
+        self.lexer.get_output()
+    }
+
+    /// Gets the lexer's errors
+    fn get_errors(&mut self) -> &mut ParseErrors {
+        self.lexer.get_errors()
+    }
+
+    /// Gets the maximum Levenshtein distance to go to for the recovery of a matching failure.
+    /// A distance of 0 indicates no recovery.
+    fn get_recovery_distance(&self) -> usize {
+        self.lexer.get_recovery_distance()
+    }
+
+    /// Sets the maximum Levenshtein distance to go to for the recovery of a matching failure.
+    /// A distance of 0 indicates no recovery.
+    fn set_recovery_distance(&mut self, distance: usize) {
+        self.lexer.set_recovery_distance(distance);
+    }
+
+    /// Gets the recorded lexical ambiguities
+    fn get_ambiguities(&self) -> &[LexicalAmbiguity] {
+        self.get_output().get_ambiguities()
+    }
+
+    /// Gets the lexer wrapped by this one, if any
+    fn get_wrapped(&self) -> Option<&dyn Lexer<'a>> {
+        Some(&self.lexer)
+    }
+
+    /// Gets the lexer wrapped by this one, if any, for modification
+    fn get_wrapped_mut(&mut self) -> Option<&mut dyn Lexer<'a>> {
+        Some(&mut self.lexer)
+    }
+
+    /// Gets the next token in the input
+    fn get_next_token(&mut self, contexts: &dyn ContextProvider) -> Option<TokenKernel> {
+        loop {
+            if let Some(kernel) = self.queue.pop_front() {
+                return Some(kernel);
+            }
+            let token = self.lexer.get_next_token(contexts)?;
+            let mut output = TokenFilterOutput {
+                repository: self.lexer.get_output_mut(),
+                queue: &mut self.queue
+            };
+            self.filter.filter(token, &mut output);
+        }
+    }
+}
+
+#[test]
+fn test_filtered_lexer_split() {
+    use super::super::fixtures::HimeGrammarLexer;
+    use super::super::symbols::SemanticElementTrait;
+    use super::run_lexer;
+    const ID_TERMINAL_NAME: u32 = 0x0009;
+    /// Splits the names in two synthetic tokens
+    struct SplitFilter {}
+    impl TokenFilter for SplitFilter {
+        fn filter(&mut self, token: TokenKernel, output: &mut TokenFilterOutput) {
+            if token.terminal_id != ID_TERMINAL_NAME {
+                output.push(token);
+                return;
+            }
+            let span = output
+                .get_repository()
+                .get_token(token.index as usize)
+                .get_span()
+                .unwrap();
+            output
+                .push_synthetic(ID_TERMINAL_NAME, span.index, 2)
+                .unwrap();
+            output
+                .push_synthetic(ID_TERMINAL_NAME, span.index + 2, span.length - 2)
+                .unwrap();
+        }
+    }
+    let fixture = HimeGrammarLexer::load();
+    let mut result = fixture.new_result("grammar Test { }");
+    let mut filter = SplitFilter {};
+    {
+        let lexer = fixture.new_lexer(result.get_lexing_data());
+        let mut lexer = FilteredLexer::new(lexer, &mut filter);
+        let kernels = run_lexer(&mut lexer);
+        let indices: Vec<u32> = kernels.iter().map(|kernel| kernel.index).collect();
+        // the synthetic tokens are recorded after the $ token and are not replayed
+        assert_eq!(indices, vec![0, 5, 6, 2, 3, 4]);
+        assert_eq!(kernels[5].terminal_id, 0x0002);
+    }
+    let repository = result.get_tokens();
+    assert_eq!(repository.get_tokens_count(), 7);
+    assert_eq!(repository.get_token(5).get_value().unwrap(), "Te");
+    assert_eq!(repository.get_token(6).get_value().unwrap(), "st");
+    assert!(!repository.is_synthetic(1));
+    assert!(repository.is_synthetic(5));
+    // the lookups by position only consider the tokens matched by the lexer
+    assert_eq!(repository.find_token_at(10).unwrap().index, 1);
+    let found = repository.find_tokens_on_line(1);
+    assert_eq!(
+        found.iter().map(|token| token.index).collect::<Vec<_>>(),
+        vec![0, 1, 2, 3]
+    );
+}
+
+#[test]
+fn test_filtered_lexer_drop_and_unknown_terminal() {
+    use super::super::fixtures::HimeGrammarLexer;
+    use super::run_lexer;
+    const ID_TERMINAL_NAME: u32 = 0x0009;
+    /// Drops the names and tries to replace them with an unknown terminal
+    struct DropFilter {
+        errors: Vec<UnknownTerminalError>
+    }
+    impl TokenFilter for DropFilter {
+        fn filter(&mut self, token: TokenKernel, output: &mut TokenFilterOutput) {
+            if token.terminal_id != ID_TERMINAL_NAME {
+                output.push(token);
+            } else if let Err(error) = output.push_synthetic(0x7FFF, 0, 0) {
+                self.errors.push(error);
+            }
+        }
+    }
+    let fixture = HimeGrammarLexer::load();
+    let mut result = fixture.new_result("grammar Test { }");
+    let mut filter = DropFilter { errors: Vec::new() };
+    {
+        let lexer = fixture.new_lexer(result.get_lexing_data());
+        let mut lexer = FilteredLexer::new(lexer, &mut filter);
+        let indices: Vec<u32> = run_lexer(&mut lexer)
+            .iter()
+            .map(|kernel| kernel.index)
+            .collect();
+        assert_eq!(indices, vec![0, 2, 3, 4]);
+    }
+    assert_eq!(
+        filter.errors,
+        vec![UnknownTerminalError {
+            terminal_id: 0x7FFF
+        }]
+    );
+    assert_eq!(result.get_tokens().get_tokens_count(), 5);
+}
@@ -19,6 +19,8 @@
 
 use std::mem::replace;
 
+use super::super::errors::LexicalCorrection;
+use super::super::errors::LexicalCorrectionKind;
 use super::super::errors::ParseErrorEndOfInput;
 use super::super::errors::ParseErrorIncorrectEncodingSequence;
 use super::super::errors::ParseErrorUnexpectedChar;
@@ -30,20 +32,56 @@ use super::automaton::AutomatonState;
 use super::automaton::TokenMatch;
 use super::automaton::DEAD_STATE;
 
+/// The costs of the corrections a fuzzy matcher may apply to the input
+#[derive(Copy, Clone)]
+pub struct FuzzyCosts {
+    /// The cost of inserting a character
+    pub insertion: usize,
+    /// The cost of deleting a character
+    pub deletion: usize,
+    /// The cost of substituting a character by another one
+    pub substitution: usize
+}
+
+impl Default for FuzzyCosts {
+    /// Gets the default costs, i.e. the Levenshtein distance
+    fn default() -> FuzzyCosts {
+        FuzzyCosts {
+            insertion: 1,
+            deletion: 1,
+            substitution: 1
+        }
+    }
+}
+
+/// Represents a correction applied by a head
+#[derive(Copy, Clone)]
+struct FuzzyEdit {
+    /// The kind of correction
+    kind: LexicalCorrectionKind,
+    /// The offset of the correction from the original index
+    offset: u32,
+    /// The inserted or substituted character, or the deleted character
+    value: Utf16C
+}
+
 /// Represents a DFA stack head
 #[derive(Clone)]
 struct FuzzyMatcherHead {
     /// The associated DFA state
     state: u32,
-    /// The data representing this head
-    errors: Option<Vec<u32>>
+    /// The total cost of the corrections on this head
+    cost: usize,
+    /// The corrections applied on this head
+    errors: Option<Vec<FuzzyEdit>>
 }
 
 impl FuzzyMatcherHead {
-    /// Initializes this head with a state and a 0 distance
+    /// Initializes this head with a state and a 0 cost
     pub fn new(state: u32) -> FuzzyMatcherHead {
         FuzzyMatcherHead {
             state,
+            cost: 0,
             errors: None
         }
     }
@@ -52,6 +90,7 @@ impl FuzzyMatcherHead {
     pub fn new_previous(previous: &FuzzyMatcherHead, state: u32) -> FuzzyMatcherHead {
         FuzzyMatcherHead {
             state,
+            cost: previous.cost,
             errors: previous.errors.clone()
         }
     }
@@ -60,44 +99,39 @@ impl FuzzyMatcherHead {
     pub fn new_error(
         previous: &FuzzyMatcherHead,
         state: u32,
-        offset: usize,
-        distance: usize
+        edits: &[FuzzyEdit],
+        cost: usize
     ) -> FuzzyMatcherHead {
-        let mut errors = Vec::<u32>::with_capacity(distance);
-        if previous.errors.is_some() {
-            let others = previous.errors.as_ref().unwrap();
-            for i in 0..others.len() {
-                errors.push(others[i]);
-            }
-        }
-        while errors.len() < distance {
-            errors.push(offset as u32);
-        }
+        let mut errors = match previous.errors {
+            None => Vec::<FuzzyEdit>::with_capacity(edits.len()),
+            Some(ref others) => others.clone()
+        };
+        errors.extend_from_slice(edits);
         FuzzyMatcherHead {
             state,
+            cost,
             errors: Some(errors)
         }
     }
 
-    /// Gets the Levenshtein distance of this head form the input
-    pub fn get_distance(&self) -> usize {
-        if self.errors.is_none() {
-            0
-        } else {
-            self.errors.as_ref().unwrap().len()
-        }
+    /// Gets the total cost of the corrections on this head
+    pub fn get_cost(&self) -> usize {
+        self.cost
     }
 
-    /// Gets the offset in the input of the i-th lexical error on this head
-    pub fn get_error(&self, i: usize) -> u32 {
-        self.errors.as_ref().unwrap()[i]
+    /// Gets the corrections applied on this head
+    pub fn get_errors(&self) -> &[FuzzyEdit] {
+        match self.errors {
+            None => &[],
+            Some(ref errors) => errors
+        }
     }
 }
 
 /// A fuzzy DFA matcher
-/// This matcher uses the Levenshtein distance to match the input ahead against the current DFA automaton.
+/// This matcher uses a weighted edit distance to match the input ahead against the current DFA automaton.
 /// The matcher favors solutions that are the closest to the original input.
-/// When multiple solutions are at the same Levenshtein distance to the input, the longest one is preferred.
+/// When multiple solutions are at the same distance to the input, the longest one is preferred.
 pub struct FuzzyMatcher<'a> {
     /// This lexer's automaton
     automaton: &'a Automaton,
@@ -107,12 +141,23 @@ pub struct FuzzyMatcher<'a> {
     text: &'a Text,
     /// Delegate for raising errors
     errors: &'a mut ParseErrors,
-    /// The maximum Levenshtein distance between the input and the DFA
+    /// The maximum distance between the input and the DFA
     max_distance: usize,
+    /// The costs of the corrections
+    costs: FuzzyCosts,
     /// The index in the input from which the error was raised
     origin_index: usize
 }
 
+/// Represents a pending insertion of characters
+#[derive(Clone)]
+struct FuzzyInsertion {
+    /// The DFA state reached after the insertion
+    state: u32,
+    /// The inserted characters
+    values: Vec<Utf16C>
+}
+
 /// The current state of a matcher
 struct FuzzyMatcherResult {
     /// The current heads
@@ -122,7 +167,7 @@ struct FuzzyMatcherResult {
     /// The current matching length
     pub match_length: usize,
     /// The current insertions
-    pub insertions: Vec<u32>
+    pub insertions: Vec<FuzzyInsertion>
 }
 
 impl FuzzyMatcherResult {
@@ -132,16 +177,16 @@ impl FuzzyMatcherResult {
             heads: Vec::<FuzzyMatcherHead>::new(),
             match_head: None,
             match_length: 0,
-            insertions: Vec::<u32>::new()
+            insertions: Vec::<FuzzyInsertion>::new()
         }
     }
 
     /// Pushes a new head onto the the queue
     pub fn push_head(&mut self, previous: &FuzzyMatcherHead, state: u32) {
-        let distance = previous.get_distance();
-        // try to find a pre-existing head with the same state at a lesser distance
+        let cost = previous.get_cost();
+        // try to find a pre-existing head with the same state at a lesser cost
         for x in self.heads.iter().rev() {
-            if x.state == state && x.get_distance() <= distance {
+            if x.state == state && x.get_cost() <= cost {
                 return;
             }
         }
@@ -150,27 +195,21 @@ impl FuzzyMatcherResult {
     }
 
     /// Pushes a new head onto the the queue for an error's fix
-    pub fn push_head_error(&mut self, previous: &FuzzyMatcherHead, state: u32, offset: usize) {
-        self.push_head_long_error(previous, state, offset, previous.get_distance() + 1);
-    }
-
-    /// Pushes a new head onto the the queue for an error's fix
-    pub fn push_head_long_error(
+    pub fn push_head_error(
         &mut self,
         previous: &FuzzyMatcherHead,
         state: u32,
-        offset: usize,
-        distance: usize
+        edits: &[FuzzyEdit],
+        cost: usize
     ) {
-        // try to find a pre-existing head with the same state at a lesser distance
+        // try to find a pre-existing head with the same state at a lesser cost
         for x in self.heads.iter().rev() {
-            if x.state == state && x.get_distance() <= distance {
+            if x.state == state && x.get_cost() <= cost {
                 return;
             }
         }
-        self.heads.push(FuzzyMatcherHead::new_error(
-            previous, state, offset, distance,
-        ));
+        self.heads
+            .push(FuzzyMatcherHead::new_error(previous, state, edits, cost));
     }
 }
 
@@ -190,10 +229,16 @@ impl<'a> FuzzyMatcher<'a> {
             text,
             errors,
             max_distance,
+            costs: FuzzyCosts::default(),
             origin_index
         }
     }
 
+    /// Sets the costs of the corrections
+    pub fn set_costs(&mut self, costs: FuzzyCosts) {
+        self.costs = costs;
+    }
+
     /// Runs this matcher
     pub fn run(&mut self) -> Option<TokenMatch> {
         let mut offset = 0;
@@ -238,23 +283,39 @@ impl<'a> FuzzyMatcher<'a> {
 
     /// Constructs the solution when succeeded to fix the error
     fn on_success(&mut self, result: &FuzzyMatcherResult) -> Option<TokenMatch> {
+        let head = result.match_head.as_ref().unwrap();
         let mut last_error_index = result.match_length + 1;
-        for i in 0..result.match_head.as_ref().unwrap().get_distance() {
-            let error_index =
-                self.origin_index + result.match_head.as_ref().unwrap().get_error(i) as usize;
+        let mut pending: Option<(usize, Vec<LexicalCorrection>)> = None;
+        for edit in head.get_errors() {
+            let error_index = self.origin_index + edit.offset as usize;
+            let correction = LexicalCorrection {
+                kind: edit.kind,
+                position: self.text.get_position_at(error_index),
+                index: error_index,
+                value: edit.value
+            };
             if error_index != last_error_index {
-                self.on_error(error_index);
+                if let Some((index, corrections)) = pending.take() {
+                    self.on_error(index, corrections);
+                }
+                pending = Some((error_index, vec![correction]));
+            } else if let Some((_, ref mut corrections)) = pending {
+                // one error per index, with all the corrections at this index
+                corrections.push(correction);
             }
             last_error_index = error_index;
         }
+        if let Some((index, corrections)) = pending {
+            self.on_error(index, corrections);
+        }
         Some(TokenMatch {
-            state: result.match_head.as_ref().unwrap().state,
+            state: head.state,
             length: result.match_length as u32
         })
     }
 
-    /// Reports on the lexical error at the specified index
-    fn on_error(&mut self, index: usize) {
+    /// Reports on the lexical error at the specified index, recovered by the specified corrections
+    fn on_error(&mut self, index: usize, corrections: Vec<LexicalCorrection>) {
         if self.text.is_end(index) {
             // the end of input was not expected
             // there is necessarily some input before because an empty input would have matched the $
@@ -267,11 +328,14 @@ impl<'a> FuzzyMatcher<'a> {
                         false,
                         c
                     )
+                    .with_corrections(corrections)
                 );
             } else {
                 // usual unexpected end of input
-                self.errors
-                    .push_error_eoi(ParseErrorEndOfInput::new(self.text.get_position_at(index)));
+                self.errors.push_error_eoi(
+                    ParseErrorEndOfInput::new(self.text.get_position_at(index))
+                        .with_corrections(corrections)
+                );
             }
         } else {
             let c = self.text.at(index);
@@ -281,11 +345,10 @@ impl<'a> FuzzyMatcher<'a> {
                 let c2 = self.text.at(index + 1);
                 if c2 >= 0xDC00 && c2 <= 0xDFFF {
                     // an unexpected high and low surrogate pair
-                    self.errors
-                        .push_error_unexpected_char(ParseErrorUnexpectedChar::new(
-                            self.text.get_position_at(index),
-                            [c, c2]
-                        ));
+                    self.errors.push_error_unexpected_char(
+                        ParseErrorUnexpectedChar::new(self.text.get_position_at(index), [c, c2])
+                            .with_corrections(corrections)
+                    );
                 } else {
                     // high surrogate without the low surrogate
                     self.errors.push_error_no_low_utf16_surrogate(
@@ -294,6 +357,7 @@ impl<'a> FuzzyMatcher<'a> {
                             false,
                             c
                         )
+                        .with_corrections(corrections)
                     );
                 }
             } else if c >= 0xDC00 && c <= 0xDFFF && index > 0 {
@@ -302,11 +366,13 @@ impl<'a> FuzzyMatcher<'a> {
                 let c2 = self.text.at(index - 1);
                 if c2 >= 0xD800 && c2 <= 0xDBFF {
                     // an unexpected high and low surrogate pair
-                    self.errors
-                        .push_error_unexpected_char(ParseErrorUnexpectedChar::new(
+                    self.errors.push_error_unexpected_char(
+                        ParseErrorUnexpectedChar::new(
                             self.text.get_position_at(index - 1),
                             [c2, c]
-                        ));
+                        )
+                        .with_corrections(corrections)
+                    );
                 } else {
                     // a low surrogate without the high surrogate
                     self.errors.push_error_no_high_utf16_surrogate(
@@ -315,15 +381,15 @@ impl<'a> FuzzyMatcher<'a> {
                             true,
                             c
                         )
+                        .with_corrections(corrections)
                     );
                 }
             } else {
                 // a simple unexpected character
-                self.errors
-                    .push_error_unexpected_char(ParseErrorUnexpectedChar::new(
-                        self.text.get_position_at(index),
-                        [c, 0]
-                    ));
+                self.errors.push_error_unexpected_char(
+                    ParseErrorUnexpectedChar::new(self.text.get_position_at(index), [c, 0])
+                        .with_corrections(corrections)
+                );
             }
         }
     }
@@ -338,6 +404,21 @@ impl<'a> FuzzyMatcher<'a> {
         None
     }
 
+    /// Pushes a new head for an error's fix, if the cost of the fix is affordable
+    fn push_error(
+        &self,
+        result: &mut FuzzyMatcherResult,
+        head: &FuzzyMatcherHead,
+        state: u32,
+        edits: &[FuzzyEdit],
+        cost: usize
+    ) {
+        let cost = head.get_cost() + cost;
+        if cost <= self.max_distance {
+            result.push_head_error(head, state, edits, cost);
+        }
+    }
+
     /// Inspects a head while at the end of the input
     fn inspect_at_end(
         &self,
@@ -352,9 +433,9 @@ impl<'a> FuzzyMatcher<'a> {
         {
             self.on_matching_head(result, head, offset);
         }
-        if head.get_distance() < self.max_distance && !state_data.is_dead_end() {
+        if head.get_cost() < self.max_distance && !state_data.is_dead_end() {
             // lookup transitions
-            self.explore_transitions(result, head, &state_data, offset, false);
+            self.explore_transitions(result, head, &state_data, offset, false, &[]);
             self.explore_insertions(result, head, offset, false, 0);
         }
     }
@@ -374,7 +455,7 @@ impl<'a> FuzzyMatcher<'a> {
         {
             self.on_matching_head(result, head, offset);
         }
-        if head.get_distance() >= self.max_distance || state_data.is_dead_end() {
+        if head.get_cost() >= self.max_distance || state_data.is_dead_end() {
             // cannot stray further
             return;
         }
@@ -385,9 +466,14 @@ impl<'a> FuzzyMatcher<'a> {
             result.push_head(head, target);
         }
         // could try a drop
-        result.push_head_error(head, head.state, offset);
+        let edit = FuzzyEdit {
+            kind: LexicalCorrectionKind::Deletion,
+            offset: offset as u32,
+            value: current
+        };
+        self.push_error(result, head, head.state, &[edit], self.costs.deletion);
         // lookup transitions
-        self.explore_transitions(result, head, &state_data, offset, false);
+        self.explore_transitions(result, head, &state_data, offset, false, &[]);
         self.explore_insertions(result, head, offset, false, current);
     }
 
@@ -398,21 +484,27 @@ impl<'a> FuzzyMatcher<'a> {
         head: &FuzzyMatcherHead,
         state_data: &AutomatonState,
         offset: usize,
-        at_end: bool
+        at_end: bool,
+        inserted: &[Utf16C]
     ) {
         for i in 0..(256 as Utf16C) {
             let target = state_data.get_cached_transition(i);
             if target != DEAD_STATE {
-                self.explore_transition_to_target(result, head, target, offset, at_end);
+                self.explore_transition_to_target(
+                    result, head, target, i, offset, at_end, inserted
+                );
             }
         }
         for i in 0..state_data.get_bulk_transitions_count() {
+            let transition = state_data.get_bulk_transition(i);
             self.explore_transition_to_target(
                 result,
                 head,
-                state_data.get_bulk_transition(i).target,
+                transition.target,
+                transition.start,
                 offset,
-                at_end
+                at_end,
+                inserted
             );
         }
     }
@@ -423,23 +515,33 @@ impl<'a> FuzzyMatcher<'a> {
         result: &mut FuzzyMatcherResult,
         head: &FuzzyMatcherHead,
         target: u32,
+        value: Utf16C,
         offset: usize,
-        at_end: bool
+        at_end: bool,
+        inserted: &[Utf16C]
     ) {
         if !at_end {
-            // try to replace
-            result.push_head_error(head, target, offset);
+            // try to replace, past the end of the input this is an insertion
+            let (kind, cost) = if self.text.is_end(self.origin_index + offset) {
+                (LexicalCorrectionKind::Insertion, self.costs.insertion)
+            } else {
+                (LexicalCorrectionKind::Substitution, self.costs.substitution)
+            };
+            let edit = FuzzyEdit {
+                kind,
+                offset: offset as u32,
+                value
+            };
+            self.push_error(result, head, target, &[edit], cost);
         }
         // try to insert
-        let mut found = false;
-        for i in 0..result.insertions.len() {
-            if result.insertions[i] == target {
-                found = true;
-                break;
-            }
-        }
-        if !found {
-            result.insertions.push(target);
+        if result.insertions.iter().all(|x| x.state != target) {
+            let mut values = inserted.to_vec();
+            values.push(value);
+            result.insertions.push(FuzzyInsertion {
+                state: target,
+                values
+            });
         }
     }
 
@@ -452,18 +554,16 @@ impl<'a> FuzzyMatcher<'a> {
         at_end: bool,
         current: Utf16C
     ) {
-        let mut distance = head.get_distance() + 1;
         let mut end = result.insertions.len();
         let mut start = 0;
         // while there are insertions to examine in a round
         while start != end {
             for i in start..end {
                 // examine insertion i
-                let state = result.insertions[i];
-                self.explore_insertion(result, head, offset, at_end, current, state, distance);
+                let insertion = result.insertions[i].clone();
+                self.explore_insertion(result, head, offset, at_end, current, &insertion);
             }
             // prepare next round
-            distance += 1;
             start = end;
             end = result.insertions.len();
         }
@@ -478,24 +578,37 @@ impl<'a> FuzzyMatcher<'a> {
         offset: usize,
         at_end: bool,
         current: Utf16C,
-        state: u32,
-        distance: usize
+        insertion: &FuzzyInsertion
     ) {
+        let cost = head.get_cost() + insertion.values.len() * self.costs.insertion;
+        if cost > self.max_distance {
+            return;
+        }
+        let edits: Vec<FuzzyEdit> = insertion
+            .values
+            .iter()
+            .map(|&value| FuzzyEdit {
+                kind: LexicalCorrectionKind::Insertion,
+                offset: offset as u32,
+                value
+            })
+            .collect();
         let state_data = self.automaton.get_state(head.state);
         if state_data.get_terminals_count() > 0
             && state_data.get_terminal(0).index as u32 != self.separator
         {
-            self.on_matching_insertion(result, head, offset, state, distance);
+            let candidate = FuzzyMatcherHead::new_error(head, insertion.state, &edits, cost);
+            self.on_matching_head(result, &candidate, offset);
         }
         if !at_end {
             let target = state_data.get_target_by(current);
             if target != DEAD_STATE {
-                result.push_head_long_error(head, target, offset, distance);
+                result.push_head_error(head, target, &edits, cost);
             }
         }
-        if distance < self.max_distance {
+        if cost + self.costs.insertion <= self.max_distance {
             // continue insertion
-            self.explore_transitions(result, head, &state_data, offset, at_end);
+            self.explore_transitions(result, head, &state_data, offset, at_end, &insertion.values);
         }
     }
 
@@ -519,37 +632,127 @@ impl<'a> FuzzyMatcher<'a> {
             }
         }
     }
-
-    /// When a matching insertion is encountered
-    fn on_matching_insertion(
-        &self,
-        result: &mut FuzzyMatcherResult,
-        previous: &FuzzyMatcherHead,
-        offset: usize,
-        target: u32,
-        distance: usize
-    ) {
-        if result.match_head.is_none() {
-            result.match_head = Some(FuzzyMatcherHead::new_error(
-                previous, target, offset, distance,
-            ));
-            result.match_length = offset;
-        } else {
-            let d = distance - previous.get_distance();
-            let current_cl =
-                get_comparable_length(result.match_head.as_ref().unwrap(), result.match_length);
-            let candidate_cl = get_comparable_length(previous, offset - d);
-            if candidate_cl > current_cl {
-                result.match_head = Some(FuzzyMatcherHead::new_error(
-                    previous, target, offset, distance,
-                ));
-                result.match_length = offset;
-            }
-        }
-    }
 }
 
 /// Computes the comparable length of the specified match
 fn get_comparable_length(head: &FuzzyMatcherHead, length: usize) -> isize {
-    length as isize - head.get_distance() as isize
+    length as isize - head.get_cost() as isize
+}
+
+#[test]
+fn test_fuzzy_corrections() {
+    use super::super::errors::ParseErrorDataTrait;
+    use super::super::utils::iterable::Iterable;
+    // automaton matching "abc" as the terminal at index 1
+    let mut states = Vec::<u16>::new();
+    let mut offsets = Vec::<u32>::new();
+    for (i, c) in "abc".chars().enumerate() {
+        offsets.push(states.len() as u32);
+        states.extend_from_slice(&[0, 1, 0]);
+        let mut cache = [DEAD_STATE as u16; 256];
+        cache[c as usize] = (i + 1) as u16;
+        states.extend_from_slice(&cache);
+    }
+    offsets.push(states.len() as u32);
+    states.extend_from_slice(&[1, 0, 0, 0, 1]);
+    states.extend_from_slice(&[DEAD_STATE as u16; 256]);
+    let mut data = Vec::<u8>::new();
+    data.extend_from_slice(&[offsets.len() as u8, 0, 0, 0]);
+    for offset in offsets.iter() {
+        data.extend_from_slice(&[*offset as u8, (*offset >> 8) as u8, 0, 0]);
+    }
+    for value in states.iter() {
+        data.extend_from_slice(&[*value as u8, (*value >> 8) as u8]);
+    }
+    let automaton = Automaton::new(&data);
+    let inputs = [
+        ("axc", LexicalCorrectionKind::Substitution, 'b', 3),
+        ("ab", LexicalCorrectionKind::Insertion, 'c', 3),
+        ("abxc", LexicalCorrectionKind::Deletion, 'x', 4)
+    ];
+    for &(input, kind, value, length) in inputs.iter() {
+        let text = Text::new(input);
+        let mut errors = ParseErrors::new();
+        let result = FuzzyMatcher::new(&automaton, 0, &text, &mut errors, 3, 0).run();
+        assert_eq!(result.unwrap().length, length);
+        assert_eq!(errors.get_count(), 1);
+        let corrections = errors[0].get_corrections();
+        assert_eq!(corrections.len(), 1);
+        let correction = corrections[0];
+        assert_eq!(correction.kind, kind);
+        assert_eq!(correction.value, value as Utf16C);
+    }
+    // substitutions costlier than a deletion and an insertion
+    let text = Text::new("abx");
+    let mut errors = ParseErrors::new();
+    {
+        let mut matcher = FuzzyMatcher::new(&automaton, 0, &text, &mut errors, 3, 0);
+        matcher.set_costs(FuzzyCosts {
+            insertion: 1,
+            deletion: 1,
+            substitution: 3
+        });
+        assert!(matcher.run().is_some());
+    }
+    let kinds: Vec<LexicalCorrectionKind> = errors
+        .iter()
+        .flat_map(|error| {
+            error
+                .get_corrections()
+                .iter()
+                .map(|correction| correction.kind)
+        })
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![
+            LexicalCorrectionKind::Deletion,
+            LexicalCorrectionKind::Insertion
+        ]
+    );
+}
+
+#[test]
+fn test_fuzzy_default_costs() {
+    use super::super::utils::iterable::Iterable;
+    let automaton = Automaton::new(include_bytes!(
+        "../../../sdk-net/Sources/Input/HimeGrammarLexer.bin"
+    ));
+    // the matches and errors of the Levenshtein matcher of the previous versions
+    // input | maximum distance | matched state | matched length | errors
+    let expected = "\
+§a|2|55|2|@(1, 1) Unexpected character '§' (U+A7)
+'abc|3|134|5|@(1, 5) Unexpected end of input
+0x1G|2|64|4|@(1, 1) Unexpected character '0' (U+30)
+ub{|2|135|5|@(1, 4) Unexpected end of input; @(1, 5) Unexpected end of input
+a§b|2|64|3|@(1, 2) Unexpected character '§' (U+A7)
+'\\u12'|1|105|1|@(1, 1) Unexpected character ''' (U+27)
+'\\u12'|3|134|6|@(1, 3) Unexpected character 'u' (U+75)
+'\\u12|2|134|6|@(1, 3) Unexpected character 'u' (U+75); @(1, 6) Unexpected end of input
+'\\u12|3|134|8|@(1, 6) Unexpected end of input; @(1, 7) Unexpected end of input; @(1, 8) Unexpected end of input
+12.e|2|128|5|@(1, 1) Unexpected character '1' (U+31); @(1, 5) Unexpected end of input
+grammr|3|64|6|
+'|2|134|3|@(1, 2) Unexpected end of input; @(1, 3) Unexpected end of input
+/* a *|2|128|7|@(1, 1) Unexpected character '/' (U+2F); @(1, 7) Unexpected end of input
+§ §|2|128|4|@(1, 1) Unexpected character '§' (U+A7); @(1, 4) Unexpected end of input";
+    for line in expected.lines() {
+        let fields: Vec<&str> = line.split('|').collect();
+        let distance: usize = fields[1].parse().unwrap();
+        let state: u32 = fields[2].parse().unwrap();
+        let length: u32 = fields[3].parse().unwrap();
+        for explicit in [false, true].iter() {
+            let text = Text::new(fields[0]);
+            let mut errors = ParseErrors::new();
+            let result = {
+                let mut matcher = FuzzyMatcher::new(&automaton, 2, &text, &mut errors, distance, 0);
+                if *explicit {
+                    matcher.set_costs(FuzzyCosts::default());
+                }
+                matcher.run().unwrap()
+            };
+            assert_eq!((result.state, result.length), (state, length), "{}", line);
+            let messages: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+            assert_eq!(messages.join("; "), fields[4], "{}", line);
+        }
+    }
 }
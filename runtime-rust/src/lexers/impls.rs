@@ -17,56 +17,182 @@
 
 //! Module for lexers' implementation
 
-use super::super::errors::ParseErrorUnexpectedChar;
+use std::cmp::max;
+use std::cmp::min;
+
 use super::super::errors::ParseErrors;
 use super::super::symbols::Symbol;
 use super::super::symbols::SID_DOLLAR;
 use super::super::text::Text;
+use super::super::text::TextSpan;
 use super::super::tokens::TokenRepository;
+use super::super::tokens::CHANNEL_DEFAULT;
 use super::automaton::run_dfa;
 use super::automaton::Automaton;
+use super::automaton::AutomatonState;
 use super::automaton::TokenMatch;
-use super::fuzzy::FuzzyMatcher;
+use super::fuzzy::FuzzyCosts;
+use super::recovery::FuzzyRecovery;
+use super::recovery::LexicalRecovery;
+use super::recovery::LexicalRecoveryContext;
+use super::recovery::RecoveryStrategy;
+use super::AmbiguityCandidate;
 use super::ContextProvider;
+use super::ExternalScanner;
+use super::ExternalScannerMode;
 use super::Lexer;
+use super::LexicalAmbiguity;
+use super::ScannedToken;
 use super::TokenKernel;
 
 /// The default maximum Levenshtein distance to go to for the recovery of a matching failure
 const DEFAULT_RECOVERY_MATCHING_DISTANCE: usize = 3;
 
-/// Runs the fuzzy DFA matcher
-fn run_fuzzy_matcher<'a>(
+/// Runs the recovery strategy at the specified index in the input
+/// Without a strategy, the default fuzzy matching is used.
+/// The tokens and skips are clamped to the remaining input and
+/// the tokens for terminals outside of the table are skipped.
+fn run_recovery<'a>(
+    strategy: &mut Option<&mut dyn RecoveryStrategy>,
     repository: &TokenRepository<'a>,
     automaton: &Automaton,
     separator_id: u32,
-    recovery: usize,
+    fallback: FuzzyRecovery,
     errors: &mut ParseErrors,
     origin_index: usize
-) -> Option<TokenMatch> {
-    if recovery <= 0 {
-        errors.push_error_unexpected_char(ParseErrorUnexpectedChar::new(
-            repository.get_input().get_position_at(origin_index),
-            [repository.get_input().at(origin_index), 0]
-        ));
-        None
-    } else {
-        let mut separator_index = 0;
-        for i in 0..repository.get_terminals().len() {
-            let terminal = repository.get_terminals()[i];
-            if terminal.id == separator_id {
-                separator_index = i;
-                break;
+) -> LexicalRecovery {
+    let mut context = LexicalRecoveryContext::new(
+        automaton,
+        repository.get_input(),
+        repository.get_terminals(),
+        separator_id,
+        errors,
+        origin_index
+    );
+    let recovery = match *strategy {
+        None => {
+            let mut fallback = fallback;
+            fallback.recover(&mut context)
+        }
+        Some(ref mut strategy) => strategy.recover(&mut context)
+    };
+    let remaining = repository.get_input().len() - origin_index;
+    let clamp = |length: usize| max(min(length, remaining), 1);
+    match recovery {
+        LexicalRecovery::Token(token)
+            if token.terminal <= 1 || token.terminal >= repository.get_terminals().len() =>
+        {
+            LexicalRecovery::Skip(clamp(token.length))
+        }
+        LexicalRecovery::Token(token) => LexicalRecovery::Token(ScannedToken {
+            terminal: token.terminal,
+            length: clamp(token.length)
+        }),
+        LexicalRecovery::Skip(length) => LexicalRecovery::Skip(clamp(length)),
+        recovery => recovery
+    }
+}
+
+/// Runs the external scanner, if any, at the specified index in the input
+/// The matches of length 0 and the matches for terminals outside of the table are ignored,
+/// and the length of a match is clamped to the remaining input.
+fn run_external_scanner(
+    scanner: &mut Option<&mut dyn ExternalScanner>,
+    repository: &TokenRepository,
+    index: usize
+) -> Option<ScannedToken> {
+    match *scanner {
+        None => None,
+        Some(ref mut scanner) => {
+            let text = repository.get_input();
+            if text.is_end(index) {
+                return None;
+            }
+            let terminals = repository.get_terminals();
+            match scanner.scan(text, index, terminals) {
+                // the terminals at index 0 and 1 are always epsilon and $
+                Some(scanned)
+                    if scanned.length > 0
+                        && scanned.terminal > 1
+                        && scanned.terminal < terminals.len() =>
+                {
+                    Some(ScannedToken {
+                        terminal: scanned.terminal,
+                        length: min(scanned.length, text.len() - index)
+                    })
+                }
+                _ => None
             }
         }
-        let mut matcher = FuzzyMatcher::new(
-            automaton,
-            separator_index as u32,
-            repository.get_input(),
-            errors,
-            recovery,
-            origin_index
-        );
-        matcher.run()
+    }
+}
+
+/// Runs the automaton at the specified index in the input,
+/// unless the external scanner is used instead of the automaton
+fn run_automaton(
+    automaton: &Automaton,
+    scanner_only: bool,
+    input: &Text,
+    index: usize
+) -> Option<TokenMatch> {
+    if scanner_only && !input.is_end(index) {
+        // the scanner has declined, only the end of the input remains to be matched
+        return None;
+    }
+    run_dfa(automaton, input, index)
+}
+
+/// Gets the lexical ambiguity for a token matched at the specified state, if any
+fn get_ambiguity(
+    repository: &TokenRepository,
+    state: AutomatonState,
+    span: TextSpan,
+    chosen: usize,
+    contexts: Option<&dyn ContextProvider>
+) -> Option<LexicalAmbiguity> {
+    let count = state.get_terminals_count();
+    if count <= 1 {
+        return None;
+    }
+    let terminals = repository.get_terminals();
+    let candidates = (0..count)
+        .map(|i| {
+            let matched = state.get_terminal(i);
+            let terminal = terminals[matched.index as usize].clone();
+            let available = match contexts {
+                None => true,
+                Some(contexts) => contexts
+                    .get_context_priority(repository.get_count(), matched.context, terminal.id)
+                    .is_some()
+            };
+            AmbiguityCandidate {
+                terminal,
+                context: matched.context,
+                available
+            }
+        })
+        .collect();
+    Some(LexicalAmbiguity {
+        position: repository.get_input().get_position_at(span.index),
+        span,
+        chosen: terminals[chosen].clone(),
+        candidates
+    })
+}
+
+/// Gets the channel assigned to a terminal
+fn get_channel_in(channels: &[(u32, u16)], terminal_id: u32) -> u16 {
+    channels
+        .iter()
+        .find(|&&(id, _)| id == terminal_id)
+        .map_or(CHANNEL_DEFAULT, |&(_, channel)| channel)
+}
+
+/// Assigns a channel to a terminal
+fn set_channel_in(channels: &mut Vec<(u32, u16)>, terminal_id: u32, channel: u16) {
+    channels.retain(|&(id, _)| id != terminal_id);
+    if channel != CHANNEL_DEFAULT {
+        channels.push((terminal_id, channel));
     }
 }
 
@@ -76,8 +202,8 @@ pub struct ContextFreeLexer<'a> {
     repository: TokenRepository<'a>,
     /// The repository for errors
     errors: &'a mut ParseErrors,
-    /// The DFA automaton for this lexer
-    automaton: Automaton,
+    /// The DFA automaton for this lexer, shared between lexers
+    automaton: &'a Automaton,
     /// Whether the lexer has run yet
     has_run: bool,
     /// Symbol ID of the SEPARATOR terminal
@@ -86,12 +212,26 @@ pub struct ContextFreeLexer<'a> {
     index: usize,
     /// The maximum Levenshtein distance to go to for the recovery of a matching failure.
     /// A distance of 0 indicates no recovery.
-    recovery: usize
+    recovery: usize,
+    /// The costs of the corrections applied for the recovery of a matching failure
+    costs: FuzzyCosts,
+    /// Whether the separators are retained as trivia
+    trivia: bool,
+    /// The channels assigned to terminals, by terminal identifier
+    channels: Vec<(u32, u16)>,
+    /// The external scanner, if any
+    scanner: Option<&'a mut dyn ExternalScanner>,
+    /// How the external scanner is used
+    scanner_mode: ExternalScannerMode,
+    /// The strategy for the recovery of matching failures, if not the default one
+    strategy: Option<&'a mut dyn RecoveryStrategy>,
+    /// Whether the lexical ambiguities are recorded in the repository
+    ambiguities: bool
 }
 
 impl<'a> Lexer<'a> for ContextFreeLexer<'a> {
     /// Gets the terminals matched by this lexer
-    fn get_terminals(&self) -> &'static [Symbol] {
+    fn get_terminals(&self) -> &'a [Symbol] {
         self.repository.get_terminals()
     }
 
@@ -105,6 +245,11 @@ impl<'a> Lexer<'a> for ContextFreeLexer<'a> {
         &self.repository
     }
 
+    /// Gets the lexer's output stream of tokens, for modification
+    fn get_output_mut(&mut self) -> &mut TokenRepository<'a> {
+        &mut self.repository
+    }
+
     /// Gets the lexer's errors
     fn get_errors(&mut self) -> &mut ParseErrors {
         &mut self.errors
@@ -122,6 +267,63 @@ impl<'a> Lexer<'a> for ContextFreeLexer<'a> {
         self.recovery = distance;
     }
 
+    /// Gets the costs of the corrections applied for the recovery of a matching failure
+    fn get_recovery_costs(&self) -> FuzzyCosts {
+        self.costs
+    }
+
+    /// Sets the costs of the corrections applied for the recovery of a matching failure.
+    /// The recovery distance is then the maximum total cost of the corrections.
+    fn set_recovery_costs(&mut self, costs: FuzzyCosts) {
+        self.costs = costs;
+    }
+
+    /// Gets whether the separators are retained as trivia in the output stream of tokens
+    fn is_trivia_retained(&self) -> bool {
+        self.trivia
+    }
+
+    /// Sets whether the separators are retained as trivia in the output stream of tokens
+    fn set_trivia_retained(&mut self, retained: bool) {
+        self.trivia = retained;
+    }
+
+    /// Gets whether the lexical ambiguities are recorded for diagnostics
+    fn are_ambiguities_recorded(&self) -> bool {
+        self.ambiguities
+    }
+
+    /// Sets whether the lexical ambiguities are recorded for diagnostics
+    fn set_ambiguities_recorded(&mut self, recorded: bool) {
+        self.ambiguities = recorded;
+    }
+
+    /// Gets the recorded lexical ambiguities
+    fn get_ambiguities(&self) -> &[LexicalAmbiguity] {
+        self.repository.get_ambiguities()
+    }
+
+    /// Gets the channel on which the tokens for the specified terminal are emitted
+    fn get_terminal_channel(&self, terminal_id: u32) -> u16 {
+        get_channel_in(&self.channels, terminal_id)
+    }
+
+    /// Sets the channel on which the tokens for the specified terminal are emitted.
+    /// Tokens on a channel other than `CHANNEL_DEFAULT` are retained as hidden tokens and not given to the parser.
+    fn set_terminal_channel(&mut self, terminal_id: u32, channel: u16) {
+        set_channel_in(&mut self.channels, terminal_id, channel);
+    }
+
+    /// Sets the external scanner consulted at each position in the input and how it is used
+    fn set_external_scanner(
+        &mut self,
+        scanner: &'a mut dyn ExternalScanner,
+        mode: ExternalScannerMode
+    ) {
+        self.scanner = Some(scanner);
+        self.scanner_mode = mode;
+    }
+
     /// Gets the next token in the input
     fn get_next_token(&mut self, _contexts: &ContextProvider) -> Option<TokenKernel> {
         if !self.has_run {
@@ -129,6 +331,12 @@ impl<'a> Lexer<'a> for ContextFreeLexer<'a> {
             self.find_tokens();
             self.has_run = true;
         }
+        // the synthetic tokens recorded by token filters are not replayed
+        while self.index < self.repository.get_tokens_count()
+            && self.repository.is_synthetic(self.index)
+        {
+            self.index += 1;
+        }
         if self.index >= self.repository.get_tokens_count() {
             return None;
         }
@@ -147,7 +355,7 @@ impl<'a> ContextFreeLexer<'a> {
     pub fn new(
         repository: TokenRepository<'a>,
         errors: &'a mut ParseErrors,
-        automaton: Automaton,
+        automaton: &'a Automaton,
         separator_id: u32
     ) -> ContextFreeLexer<'a> {
         ContextFreeLexer {
@@ -157,7 +365,34 @@ impl<'a> ContextFreeLexer<'a> {
             has_run: false,
             separator_id,
             index: 0,
-            recovery: DEFAULT_RECOVERY_MATCHING_DISTANCE
+            recovery: DEFAULT_RECOVERY_MATCHING_DISTANCE,
+            costs: FuzzyCosts::default(),
+            trivia: false,
+            channels: Vec::new(),
+            scanner: None,
+            scanner_mode: ExternalScannerMode::BeforeAutomaton,
+            strategy: None,
+            ambiguities: false
+        }
+    }
+
+    /// Sets the strategy for the recovery of matching failures, replacing the default fuzzy matching
+    pub fn set_recovery_strategy(&mut self, strategy: &'a mut dyn RecoveryStrategy) {
+        self.strategy = Some(strategy);
+    }
+
+    /// Gets whether the external scanner is used instead of the automaton
+    fn is_scanner_only(&self) -> bool {
+        self.scanner.is_some() && self.scanner_mode == ExternalScannerMode::InsteadOfAutomaton
+    }
+
+    /// Gets the maximum distance for the default fuzzy recovery
+    /// The fuzzy matching relies on the automaton and does not apply when the scanner replaces it.
+    fn get_fallback_distance(&self) -> usize {
+        if self.is_scanner_only() {
+            0
+        } else {
+            self.recovery
         }
     }
 
@@ -165,45 +400,94 @@ impl<'a> ContextFreeLexer<'a> {
     fn find_tokens(&mut self) {
         let mut index = 0;
         loop {
-            let mut result = run_dfa(&self.automaton, self.repository.get_input(), index);
+            if let Some(scanned) = run_external_scanner(&mut self.scanner, &self.repository, index)
+            {
+                self.add_match(scanned.terminal, index, scanned.length);
+                index += scanned.length;
+                continue;
+            }
+            let mut result = run_automaton(
+                self.automaton,
+                self.is_scanner_only(),
+                self.repository.get_input(),
+                index
+            );
             if result.is_none() {
                 // failed to match, retry with error handling
-                result = run_fuzzy_matcher(
+                let fallback = FuzzyRecovery::new(self.get_fallback_distance(), self.costs);
+                match run_recovery(
+                    &mut self.strategy,
                     &self.repository,
-                    &self.automaton,
+                    self.automaton,
                     self.separator_id,
-                    self.recovery,
+                    fallback,
                     &mut self.errors,
                     index
-                );
+                ) {
+                    LexicalRecovery::Match(the_match) => result = Some(the_match),
+                    LexicalRecovery::Token(token) => {
+                        self.add_match(token.terminal, index, token.length);
+                        index += token.length;
+                        continue;
+                    }
+                    LexicalRecovery::Skip(length) => {
+                        // skip these characters
+                        index += length;
+                        continue;
+                    }
+                    LexicalRecovery::Stop => {
+                        // stop here as if the end of the input was reached
+                        result = Some(TokenMatch {
+                            state: 0,
+                            length: 0
+                        });
+                    }
+                }
             }
-            if result.is_none() {
-                // skip this character
-                index += 1;
+            let the_match = result.unwrap();
+            if the_match.state == 0 {
+                // this is the dollar terminal, at the end of the input
+                // the index of the $ symbol is always 1
+                self.repository.add(1, index, 0);
+                // exit here
+                return;
             } else {
-                let the_match = result.unwrap();
-                if the_match.state == 0 {
-                    // this is the dollar terminal, at the end of the input
-                    // the index of the $ symbol is always 1
-                    self.repository.add(1, index, 0);
-                    // exit here
-                    return;
-                } else {
-                    // matched something
-                    let terminal = self
-                        .automaton
-                        .get_state(the_match.state)
-                        .get_terminal(0)
-                        .index as usize;
-                    if self.repository.get_terminals()[terminal].id != self.separator_id {
-                        self.repository
-                            .add(terminal, index, the_match.length as usize);
+                // matched something
+                let terminal = self
+                    .automaton
+                    .get_state(the_match.state)
+                    .get_terminal(0)
+                    .index as usize;
+                if self.ambiguities {
+                    let state = self.automaton.get_state(the_match.state);
+                    let span = TextSpan {
+                        index,
+                        length: the_match.length as usize
+                    };
+                    if let Some(ambiguity) =
+                        get_ambiguity(&self.repository, state, span, terminal, None)
+                    {
+                        self.repository.add_ambiguity(ambiguity);
                     }
-                    index += the_match.length as usize;
                 }
+                self.add_match(terminal, index, the_match.length as usize);
+                index += the_match.length as usize;
             }
         }
     }
+
+    /// Registers a match for the specified terminal in the repository
+    fn add_match(&mut self, terminal: usize, index: usize, length: usize) {
+        let terminal_id = self.repository.get_terminals()[terminal].id;
+        let channel = get_channel_in(&self.channels, terminal_id);
+        if channel != CHANNEL_DEFAULT {
+            self.repository.add_hidden(terminal, index, length, channel);
+        } else if terminal_id != self.separator_id {
+            self.repository.add(terminal, index, length);
+        } else if self.trivia {
+            self.repository.add_trivia(terminal, index, length);
+        }
+    }
 }
 
 /// Represents a context-sensitive lexer (lexing rules do not depend on the context)
@@ -212,8 +496,8 @@ pub struct ContextSensitiveLexer<'a> {
     repository: TokenRepository<'a>,
     /// The repository for errors
     errors: &'a mut ParseErrors,
-    /// The DFA automaton for this lexer
-    automaton: Automaton,
+    /// The DFA automaton for this lexer, shared between lexers
+    automaton: &'a Automaton,
     /// Whether the lexer has run yet
     has_run: bool,
     /// Symbol ID of the SEPARATOR terminal
@@ -222,12 +506,26 @@ pub struct ContextSensitiveLexer<'a> {
     input_index: usize,
     /// The maximum Levenshtein distance to go to for the recovery of a matching failure.
     /// A distance of 0 indicates no recovery.
-    recovery: usize
+    recovery: usize,
+    /// The costs of the corrections applied for the recovery of a matching failure
+    costs: FuzzyCosts,
+    /// Whether the separators are retained as trivia
+    trivia: bool,
+    /// The channels assigned to terminals, by terminal identifier
+    channels: Vec<(u32, u16)>,
+    /// The external scanner, if any
+    scanner: Option<&'a mut dyn ExternalScanner>,
+    /// How the external scanner is used
+    scanner_mode: ExternalScannerMode,
+    /// The strategy for the recovery of matching failures, if not the default one
+    strategy: Option<&'a mut dyn RecoveryStrategy>,
+    /// Whether the lexical ambiguities are recorded in the repository
+    ambiguities: bool
 }
 
 impl<'a> Lexer<'a> for ContextSensitiveLexer<'a> {
     /// Gets the terminals matched by this lexer
-    fn get_terminals(&self) -> &'static [Symbol] {
+    fn get_terminals(&self) -> &'a [Symbol] {
         self.repository.get_terminals()
     }
 
@@ -241,6 +539,11 @@ impl<'a> Lexer<'a> for ContextSensitiveLexer<'a> {
         &self.repository
     }
 
+    /// Gets the lexer's output stream of tokens, for modification
+    fn get_output_mut(&mut self) -> &mut TokenRepository<'a> {
+        &mut self.repository
+    }
+
     /// Gets the lexer's errors
     fn get_errors(&mut self) -> &mut ParseErrors {
         &mut self.errors
@@ -258,61 +561,151 @@ impl<'a> Lexer<'a> for ContextSensitiveLexer<'a> {
         self.recovery = distance;
     }
 
+    /// Gets the costs of the corrections applied for the recovery of a matching failure
+    fn get_recovery_costs(&self) -> FuzzyCosts {
+        self.costs
+    }
+
+    /// Sets the costs of the corrections applied for the recovery of a matching failure.
+    /// The recovery distance is then the maximum total cost of the corrections.
+    fn set_recovery_costs(&mut self, costs: FuzzyCosts) {
+        self.costs = costs;
+    }
+
+    /// Gets whether the separators are retained as trivia in the output stream of tokens
+    fn is_trivia_retained(&self) -> bool {
+        self.trivia
+    }
+
+    /// Sets whether the separators are retained as trivia in the output stream of tokens
+    fn set_trivia_retained(&mut self, retained: bool) {
+        self.trivia = retained;
+    }
+
+    /// Gets whether the lexical ambiguities are recorded for diagnostics
+    fn are_ambiguities_recorded(&self) -> bool {
+        self.ambiguities
+    }
+
+    /// Sets whether the lexical ambiguities are recorded for diagnostics
+    fn set_ambiguities_recorded(&mut self, recorded: bool) {
+        self.ambiguities = recorded;
+    }
+
+    /// Gets the recorded lexical ambiguities
+    fn get_ambiguities(&self) -> &[LexicalAmbiguity] {
+        self.repository.get_ambiguities()
+    }
+
+    /// Gets the channel on which the tokens for the specified terminal are emitted
+    fn get_terminal_channel(&self, terminal_id: u32) -> u16 {
+        get_channel_in(&self.channels, terminal_id)
+    }
+
+    /// Sets the channel on which the tokens for the specified terminal are emitted.
+    /// Tokens on a channel other than `CHANNEL_DEFAULT` are retained as hidden tokens and not given to the parser.
+    fn set_terminal_channel(&mut self, terminal_id: u32, channel: u16) {
+        set_channel_in(&mut self.channels, terminal_id, channel);
+    }
+
+    /// Sets the external scanner consulted at each position in the input and how it is used
+    fn set_external_scanner(
+        &mut self,
+        scanner: &'a mut dyn ExternalScanner,
+        mode: ExternalScannerMode
+    ) {
+        self.scanner = Some(scanner);
+        self.scanner_mode = mode;
+    }
+
     /// Gets the next token in the input
     fn get_next_token(&mut self, contexts: &ContextProvider) -> Option<TokenKernel> {
         if self.has_run {
             return None;
         }
         loop {
-            let mut result = run_dfa(
-                &self.automaton,
+            if let Some(scanned) =
+                run_external_scanner(&mut self.scanner, &self.repository, self.input_index)
+            {
+                let result = self.add_match(scanned.terminal, scanned.length);
+                if result.is_some() {
+                    return result;
+                }
+                continue;
+            }
+            let mut result = run_automaton(
+                self.automaton,
+                self.is_scanner_only(),
                 self.repository.get_input(),
                 self.input_index
             );
             if result.is_none() {
                 // failed to match, retry with error handling
-                result = run_fuzzy_matcher(
+                let fallback = FuzzyRecovery::new(self.get_fallback_distance(), self.costs);
+                match run_recovery(
+                    &mut self.strategy,
                     &self.repository,
-                    &self.automaton,
+                    self.automaton,
                     self.separator_id,
-                    self.recovery,
+                    fallback,
                     &mut self.errors,
                     self.input_index
-                );
+                ) {
+                    LexicalRecovery::Match(the_match) => result = Some(the_match),
+                    LexicalRecovery::Token(token) => {
+                        let result = self.add_match(token.terminal, token.length);
+                        if result.is_some() {
+                            return result;
+                        }
+                        continue;
+                    }
+                    LexicalRecovery::Skip(length) => {
+                        // skip these characters
+                        self.input_index += length;
+                        continue;
+                    }
+                    LexicalRecovery::Stop => {
+                        // stop here as if the end of the input was reached
+                        result = Some(TokenMatch {
+                            state: 0,
+                            length: 0
+                        });
+                    }
+                }
             }
-            if result.is_none() {
-                // skip this character
-                self.input_index += 1;
+            let the_match = result.unwrap();
+            if the_match.state == 0 {
+                // this is the dollar terminal, at the end of the input
+                // the index of the $ symbol is always 1
+                let token_index = self.repository.add(1, self.input_index, 0);
+                self.has_run = true;
+                return Some(TokenKernel {
+                    terminal_id: SID_DOLLAR,
+                    index: token_index as u32
+                });
             } else {
-                let the_match = result.unwrap();
-                if the_match.state == 0 {
-                    // this is the dollar terminal, at the end of the input
-                    // the index of the $ symbol is always 1
-                    let token_index = self.repository.add(1, self.input_index, 0);
-                    self.has_run = true;
-                    return Some(TokenKernel {
-                        terminal_id: SID_DOLLAR,
-                        index: token_index as u32
-                    });
-                } else {
-                    // matched something
-                    let terminal_index = self.get_terminal_for(the_match.state, contexts);
-                    let terminal_id = self.repository.get_terminals()[terminal_index as usize].id;
-                    if terminal_id != self.separator_id {
-                        let token_index = self.repository.add(
-                            terminal_index as usize,
-                            self.input_index,
-                            the_match.length as usize
-                        );
-                        self.input_index += the_match.length as usize;
-                        return Some(TokenKernel {
-                            terminal_id,
-                            index: token_index as u32
-                        });
-                    } else {
-                        self.input_index += the_match.length as usize;
+                // matched something
+                let terminal_index = self.get_terminal_for(the_match.state, contexts);
+                if self.ambiguities {
+                    let state = self.automaton.get_state(the_match.state);
+                    let span = TextSpan {
+                        index: self.input_index,
+                        length: the_match.length as usize
+                    };
+                    if let Some(ambiguity) = get_ambiguity(
+                        &self.repository,
+                        state,
+                        span,
+                        terminal_index as usize,
+                        Some(contexts)
+                    ) {
+                        self.repository.add_ambiguity(ambiguity);
                     }
                 }
+                let result = self.add_match(terminal_index as usize, the_match.length as usize);
+                if result.is_some() {
+                    return result;
+                }
             }
         }
     }
@@ -323,7 +716,7 @@ impl<'a> ContextSensitiveLexer<'a> {
     pub fn new(
         repository: TokenRepository<'a>,
         errors: &'a mut ParseErrors,
-        automaton: Automaton,
+        automaton: &'a Automaton,
         separator_id: u32
     ) -> ContextSensitiveLexer<'a> {
         ContextSensitiveLexer {
@@ -333,7 +726,58 @@ impl<'a> ContextSensitiveLexer<'a> {
             has_run: false,
             separator_id,
             input_index: 0,
-            recovery: DEFAULT_RECOVERY_MATCHING_DISTANCE
+            recovery: DEFAULT_RECOVERY_MATCHING_DISTANCE,
+            costs: FuzzyCosts::default(),
+            trivia: false,
+            channels: Vec::new(),
+            scanner: None,
+            scanner_mode: ExternalScannerMode::BeforeAutomaton,
+            strategy: None,
+            ambiguities: false
+        }
+    }
+
+    /// Sets the strategy for the recovery of matching failures, replacing the default fuzzy matching
+    pub fn set_recovery_strategy(&mut self, strategy: &'a mut dyn RecoveryStrategy) {
+        self.strategy = Some(strategy);
+    }
+
+    /// Gets whether the external scanner is used instead of the automaton
+    fn is_scanner_only(&self) -> bool {
+        self.scanner.is_some() && self.scanner_mode == ExternalScannerMode::InsteadOfAutomaton
+    }
+
+    /// Gets the maximum distance for the default fuzzy recovery
+    /// The fuzzy matching relies on the automaton and does not apply when the scanner replaces it.
+    fn get_fallback_distance(&self) -> usize {
+        if self.is_scanner_only() {
+            0
+        } else {
+            self.recovery
+        }
+    }
+
+    /// Registers a match for the specified terminal at the current index in the repository
+    /// Gets the kernel of the token to give to the parser, if any
+    fn add_match(&mut self, terminal: usize, length: usize) -> Option<TokenKernel> {
+        let terminal_id = self.repository.get_terminals()[terminal].id;
+        let channel = get_channel_in(&self.channels, terminal_id);
+        let index = self.input_index;
+        self.input_index += length;
+        if channel != CHANNEL_DEFAULT {
+            self.repository.add_hidden(terminal, index, length, channel);
+            None
+        } else if terminal_id != self.separator_id {
+            let token_index = self.repository.add(terminal, index, length);
+            Some(TokenKernel {
+                terminal_id,
+                index: token_index as u32
+            })
+        } else {
+            if self.trivia {
+                self.repository.add_trivia(terminal, index, length);
+            }
+            None
         }
     }
 
@@ -369,3 +813,274 @@ impl<'a> ContextSensitiveLexer<'a> {
         result
     }
 }
+
+/// Lexes the specified input with the lexer for Hime grammars and an external scanner
+/// Returns the values of the tokens and the number of errors.
+#[cfg(test)]
+fn lex_with_scanner(
+    input: &str,
+    scanner: &mut dyn ExternalScanner,
+    mode: ExternalScannerMode,
+    context_sensitive: bool
+) -> (Vec<String>, usize) {
+    use super::super::fixtures::HimeGrammarLexer;
+    use super::super::symbols::SemanticElementTrait;
+    use super::super::utils::iterable::Iterable;
+    use super::run_lexer;
+    let fixture = HimeGrammarLexer::load();
+    let mut result = fixture.new_result(input);
+    {
+        let data = result.get_lexing_data();
+        if context_sensitive {
+            let mut lexer = fixture.new_context_sensitive_lexer(data);
+            lexer.set_external_scanner(scanner, mode);
+            run_lexer(&mut lexer);
+        } else {
+            let mut lexer = fixture.new_lexer(data);
+            lexer.set_external_scanner(scanner, mode);
+            run_lexer(&mut lexer);
+        }
+    }
+    let values = result
+        .get_tokens()
+        .iter()
+        .map(|token| token.get_value().unwrap())
+        .collect();
+    (values, result.get_errors().get_count())
+}
+
+/// Scans the text between two `%` as a name
+#[cfg(test)]
+struct PercentScanner {}
+
+#[cfg(test)]
+impl ExternalScanner for PercentScanner {
+    fn scan(&mut self, text: &Text, index: usize, terminals: &[Symbol]) -> Option<ScannedToken> {
+        if text.at(index) != u16::from(b'%') {
+            return None;
+        }
+        let mut end = index + 1;
+        while !text.is_end(end) && text.at(end) != u16::from(b'%') {
+            end += 1;
+        }
+        Some(ScannedToken {
+            terminal: terminals.iter().position(|symbol| symbol.id == 0x0009)?,
+            length: end + 1 - index
+        })
+    }
+}
+
+#[test]
+fn test_external_scanner_before_automaton() {
+    for &context_sensitive in [false, true].iter() {
+        let (values, errors) = lex_with_scanner(
+            "grammar %a b% { }",
+            &mut PercentScanner {},
+            ExternalScannerMode::BeforeAutomaton,
+            context_sensitive
+        );
+        assert_eq!(values, vec!["grammar", "%a b%", "{", "}", ""]);
+        assert_eq!(errors, 0);
+    }
+}
+
+#[test]
+fn test_external_scanner_out_of_bounds() {
+    /// Claims a token at each position for a terminal outside of the table
+    struct UnknownScanner {}
+    impl ExternalScanner for UnknownScanner {
+        fn scan(
+            &mut self,
+            _text: &Text,
+            _index: usize,
+            terminals: &[Symbol]
+        ) -> Option<ScannedToken> {
+            Some(ScannedToken {
+                terminal: terminals.len(),
+                length: 1
+            })
+        }
+    }
+    /// Claims the $ terminal at each position
+    struct DollarScanner {}
+    impl ExternalScanner for DollarScanner {
+        fn scan(
+            &mut self,
+            _text: &Text,
+            _index: usize,
+            _terminals: &[Symbol]
+        ) -> Option<ScannedToken> {
+            Some(ScannedToken {
+                terminal: 1,
+                length: 1
+            })
+        }
+    }
+    // the results are ignored, as if the scanner declined
+    for &context_sensitive in [false, true].iter() {
+        let (values, errors) = lex_with_scanner(
+            "grammar Test { }",
+            &mut UnknownScanner {},
+            ExternalScannerMode::BeforeAutomaton,
+            context_sensitive
+        );
+        assert_eq!(values, vec!["grammar", "Test", "{", "}", ""]);
+        assert_eq!(errors, 0);
+        let (values, _) = lex_with_scanner(
+            "grammar Test { }",
+            &mut DollarScanner {},
+            ExternalScannerMode::BeforeAutomaton,
+            context_sensitive
+        );
+        assert_eq!(values, vec!["grammar", "Test", "{", "}", ""]);
+    }
+    // an unterminated match is clamped to the end of the input
+    for &context_sensitive in [false, true].iter() {
+        let (values, errors) = lex_with_scanner(
+            "grammar %Test",
+            &mut PercentScanner {},
+            ExternalScannerMode::BeforeAutomaton,
+            context_sensitive
+        );
+        assert_eq!(values, vec!["grammar", "%Test", ""]);
+        assert_eq!(errors, 0);
+    }
+}
+
+#[test]
+fn test_external_scanner_instead_of_automaton() {
+    /// Scans the words as names and the spaces as separators
+    struct WordScanner {}
+    impl ExternalScanner for WordScanner {
+        fn scan(
+            &mut self,
+            text: &Text,
+            index: usize,
+            terminals: &[Symbol]
+        ) -> Option<ScannedToken> {
+            let is_space = text.at(index) == u16::from(b' ');
+            let is_letter = |c: u16| c >= u16::from(b'a') && c <= u16::from(b'z');
+            let mut end = index;
+            while !text.is_end(end)
+                && ((is_space && text.at(end) == u16::from(b' '))
+                    || (!is_space && is_letter(text.at(end))))
+            {
+                end += 1;
+            }
+            let id = if is_space { 0x0007 } else { 0x0009 };
+            Some(ScannedToken {
+                terminal: terminals.iter().position(|symbol| symbol.id == id)?,
+                length: end - index
+            })
+        }
+    }
+    for &context_sensitive in [false, true].iter() {
+        // the keywords of the automaton are not matched
+        let (values, errors) = lex_with_scanner(
+            "grammar test",
+            &mut WordScanner {},
+            ExternalScannerMode::InsteadOfAutomaton,
+            context_sensitive
+        );
+        assert_eq!(values, vec!["grammar", "test", ""]);
+        assert_eq!(errors, 0);
+        // a position declined by the scanner is a matching failure
+        let (_, errors) = lex_with_scanner(
+            "a 1 b",
+            &mut WordScanner {},
+            ExternalScannerMode::InsteadOfAutomaton,
+            context_sensitive
+        );
+        assert_eq!(errors, 1);
+    }
+}
+
+#[test]
+fn test_external_scanner_forwarded() {
+    use super::super::fixtures::HimeGrammarLexer;
+    use super::filters::FilteredLexer;
+    use super::filters::TokenFilter;
+    use super::filters::TokenFilterOutput;
+    use super::run_lexer;
+    /// Gives all the tokens to the parser
+    struct PassFilter {}
+    impl TokenFilter for PassFilter {
+        fn filter(&mut self, token: TokenKernel, output: &mut TokenFilterOutput) {
+            output.push(token);
+        }
+    }
+    let fixture = HimeGrammarLexer::load();
+    let mut result = fixture.new_result("grammar %a b% { }");
+    let mut filter = PassFilter {};
+    let mut scanner = PercentScanner {};
+    {
+        let lexer = fixture.new_lexer(result.get_lexing_data());
+        let mut lexer = FilteredLexer::new(lexer, &mut filter);
+        // the scanner is set through the wrapper
+        lexer.set_external_scanner(&mut scanner, ExternalScannerMode::BeforeAutomaton);
+        assert_eq!(run_lexer(&mut lexer).len(), 5);
+    }
+    assert_eq!(result.get_tokens().get_symbol_id_for(1), 0x0009);
+    assert_eq!(result.get_errors().get_count(), 0);
+}
+
+#[test]
+fn test_ambiguities_keyword_identifier() {
+    use super::super::result::ParseResult;
+    use super::super::result::TokenizeResult;
+    use super::automaton::build_keyword_automaton;
+    use super::run_lexer;
+    let terminals = vec![
+        Symbol::new_static(0x0001, "ε"),
+        Symbol::new_static(0x0002, "$"),
+        Symbol::new_static(0x0003, "NAME"),
+        Symbol::new_static(0x0004, "a"),
+        Symbol::new_static(0x0005, "SEPARATOR")
+    ];
+    let automaton = Automaton::new(&build_keyword_automaton());
+    // the ambiguities outlive the context-free lexer
+    let mut result = TokenizeResult::new(terminals.clone(), Text::new("a a"));
+    {
+        let (repository, errors) = result.get_lexing_data();
+        let mut lexer = ContextFreeLexer::new(repository, errors, &automaton, 0x0005);
+        lexer.set_ambiguities_recorded(true);
+        run_lexer(&mut lexer);
+        assert_eq!(lexer.get_ambiguities().len(), 2);
+    }
+    let ambiguities = result.get_ambiguities();
+    assert_eq!(ambiguities.len(), 2);
+    assert_eq!(ambiguities[1].span.index, 2);
+    assert_eq!(ambiguities[1].span.length, 1);
+    assert_eq!(ambiguities[1].chosen.id, 0x0004);
+    let candidates: Vec<(u32, bool)> = ambiguities[1]
+        .candidates
+        .iter()
+        .map(|candidate| (candidate.terminal.id, candidate.available))
+        .collect();
+    assert_eq!(candidates, vec![(0x0004, true), (0x0003, true)]);
+    assert_eq!(ambiguities[1].candidates[0].context, 1);
+    result.reset("a");
+    assert!(result.get_ambiguities().is_empty());
+    // the ambiguities outlive the context-sensitive lexer
+    let mut result = ParseResult::new(terminals.clone(), Vec::new(), Vec::new(), Text::new("a"));
+    {
+        let (repository, errors, _) = result.get_parsing_data();
+        let mut lexer = ContextSensitiveLexer::new(repository, errors, &automaton, 0x0005);
+        lexer.set_ambiguities_recorded(true);
+        run_lexer(&mut lexer);
+    }
+    let ambiguities = result.get_ambiguities();
+    assert_eq!(ambiguities.len(), 1);
+    assert_eq!(ambiguities[0].chosen.id, 0x0004);
+    assert_eq!(ambiguities[0].candidates.len(), 2);
+    // the ambiguities are not recorded by default
+    let mut result = TokenizeResult::new(terminals, Text::new("a a"));
+    {
+        let (repository, errors) = result.get_lexing_data();
+        let mut lexer = ContextFreeLexer::new(repository, errors, &automaton, 0x0005);
+        assert!(!lexer.are_ambiguities_recorded());
+        run_lexer(&mut lexer);
+    }
+    assert_eq!(result.get_tokens().get_tokens_count(), 3);
+    assert!(result.get_ambiguities().is_empty());
+}
@@ -0,0 +1,395 @@
+/*******************************************************************************
+ * Copyright (c) 2017 Association Cénotélie (cenotelie.fr)
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Lesser General Public License as
+ * published by the Free Software Foundation, either version 3
+ * of the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General
+ * Public License along with this program.
+ * If not, see <http://www.gnu.org/licenses/>.
+ ******************************************************************************/
+
+//! Module for the indentation-sensitive lexers (offside rule)
+
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+
+use super::super::errors::ParseErrorInconsistentIndentation;
+use super::super::errors::ParseErrors;
+use super::super::errors::UnknownTerminalError;
+use super::super::symbols::SemanticElementTrait;
+use super::super::symbols::Symbol;
+use super::super::symbols::SID_DOLLAR;
+use super::super::text::Text;
+use super::super::tokens::TokenRepository;
+use super::ContextProvider;
+use super::Lexer;
+use super::LexicalAmbiguity;
+use super::TokenKernel;
+
+/// The default width of a tab character for the computation of indentation
+pub const DEFAULT_TAB_WIDTH: usize = 8;
+
+/// A terminal for the synthetic tokens produced by an indentation lexer
+#[derive(Copy, Clone)]
+struct SyntheticTerminal {
+    /// The terminal's unique identifier
+    id: u32,
+    /// The terminal's index in the table of terminals
+    index: usize
+}
+
+/// A lexer that produces indentation tokens on top of the tokens of another lexer
+/// The indentation of a line is given by the spaces and tabs before its first token.
+/// When a token starts a new line:
+/// * a token for the newline terminal is first produced, except for the first line,
+/// * when the line is more indented than the current level, a token for the indent terminal is produced,
+/// * when the line is less indented, a token for the dedent terminal is produced for each closed level.
+///
+/// Before the end of the input, a newline token and the remaining dedent tokens are produced.
+/// The indentation tokens are recorded in the repository as synthetic tokens,
+/// with an empty span at the position of the next token.
+/// Being synthetic, they are not found by the lookups by position in the repository.
+pub struct IndentationLexer<'a, L: Lexer<'a>> {
+    /// The lexer producing the tokens
+    lexer: L,
+    /// The terminal for the indent tokens
+    indent: SyntheticTerminal,
+    /// The terminal for the dedent tokens
+    dedent: SyntheticTerminal,
+    /// The terminal for the newline tokens
+    newline: SyntheticTerminal,
+    /// The width of a tab character
+    tab_width: usize,
+    /// The stack of the widths of the current indentation levels
+    stack: Vec<usize>,
+    /// The line of the last token produced by the lexer, 0 before the first token
+    line: usize,
+    /// The tokens not yet given to the parser
+    queue: VecDeque<TokenKernel>,
+    /// Marker for the lifetime of the lexer
+    marker: PhantomData<&'a ()>
+}
+
+impl<'a, L: Lexer<'a>> IndentationLexer<'a, L> {
+    /// Creates a new indentation lexer
+    /// The indent, dedent and newline terminals must be terminals of the lexer,
+    /// otherwise the first unknown terminal is reported as an error.
+    pub fn new(
+        lexer: L,
+        indent_id: u32,
+        dedent_id: u32,
+        newline_id: u32
+    ) -> Result<IndentationLexer<'a, L>, UnknownTerminalError> {
+        let indent = get_terminal(lexer.get_terminals(), indent_id)?;
+        let dedent = get_terminal(lexer.get_terminals(), dedent_id)?;
+        let newline = get_terminal(lexer.get_terminals(), newline_id)?;
+        Ok(IndentationLexer {
+            lexer,
+            indent,
+            dedent,
+            newline,
+            tab_width: DEFAULT_TAB_WIDTH,
+            stack: vec![0],
+            line: 0,
+            queue: VecDeque::new(),
+            marker: PhantomData
+        })
+    }
+
+    /// Gets the width of a tab character for the computation of indentation
+    pub fn get_tab_width(&self) -> usize {
+        self.tab_width
+    }
+
+    /// Sets the width of a tab character for the computation of indentation
+    pub fn set_tab_width(&mut self, width: usize) {
+        self.tab_width = width;
+    }
+
+    /// Records a synthetic token and queues it for the parser
+    fn push_synthetic(&mut self, terminal: SyntheticTerminal, index: usize) {
+        let token_index = self
+            .lexer
+            .get_output_mut()
+            .add_synthetic(terminal.index, index, 0);
+        self.queue.push_back(TokenKernel {
+            terminal_id: terminal.id,
+            index: token_index as u32
+        });
+    }
+
+    /// Handles the next token given by the lexer
+    fn on_token(&mut self, token: TokenKernel) {
+        let span = self
+            .lexer
+            .get_output()
+            .get_token(token.index as usize)
+            .get_span()
+            .unwrap();
+        if token.terminal_id == SID_DOLLAR {
+            if self.line != 0 {
+                self.push_synthetic(self.newline, span.index);
+            }
+            while self.stack.len() > 1 {
+                self.stack.pop();
+                self.push_synthetic(self.dedent, span.index);
+            }
+            self.queue.push_back(token);
+            return;
+        }
+        let line = self.find_line_from(self.line, span.index);
+        if line != self.line {
+            if self.line != 0 {
+                self.push_synthetic(self.newline, span.index);
+            }
+            let (width, start) = self.get_indentation(line, span.index);
+            let top = self.stack[self.stack.len() - 1];
+            if width > top {
+                self.stack.push(width);
+                self.push_synthetic(self.indent, span.index);
+            } else if width < top {
+                while self.stack.len() > 1 && width < self.stack[self.stack.len() - 1] {
+                    self.stack.pop();
+                    self.push_synthetic(self.dedent, span.index);
+                }
+                let expected = self.stack[self.stack.len() - 1];
+                if width != expected {
+                    let position = self.lexer.get_input().get_position_at(start);
+                    self.lexer.get_errors().push_error_inconsistent_indentation(
+                        ParseErrorInconsistentIndentation::new(
+                            position,
+                            span.index - start,
+                            width,
+                            expected
+                        )
+                    );
+                }
+            }
+        }
+        let last = span.index + if span.length == 0 { 0 } else { span.length - 1 };
+        self.line = self.find_line_from(line, last);
+        self.queue.push_back(token);
+    }
+
+    /// Finds the line of the specified index, looking forward from the specified line
+    /// The tokens come in the order of the input, so that the lines are walked once overall.
+    fn find_line_from(&self, line: usize, index: usize) -> usize {
+        let text = self.lexer.get_input();
+        if line == 0 || index < text.get_line_index(line) {
+            return text.get_position_at(index).line;
+        }
+        let mut line = line;
+        while line < text.get_line_count() && text.get_line_index(line + 1) <= index {
+            line += 1;
+        }
+        line
+    }
+
+    /// Gets the width of the indentation of a line before the specified index,
+    /// together with the index of the start of the line
+    fn get_indentation(&self, line: usize, index: usize) -> (usize, usize) {
+        let text = self.lexer.get_input();
+        let start = text.get_line_index(line);
+        let mut width = 0;
+        for i in start..index {
+            match text.at(i) {
+                0x20 => width += 1,
+                0x09 => width += self.tab_width - (width % self.tab_width),
+                _ => break
+            }
+        }
+        (width, start)
+    }
+}
+
+impl<'a, L: Lexer<'a>> Lexer<'a> for IndentationLexer<'a, L> {
+    /// Gets the terminals matched by this lexer
+    fn get_terminals(&self) -> &'a [Symbol] {
+        self.lexer.get_terminals()
+    }
+
+    /// Gets the lexer's input text
+    fn get_input(&self) -> &Text {
+        self.lexer.get_input()
+    }
+
+    /// Gets the lexer's output stream of tokens
+    fn get_output(&self) -> &TokenRepository<'a> {
+        self.lexer.get_output()
+    }
+
+    /// Gets the lexer's errors
+    fn get_errors(&mut self) -> &mut ParseErrors {
+        self.lexer.get_errors()
+    }
+
+    /// Gets the maximum Levenshtein distance to go to for the recovery of a matching failure.
+    /// A distance of 0 indicates no recovery.
+    fn get_recovery_distance(&self) -> usize {
+        self.lexer.get_recovery_distance()
+    }
+
+    /// Sets the maximum Levenshtein distance to go to for the recovery of a matching failure.
+    /// A distance of 0 indicates no recovery.
+    fn set_recovery_distance(&mut self, distance: usize) {
+        self.lexer.set_recovery_distance(distance);
+    }
+
+    /// Gets the recorded lexical ambiguities
+    fn get_ambiguities(&self) -> &[LexicalAmbiguity] {
+        self.get_output().get_ambiguities()
+    }
+
+    /// Gets the lexer wrapped by this one, if any
+    fn get_wrapped(&self) -> Option<&dyn Lexer<'a>> {
+        Some(&self.lexer)
+    }
+
+    /// Gets the lexer wrapped by this one, if any, for modification
+    fn get_wrapped_mut(&mut self) -> Option<&mut dyn Lexer<'a>> {
+        Some(&mut self.lexer)
+    }
+
+    /// Gets the next token in the input
+    fn get_next_token(&mut self, contexts: &dyn ContextProvider) -> Option<TokenKernel> {
+        loop {
+            if let Some(kernel) = self.queue.pop_front() {
+                return Some(kernel);
+            }
+            let token = self.lexer.get_next_token(contexts)?;
+            self.on_token(token);
+        }
+    }
+}
+
+/// Gets the synthetic terminal for the specified identifier
+fn get_terminal(terminals: &[Symbol], id: u32) -> Result<SyntheticTerminal, UnknownTerminalError> {
+    match terminals.iter().position(|symbol| symbol.id == id) {
+        None => Err(UnknownTerminalError { terminal_id: id }),
+        Some(index) => Ok(SyntheticTerminal { id, index })
+    }
+}
+
+/// The terminal for the names in the lexer for Hime grammars
+#[cfg(test)]
+const NAME: u32 = 0x0009;
+/// The terminal `>` used for the indent tokens in the tests
+#[cfg(test)]
+const INDENT: u32 = 0x0055;
+/// The terminal `#` used for the dedent tokens in the tests
+#[cfg(test)]
+const DEDENT: u32 = 0x0056;
+/// The terminal `:` used for the newline tokens in the tests
+#[cfg(test)]
+const NEWLINE: u32 = 0x005E;
+
+/// Lexes the specified input with the lexer for Hime grammars and indentation tokens
+/// Returns the identifiers of the produced terminals and the errors.
+#[cfg(test)]
+fn lex_indented(input: &str) -> (Vec<u32>, Vec<String>) {
+    use super::super::fixtures::HimeGrammarLexer;
+    use super::super::utils::iterable::Iterable;
+    use super::run_lexer;
+    let fixture = HimeGrammarLexer::load();
+    let mut result = fixture.new_result(input);
+    let ids = {
+        let lexer = fixture.new_lexer(result.get_lexing_data());
+        let mut lexer = IndentationLexer::new(lexer, INDENT, DEDENT, NEWLINE).unwrap();
+        run_lexer(&mut lexer)
+            .iter()
+            .map(|kernel| kernel.terminal_id)
+            .collect()
+    };
+    let errors = result
+        .get_errors()
+        .iter()
+        .map(|error| error.to_string())
+        .collect();
+    (ids, errors)
+}
+
+#[test]
+fn test_indentation_nested_block() {
+    let (ids, errors) = lex_indented("a\n  b\n    c\n  d\ne");
+    assert_eq!(
+        ids,
+        vec![
+            NAME, NEWLINE, INDENT, NAME, NEWLINE, INDENT, NAME, NEWLINE, DEDENT, NAME, NEWLINE,
+            DEDENT, NAME, NEWLINE, SID_DOLLAR,
+        ]
+    );
+    assert!(errors.is_empty());
+}
+
+#[test]
+fn test_indentation_multi_level_dedent() {
+    let (ids, errors) = lex_indented("a\n  b\n    c\nd\n  e");
+    assert_eq!(
+        ids,
+        vec![
+            NAME, NEWLINE, INDENT, NAME, NEWLINE, INDENT, NAME, NEWLINE, DEDENT, DEDENT, NAME,
+            NEWLINE, INDENT, NAME, NEWLINE, DEDENT, SID_DOLLAR,
+        ]
+    );
+    assert!(errors.is_empty());
+}
+
+#[test]
+fn test_indentation_inconsistent() {
+    let (ids, errors) = lex_indented("a\n    b\n  c");
+    assert_eq!(
+        ids,
+        vec![NAME, NEWLINE, INDENT, NAME, NEWLINE, DEDENT, NAME, NEWLINE, SID_DOLLAR]
+    );
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].starts_with("@(3, 1)"), "{}", errors[0]);
+}
+
+#[test]
+fn test_indentation_unknown_terminal() {
+    use super::super::fixtures::HimeGrammarLexer;
+    let fixture = HimeGrammarLexer::load();
+    let mut result = fixture.new_result("a");
+    let lexer = fixture.new_lexer(result.get_lexing_data());
+    let error = IndentationLexer::new(lexer, INDENT, 0xFFFF, NEWLINE).err();
+    assert_eq!(
+        error,
+        Some(UnknownTerminalError {
+            terminal_id: 0xFFFF
+        })
+    );
+}
+
+#[test]
+fn test_indentation_wrapped_settings() {
+    use super::super::fixtures::HimeGrammarLexer;
+    use super::fuzzy::FuzzyCosts;
+    let fixture = HimeGrammarLexer::load();
+    let mut result = fixture.new_result("a");
+    let lexer = fixture.new_lexer(result.get_lexing_data());
+    let mut lexer = IndentationLexer::new(lexer, INDENT, DEDENT, NEWLINE).unwrap();
+    let costs = FuzzyCosts {
+        insertion: 1,
+        deletion: 2,
+        substitution: 3
+    };
+    // the settings are those of the wrapped lexer
+    lexer.set_trivia_retained(true);
+    lexer.set_ambiguities_recorded(true);
+    lexer.set_terminal_channel(NAME, 2);
+    lexer.set_recovery_costs(costs);
+    let wrapped = lexer.get_wrapped().unwrap();
+    assert!(wrapped.is_trivia_retained());
+    assert!(wrapped.are_ambiguities_recorded());
+    assert_eq!(wrapped.get_terminal_channel(NAME), 2);
+    assert_eq!(wrapped.get_recovery_costs().substitution, 3);
+    assert!(lexer.is_trivia_retained());
+    assert_eq!(lexer.get_terminal_channel(NAME), 2);
+}
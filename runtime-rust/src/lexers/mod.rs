@@ -18,15 +18,26 @@
 //! Module for lexers API
 
 pub mod automaton;
+pub mod contexts;
+pub mod filters;
 pub mod fuzzy;
 pub mod impls;
+pub mod indentation;
+pub mod recovery;
 
 use std::usize;
 
+use self::contexts::ContextLayer;
+use self::filters::TokenFilter;
+use self::fuzzy::FuzzyCosts;
 use super::errors::ParseErrors;
 use super::symbols::Symbol;
 use super::text::Text;
+use super::text::TextPosition;
+use super::text::TextSpan;
+use super::tokens::Token;
 use super::tokens::TokenRepository;
+use super::tokens::CHANNEL_DEFAULT;
 
 /// Identifier of the default context
 pub const DEFAULT_CONTEXT: u16 = 0;
@@ -42,6 +53,12 @@ pub trait ContextProvider {
         context: u16,
         terminal_id: u32
     ) -> Option<usize>;
+
+    /// Gets the contexts that are currently active, for debugging purposes
+    /// The default context is always active.
+    fn get_active_contexts(&self) -> Vec<u16> {
+        vec![DEFAULT_CONTEXT]
+    }
 }
 
 /// Implementation of the default context provider
@@ -71,10 +88,68 @@ pub struct TokenKernel {
     pub index: u32
 }
 
+/// Represents a candidate terminal for an ambiguous token
+#[derive(Clone)]
+pub struct AmbiguityCandidate {
+    /// The candidate terminal
+    pub terminal: Symbol,
+    /// The context required by the candidate terminal
+    pub context: u16,
+    /// Whether the required context was available when the token was matched
+    pub available: bool
+}
+
+/// Represents a lexical ambiguity, i.e. a token whose text was matched by more than one terminal
+#[derive(Clone)]
+pub struct LexicalAmbiguity {
+    /// The position of the token in the input
+    pub position: TextPosition,
+    /// The span of the token in the input
+    pub span: TextSpan,
+    /// The terminal that has been chosen by the lexer
+    pub chosen: Symbol,
+    /// All the candidate terminals, in the order of the automaton
+    pub candidates: Vec<AmbiguityCandidate>
+}
+
+/// Represents a token matched by an external scanner
+#[derive(Copy, Clone)]
+pub struct ScannedToken {
+    /// The index of the matched terminal in the lexer's table of terminals
+    pub terminal: usize,
+    /// The length of the match in the input
+    pub length: usize
+}
+
+/// A hand-written scanner for the tokens that cannot be matched by the lexer's automaton,
+/// for example nested comments, heredocs or raw strings with custom delimiters.
+/// A lexer consults its external scanner at each position in the input,
+/// before or instead of running its automaton depending on the `ExternalScannerMode`.
+pub trait ExternalScanner {
+    /// Tries to match a token at the specified index in the input
+    /// Returns the matched terminal and the length of the match,
+    /// or `None` to decline and let the lexer run its automaton.
+    /// A match of length 0, or for a terminal that is not in the table of terminals,
+    /// is considered as declined; the ε and $ terminals cannot be matched.
+    /// The length of a match is bounded by the remaining input.
+    fn scan(&mut self, text: &Text, index: usize, terminals: &[Symbol]) -> Option<ScannedToken>;
+}
+
+/// The way a lexer uses its external scanner
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ExternalScannerMode {
+    /// The scanner is consulted first, the automaton runs when the scanner declines
+    BeforeAutomaton,
+    /// The scanner replaces the automaton, a position declined by the scanner is a matching failure
+    /// Without a recovery strategy, the unexpected character is reported and skipped,
+    /// the default fuzzy matching with the automaton does not apply.
+    InsteadOfAutomaton
+}
+
 /// The public interface of a lexer
 pub trait Lexer<'a> {
     /// Gets the terminals matched by this lexer
-    fn get_terminals(&self) -> &'static [Symbol];
+    fn get_terminals(&self) -> &'a [Symbol];
 
     /// Gets the lexer's input text
     fn get_input(&self) -> &Text;
@@ -82,6 +157,19 @@ pub trait Lexer<'a> {
     /// Gets the lexer's output stream of tokens
     fn get_output(&self) -> &TokenRepository<'a>;
 
+    /// Gets the lexer's output stream of tokens, for modification
+    /// By default, gets the output of the wrapped lexer.
+    ///
+    /// # Panics
+    ///
+    /// The default implementation panics for a lexer that wraps no other lexer.
+    fn get_output_mut(&mut self) -> &mut TokenRepository<'a> {
+        match self.get_wrapped_mut() {
+            Some(lexer) => lexer.get_output_mut(),
+            None => panic!("The lexer does not give access to its output for modification")
+        }
+    }
+
     /// Gets the lexer's errors
     fn get_errors(&mut self) -> &mut ParseErrors;
 
@@ -93,6 +181,203 @@ pub trait Lexer<'a> {
     /// A distance of 0 indicates no recovery.
     fn set_recovery_distance(&mut self, distance: usize);
 
+    /// Gets the costs of the corrections applied for the recovery of a matching failure
+    /// By default, gets the costs of the wrapped lexer, or the default costs.
+    fn get_recovery_costs(&self) -> FuzzyCosts {
+        match self.get_wrapped() {
+            Some(lexer) => lexer.get_recovery_costs(),
+            None => FuzzyCosts::default()
+        }
+    }
+
+    /// Sets the costs of the corrections applied for the recovery of a matching failure.
+    /// The recovery distance is then the maximum total cost of the corrections.
+    /// By default, the costs are given to the wrapped lexer, or ignored.
+    fn set_recovery_costs(&mut self, costs: FuzzyCosts) {
+        if let Some(lexer) = self.get_wrapped_mut() {
+            lexer.set_recovery_costs(costs);
+        }
+    }
+
+    /// Gets whether the separators are retained as trivia in the output stream of tokens
+    /// By default, lexers do not retain trivia, unless the wrapped lexer does.
+    fn is_trivia_retained(&self) -> bool {
+        match self.get_wrapped() {
+            Some(lexer) => lexer.is_trivia_retained(),
+            None => false
+        }
+    }
+
+    /// Sets whether the separators are retained as trivia in the output stream of tokens
+    /// By default, the setting is given to the wrapped lexer, or ignored by lexers that do not support trivia.
+    fn set_trivia_retained(&mut self, retained: bool) {
+        if let Some(lexer) = self.get_wrapped_mut() {
+            lexer.set_trivia_retained(retained);
+        }
+    }
+
+    /// Gets whether the lexical ambiguities are recorded for diagnostics
+    /// By default, lexers do not record ambiguities, unless the wrapped lexer does.
+    fn are_ambiguities_recorded(&self) -> bool {
+        match self.get_wrapped() {
+            Some(lexer) => lexer.are_ambiguities_recorded(),
+            None => false
+        }
+    }
+
+    /// Sets whether the lexical ambiguities are recorded for diagnostics
+    /// By default, the setting is given to the wrapped lexer, or ignored.
+    fn set_ambiguities_recorded(&mut self, recorded: bool) {
+        if let Some(lexer) = self.get_wrapped_mut() {
+            lexer.set_ambiguities_recorded(recorded);
+        }
+    }
+
+    /// Gets the recorded lexical ambiguities
+    /// The ambiguities are recorded in the output stream of tokens and remain available on the result.
+    /// By default, lexers do not record ambiguities;
+    /// a lexer that wraps another one gets the ambiguities recorded in the shared output.
+    fn get_ambiguities(&self) -> &[LexicalAmbiguity] {
+        &[]
+    }
+
+    /// Gets the channel on which the tokens for the specified terminal are emitted
+    /// By default, gets the channel of the wrapped lexer, or `CHANNEL_DEFAULT`.
+    fn get_terminal_channel(&self, terminal_id: u32) -> u16 {
+        match self.get_wrapped() {
+            Some(lexer) => lexer.get_terminal_channel(terminal_id),
+            None => CHANNEL_DEFAULT
+        }
+    }
+
+    /// Sets the channel on which the tokens for the specified terminal are emitted.
+    /// Tokens on a channel other than `CHANNEL_DEFAULT` are retained as hidden tokens and not given to the parser.
+    /// By default, the setting is given to the wrapped lexer, or ignored.
+    fn set_terminal_channel(&mut self, terminal_id: u32, channel: u16) {
+        if let Some(lexer) = self.get_wrapped_mut() {
+            lexer.set_terminal_channel(terminal_id, channel);
+        }
+    }
+
+    /// Sets the external scanner consulted at each position in the input and how it is used
+    /// By default, the scanner is given to the wrapped lexer, or ignored by the lexers that do not support one.
+    fn set_external_scanner(
+        &mut self,
+        scanner: &'a mut dyn ExternalScanner,
+        mode: ExternalScannerMode
+    ) {
+        if let Some(lexer) = self.get_wrapped_mut() {
+            lexer.set_external_scanner(scanner, mode);
+        }
+    }
+
+    /// Gets the lexer wrapped by this one, if any
+    /// The settings of a lexer that wraps another one are by default those of the wrapped lexer.
+    fn get_wrapped(&self) -> Option<&dyn Lexer<'a>> {
+        None
+    }
+
+    /// Gets the lexer wrapped by this one, if any, for modification
+    fn get_wrapped_mut(&mut self) -> Option<&mut dyn Lexer<'a>> {
+        None
+    }
+
     /// Gets the next token in the input
     fn get_next_token(&mut self, contexts: &ContextProvider) -> Option<TokenKernel>;
 }
+
+/// Delegate for setting up a lexer before it is run, for example to retain the trivia
+pub type LexerSetup<'s> = dyn for<'a> FnMut(&mut dyn Lexer<'a>) + 's;
+
+/// The options for running the lexer of a parser
+/// The default options apply no filter, no setup and no context layer.
+#[derive(Default)]
+pub struct LexerOptions<'o> {
+    /// The filter rewriting the stream of tokens, if any
+    pub filter: Option<&'o mut dyn TokenFilter>,
+    /// The function setting up the lexer before it is run, if any
+    /// The setup can for example retain the separators as trivia on the tokens.
+    pub setup: Option<&'o mut LexerSetup<'o>>,
+    /// The layers resolving the lexical contexts on top of the contexts provided by the parser
+    /// The last layer is the outermost.
+    pub layers: &'o [&'o dyn ContextLayer]
+}
+
+/// Runs a lexer until the end of its input, without a parser
+/// All the lexical contexts are considered to be available.
+/// Returns the kernels of the tokens given by the lexer, in order.
+pub fn run_lexer(lexer: &mut dyn Lexer) -> Vec<TokenKernel> {
+    LexerTokens::new(lexer).collect()
+}
+
+/// A lazy iterator over the tokens produced by a lexer, without a parser
+/// Each step asks the lexer for its next token, all the lexical contexts being considered available.
+/// The items are the kernels of the tokens; because the lexer keeps writing to its output,
+/// the complete tokens are retrieved with `get_token` between two steps,
+/// or from the lexer's output once the iteration is done.
+pub struct LexerTokens<'b, 'a: 'b> {
+    /// The lexer to run
+    lexer: &'b mut dyn Lexer<'a>
+}
+
+impl<'b, 'a: 'b> LexerTokens<'b, 'a> {
+    /// Creates the iterator over the tokens of the specified lexer
+    pub fn new(lexer: &'b mut dyn Lexer<'a>) -> LexerTokens<'b, 'a> {
+        LexerTokens { lexer }
+    }
+
+    /// Gets the token for the specified kernel, as given by this iterator
+    pub fn get_token(&self, kernel: TokenKernel) -> Token<'_> {
+        let repository: &TokenRepository = self.lexer.get_output();
+        repository.get_token(kernel.index as usize)
+    }
+}
+
+/// Implementation of `Iterator` for `LexerTokens`
+impl<'b, 'a: 'b> Iterator for LexerTokens<'b, 'a> {
+    type Item = TokenKernel;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.lexer.get_next_token(&DefaultContextProvider {})
+    }
+}
+
+#[test]
+fn test_lexer_tokens() {
+    use super::fixtures::HimeGrammarLexer;
+    use super::symbols::SemanticElementTrait;
+    let fixture = HimeGrammarLexer::load();
+    let mut result = fixture.new_result("grammar Test { }");
+    let mut lexer = fixture.new_lexer(result.get_lexing_data());
+    {
+        // the tokens are retrieved while lexing
+        let mut tokens = LexerTokens::new(&mut lexer);
+        let mut values = Vec::new();
+        while let Some(kernel) = tokens.next() {
+            let token = tokens.get_token(kernel);
+            assert_eq!(token.get_symbol().id, kernel.terminal_id);
+            values.push(token.get_value().unwrap());
+        }
+        assert_eq!(values, vec!["grammar", "Test", "{", "}", ""]);
+        assert!(tokens.next().is_none());
+    }
+    // the lexer is exhausted
+    assert!(run_lexer(&mut lexer).is_empty());
+    assert_eq!(lexer.get_output().get_tokens_count(), 5);
+}
+
+#[test]
+fn test_run_lexer() {
+    use super::fixtures::HimeGrammarLexer;
+    let fixture = HimeGrammarLexer::load();
+    let mut result = fixture.new_result("grammar Test {\n}");
+    {
+        let mut lexer = fixture.new_lexer(result.get_lexing_data());
+        let kernels = run_lexer(&mut lexer);
+        let ids: Vec<u32> = kernels.iter().map(|kernel| kernel.terminal_id).collect();
+        // grammar, NAME, {, }, $
+        assert_eq!(ids, vec![0x0060, 0x0009, 0x0048, 0x004A, 0x0002]);
+        let indices: Vec<u32> = kernels.iter().map(|kernel| kernel.index).collect();
+        assert_eq!(indices, vec![0, 1, 2, 3, 4]);
+    }
+    assert!(result.is_success());
+}
@@ -0,0 +1,374 @@
+/*******************************************************************************
+ * Copyright (c) 2017 Association Cénotélie (cenotelie.fr)
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Lesser General Public License as
+ * published by the Free Software Foundation, either version 3
+ * of the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General
+ * Public License along with this program.
+ * If not, see <http://www.gnu.org/licenses/>.
+ ******************************************************************************/
+
+//! Module for the strategies of recovery from lexical errors
+
+use super::super::errors::ParseErrorUnexpectedChar;
+use super::super::errors::ParseErrors;
+use super::super::symbols::Symbol;
+use super::super::text::Text;
+use super::super::text::Utf16C;
+use super::automaton::run_dfa;
+use super::automaton::Automaton;
+use super::automaton::TokenMatch;
+use super::fuzzy::FuzzyCosts;
+use super::fuzzy::FuzzyMatcher;
+use super::ScannedToken;
+
+/// Represents the action taken by a lexer to recover from a lexical error
+pub enum LexicalRecovery {
+    /// Resumes with a match of the lexer's automaton
+    Match(TokenMatch),
+    /// Resumes with a token for a terminal of the lexer, for example an "unknown" token
+    /// The length is clamped between 1 and the remaining input.
+    /// A token for ε, $ or a terminal outside of the table is skipped instead.
+    Token(ScannedToken),
+    /// Skips the specified number of characters in the input
+    /// The length is clamped between 1 and the remaining input.
+    Skip(usize),
+    /// Stops the lexer as if it reached the end of the input
+    Stop
+}
+
+/// The context of a lexical error given to a recovery strategy
+pub struct LexicalRecoveryContext<'c> {
+    /// The lexer's automaton
+    automaton: &'c Automaton,
+    /// The input text
+    text: &'c Text,
+    /// The terminals matched by the lexer
+    terminals: &'c [Symbol],
+    /// The index of the SEPARATOR terminal in the lexer's table of terminals
+    separator: u32,
+    /// The lexer's errors
+    errors: &'c mut ParseErrors,
+    /// The index in the input at which the error was raised
+    index: usize
+}
+
+impl<'c> LexicalRecoveryContext<'c> {
+    /// Creates a new context
+    pub fn new(
+        automaton: &'c Automaton,
+        text: &'c Text,
+        terminals: &'c [Symbol],
+        separator_id: u32,
+        errors: &'c mut ParseErrors,
+        index: usize
+    ) -> LexicalRecoveryContext<'c> {
+        let separator = terminals
+            .iter()
+            .position(|terminal| terminal.id == separator_id)
+            .unwrap_or(0);
+        LexicalRecoveryContext {
+            automaton,
+            text,
+            terminals,
+            separator: separator as u32,
+            errors,
+            index
+        }
+    }
+
+    /// Gets the lexer's automaton
+    pub fn get_automaton(&self) -> &'c Automaton {
+        self.automaton
+    }
+
+    /// Gets the input text
+    pub fn get_input(&self) -> &'c Text {
+        self.text
+    }
+
+    /// Gets the terminals matched by the lexer
+    pub fn get_terminals(&self) -> &'c [Symbol] {
+        self.terminals
+    }
+
+    /// Gets the index of the SEPARATOR terminal in the lexer's table of terminals
+    pub fn get_separator(&self) -> u32 {
+        self.separator
+    }
+
+    /// Gets the lexer's errors
+    pub fn get_errors(&mut self) -> &mut ParseErrors {
+        self.errors
+    }
+
+    /// Gets the index in the input at which the error was raised
+    pub fn get_index(&self) -> usize {
+        self.index
+    }
+
+    /// Raises an unexpected character error at the specified index in the input
+    pub fn push_unexpected_char(&mut self, index: usize) {
+        let c = self.text.at(index);
+        let unexpected = if (0xD800..=0xDBFF).contains(&c) && !self.text.is_end(index + 1) {
+            [c, self.text.at(index + 1)]
+        } else {
+            [c, 0]
+        };
+        self.errors
+            .push_error_unexpected_char(ParseErrorUnexpectedChar::new(
+                self.text.get_position_at(index),
+                unexpected
+            ));
+    }
+
+    /// Gets whether the lexer's automaton matches a token at the specified index in the input
+    pub fn is_matching_at(&self, index: usize) -> bool {
+        run_dfa(self.automaton, self.text, index).is_some()
+    }
+}
+
+/// A strategy for the recovery of a lexer from the failure of its automaton to match the input
+/// The strategy is responsible for raising the appropriate errors.
+pub trait RecoveryStrategy {
+    /// Recovers from the failure to match the input at the context's index
+    fn recover(&mut self, context: &mut LexicalRecoveryContext) -> LexicalRecovery;
+}
+
+/// Recovers by matching the input against the automaton with a fuzzy matcher
+/// This is the default strategy of the lexers.
+pub struct FuzzyRecovery {
+    /// The maximum distance between the input and the automaton.
+    /// A distance of 0 indicates no recovery.
+    max_distance: usize,
+    /// The costs of the corrections
+    costs: FuzzyCosts
+}
+
+impl FuzzyRecovery {
+    /// Creates a new strategy with the specified maximum distance and costs
+    pub fn new(max_distance: usize, costs: FuzzyCosts) -> FuzzyRecovery {
+        FuzzyRecovery {
+            max_distance,
+            costs
+        }
+    }
+}
+
+impl RecoveryStrategy for FuzzyRecovery {
+    fn recover(&mut self, context: &mut LexicalRecoveryContext) -> LexicalRecovery {
+        if self.max_distance == 0 {
+            let index = context.index;
+            context.push_unexpected_char(index);
+            return LexicalRecovery::Skip(1);
+        }
+        let mut matcher = FuzzyMatcher::new(
+            context.automaton,
+            context.separator,
+            context.text,
+            context.errors,
+            self.max_distance,
+            context.index
+        );
+        matcher.set_costs(self.costs);
+        match matcher.run() {
+            Some(result) => LexicalRecovery::Match(result),
+            None => LexicalRecovery::Skip(1)
+        }
+    }
+}
+
+/// Recovers by skipping the input up to the next synchronization character,
+/// for example a semicolon or a closing brace, raising a single error.
+pub struct SynchronizingRecovery {
+    /// The synchronization characters
+    synchronizers: Vec<Utf16C>
+}
+
+impl SynchronizingRecovery {
+    /// Creates a new strategy with the specified synchronization characters
+    pub fn new(synchronizers: &str) -> SynchronizingRecovery {
+        SynchronizingRecovery {
+            synchronizers: synchronizers.encode_utf16().collect()
+        }
+    }
+}
+
+impl RecoveryStrategy for SynchronizingRecovery {
+    fn recover(&mut self, context: &mut LexicalRecoveryContext) -> LexicalRecovery {
+        let origin = context.index;
+        context.push_unexpected_char(origin);
+        let mut index = origin + 1;
+        while !context.text.is_end(index) && !self.synchronizers.contains(&context.text.at(index)) {
+            index += 1;
+        }
+        LexicalRecovery::Skip(index - origin)
+    }
+}
+
+/// Recovers by skipping the whole run of unmatched input, raising a single error
+pub struct SkipRunRecovery {}
+
+impl RecoveryStrategy for SkipRunRecovery {
+    fn recover(&mut self, context: &mut LexicalRecoveryContext) -> LexicalRecovery {
+        let origin = context.index;
+        context.push_unexpected_char(origin);
+        let mut index = origin + 1;
+        while !context.text.is_end(index) && !context.is_matching_at(index) {
+            index += 1;
+        }
+        LexicalRecovery::Skip(index - origin)
+    }
+}
+
+/// Does not recover, the lexer stops at the first lexical error
+pub struct FailFastRecovery {}
+
+impl RecoveryStrategy for FailFastRecovery {
+    fn recover(&mut self, context: &mut LexicalRecoveryContext) -> LexicalRecovery {
+        let index = context.index;
+        context.push_unexpected_char(index);
+        LexicalRecovery::Stop
+    }
+}
+
+/// Lexes the specified input with the lexer for Hime grammars and a recovery strategy
+/// Returns the values of the tokens and the number of errors.
+#[cfg(test)]
+fn lex_with_recovery(
+    input: &str,
+    strategy: &mut dyn RecoveryStrategy,
+    context_sensitive: bool
+) -> (Vec<String>, usize) {
+    use super::super::fixtures::HimeGrammarLexer;
+    use super::super::symbols::SemanticElementTrait;
+    use super::super::utils::iterable::Iterable;
+    use super::run_lexer;
+    let fixture = HimeGrammarLexer::load();
+    let mut result = fixture.new_result(input);
+    {
+        let data = result.get_lexing_data();
+        if context_sensitive {
+            let mut lexer = fixture.new_context_sensitive_lexer(data);
+            lexer.set_recovery_strategy(strategy);
+            run_lexer(&mut lexer);
+        } else {
+            let mut lexer = fixture.new_lexer(data);
+            lexer.set_recovery_strategy(strategy);
+            run_lexer(&mut lexer);
+        }
+    }
+    let values = result
+        .get_tokens()
+        .iter()
+        .map(|token| token.get_value().unwrap())
+        .collect();
+    (values, result.get_errors().get_count())
+}
+
+#[test]
+fn test_synchronizing_recovery() {
+    for &context_sensitive in [false, true].iter() {
+        let (values, errors) = lex_with_recovery(
+            "grammar Test §§ x { }",
+            &mut SynchronizingRecovery::new("{}"),
+            context_sensitive
+        );
+        assert_eq!(values, vec!["grammar", "Test", "{", "}", ""]);
+        assert_eq!(errors, 1);
+        // without a synchronizer, the rest of the input is skipped
+        let (values, errors) = lex_with_recovery(
+            "grammar Test § x",
+            &mut SynchronizingRecovery::new(";"),
+            context_sensitive
+        );
+        assert_eq!(values, vec!["grammar", "Test", ""]);
+        assert_eq!(errors, 1);
+    }
+}
+
+#[test]
+fn test_skip_run_recovery() {
+    for &context_sensitive in [false, true].iter() {
+        let (values, errors) = lex_with_recovery(
+            "grammar Test §§§{ } §",
+            &mut SkipRunRecovery {},
+            context_sensitive
+        );
+        assert_eq!(values, vec!["grammar", "Test", "{", "}", ""]);
+        assert_eq!(errors, 2);
+    }
+}
+
+#[test]
+fn test_fail_fast_recovery() {
+    for &context_sensitive in [false, true].iter() {
+        let (values, errors) = lex_with_recovery(
+            "grammar Test § { } §",
+            &mut FailFastRecovery {},
+            context_sensitive
+        );
+        assert_eq!(values, vec!["grammar", "Test", ""]);
+        assert_eq!(errors, 1);
+    }
+}
+
+#[test]
+fn test_recovery_clamped() {
+    /// Skips far beyond the end of the input
+    struct OverrunSkip {}
+    impl RecoveryStrategy for OverrunSkip {
+        fn recover(&mut self, _context: &mut LexicalRecoveryContext) -> LexicalRecovery {
+            LexicalRecovery::Skip(100)
+        }
+    }
+    /// Produces a name token far beyond the end of the input
+    struct OverrunToken {}
+    impl RecoveryStrategy for OverrunToken {
+        fn recover(&mut self, context: &mut LexicalRecoveryContext) -> LexicalRecovery {
+            LexicalRecovery::Token(ScannedToken {
+                terminal: context
+                    .get_terminals()
+                    .iter()
+                    .position(|symbol| symbol.id == 0x0009)
+                    .unwrap(),
+                length: 100
+            })
+        }
+    }
+    /// Produces tokens for terminals that cannot be produced
+    struct InvalidToken {
+        /// The index of the terminal to produce, or none for the first index outside of the table
+        terminal: Option<usize>
+    }
+    impl RecoveryStrategy for InvalidToken {
+        fn recover(&mut self, context: &mut LexicalRecoveryContext) -> LexicalRecovery {
+            LexicalRecovery::Token(ScannedToken {
+                terminal: self.terminal.unwrap_or(context.get_terminals().len()),
+                length: 0
+            })
+        }
+    }
+    for &context_sensitive in [false, true].iter() {
+        let (values, _) = lex_with_recovery("grammar § x", &mut OverrunSkip {}, context_sensitive);
+        assert_eq!(values, vec!["grammar", ""]);
+        let (values, _) = lex_with_recovery("grammar § x", &mut OverrunToken {}, context_sensitive);
+        assert_eq!(values, vec!["grammar", "§ x", ""]);
+        // ε, $ and the terminals outside of the table are skipped
+        for &terminal in [Some(0), Some(1), None].iter() {
+            let (values, _) = lex_with_recovery(
+                "grammar § x",
+                &mut InvalidToken { terminal },
+                context_sensitive
+            );
+            assert_eq!(values, vec!["grammar", "x", ""]);
+        }
+    }
+}
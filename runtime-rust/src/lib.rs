@@ -27,7 +27,7 @@
 //!
 //! ```toml
 //! [dependencies]
-//! hime_redist = "3.4.2"
+//! hime_redist = "4.0.0"
 //! ```
 //!
 //! and this to your crate root:
@@ -37,11 +37,17 @@
 //! ```
 
 pub mod ast;
+pub mod batch;
 pub mod errors;
 pub mod lexers;
+pub mod loader;
 pub mod parsers;
 pub mod result;
+pub mod snippets;
 pub mod symbols;
 pub mod text;
 pub mod tokens;
 pub mod utils;
+
+#[cfg(test)]
+mod fixtures;
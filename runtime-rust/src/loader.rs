@@ -0,0 +1,542 @@
+/*******************************************************************************
+ * Copyright (c) 2017 Association Cénotélie (cenotelie.fr)
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Lesser General Public License as
+ * published by the Free Software Foundation, either version 3
+ * of the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General
+ * Public License along with this program.
+ * If not, see <http://www.gnu.org/licenses/>.
+ ******************************************************************************/
+
+//! Module for loading parsers at runtime
+//! A parser is loaded from the binary data of its lexer's and parser's automata,
+//! as produced by the generator, and a small text file describing its symbols,
+//! also produced by the generator for Rust targets (the `Symbols.txt` file).
+//! The symbol file is line-based, with empty lines and lines starting with `#` being ignored:
+//!
+//! ```text
+//! # the kind of lexer: context-free (default) or context-sensitive
+//! lexer context-free
+//! # the kind of parser: lrk (default) or rnglr
+//! parser lrk
+//! # the identifier of the separator terminal, if any
+//! separator 0x0007
+//! # the grammar symbols, in the order of the generated tables
+//! terminal 0x0001 ε
+//! terminal 0x0002 $
+//! variable 0x0010 expression
+//! virtual 0x0020 operator
+//! ```
+
+use std::io::Read;
+use std::sync::Arc;
+
+use super::ast::Ast;
+use super::errors::LoaderError;
+use super::lexers::automaton::Automaton;
+use super::lexers::impls::ContextFreeLexer;
+use super::lexers::impls::ContextSensitiveLexer;
+use super::lexers::run_lexer;
+use super::lexers::Lexer;
+use super::parsers::lrk::LRkAutomaton;
+use super::parsers::lrk::LRkParser;
+use super::parsers::lrk::LRkParserBuffers;
+use super::parsers::rnglr::RNGLRAutomaton;
+use super::parsers::rnglr::RNGLRParser;
+use super::parsers::rnglr::RNGLRParserBuffers;
+use super::parsers::Parser;
+use super::result::ParseResult;
+use super::result::TokenizeResult;
+use super::symbols::SemanticBody;
+use super::symbols::Symbol;
+use super::symbols::SymbolTable;
+use super::symbols::SID_DOLLAR;
+use super::symbols::SID_EPSILON;
+use super::text::Text;
+
+/// The identifier of the separator when the grammar has none
+const NO_SEPARATOR: u32 = 0xFFFF;
+
+/// The kinds of lexers
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum LexerKind {
+    /// A context-free lexer
+    ContextFree,
+    /// A context-sensitive lexer
+    ContextSensitive
+}
+
+/// The kinds of parsers
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ParserKind {
+    /// A LR(k) parser
+    LRk,
+    /// A RNGLR parser
+    Rnglr
+}
+
+/// The description of a grammar's symbols, as read from a symbol file
+#[derive(Clone)]
+pub struct GrammarSymbols {
+    /// The kind of lexer
+    pub lexer: LexerKind,
+    /// The kind of parser
+    pub parser: ParserKind,
+    /// The identifier of the separator terminal
+    pub separator: u32,
+    /// The grammar terminals
+    pub terminals: Vec<Symbol>,
+    /// The grammar variables
+    pub variables: Vec<Symbol>,
+    /// The grammar virtuals
+    pub virtuals: Vec<Symbol>
+}
+
+impl GrammarSymbols {
+    /// Parses the content of a symbol file
+    pub fn parse(content: &str) -> Result<GrammarSymbols, LoaderError> {
+        let mut result = GrammarSymbols {
+            lexer: LexerKind::ContextFree,
+            parser: ParserKind::LRk,
+            separator: NO_SEPARATOR,
+            terminals: Vec::new(),
+            variables: Vec::new(),
+            virtuals: Vec::new()
+        };
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (keyword, rest) = split_first(line);
+            match keyword {
+                "lexer" => {
+                    result.lexer = match rest {
+                        "context-free" => LexerKind::ContextFree,
+                        "context-sensitive" => LexerKind::ContextSensitive,
+                        _ => return Err(symbols_error(index, format!("Unknown lexer {}", rest)))
+                    }
+                }
+                "parser" => {
+                    result.parser = match rest {
+                        "lrk" => ParserKind::LRk,
+                        "rnglr" => ParserKind::Rnglr,
+                        _ => return Err(symbols_error(index, format!("Unknown parser {}", rest)))
+                    }
+                }
+                "separator" => {
+                    result.separator = parse_identifier(index, rest)?;
+                }
+                "terminal" => result.terminals.push(parse_symbol(index, rest)?),
+                "variable" => result.variables.push(parse_symbol(index, rest)?),
+                "virtual" => result.virtuals.push(parse_symbol(index, rest)?),
+                _ => {
+                    let message = format!("Unknown directive {}", keyword);
+                    return Err(symbols_error(index, message));
+                }
+            }
+        }
+        Ok(result)
+    }
+}
+
+/// Builds an error for the line at the specified index
+fn symbols_error(index: usize, message: String) -> LoaderError {
+    LoaderError::Symbols {
+        line: index + 1,
+        message
+    }
+}
+
+/// Splits a line into its first word and the trimmed rest
+fn split_first(line: &str) -> (&str, &str) {
+    match line.find(char::is_whitespace) {
+        Some(position) => (&line[..position], line[position..].trim()),
+        None => (line, "")
+    }
+}
+
+/// Parses a symbol identifier, in hexadecimal when prefixed by 0x
+fn parse_identifier(index: usize, value: &str) -> Result<u32, LoaderError> {
+    let parsed = if value.starts_with("0x") || value.starts_with("0X") {
+        u32::from_str_radix(&value[2..], 16)
+    } else {
+        value.parse::<u32>()
+    };
+    parsed.map_err(|_| symbols_error(index, format!("Invalid identifier {}", value)))
+}
+
+/// Parses the identifier and name of a symbol
+fn parse_symbol(index: usize, value: &str) -> Result<Symbol, LoaderError> {
+    let (identifier, name) = split_first(value);
+    if name.is_empty() {
+        return Err(symbols_error(index, String::from("Missing symbol name")));
+    }
+    let id = parse_identifier(index, identifier)?;
+    Ok(Symbol::new_shared(id, name))
+}
+
+/// The automaton of a parser loaded at runtime
+enum ParserAutomaton {
+    /// The automaton of a LR(k) parser
+    LRk(LRkAutomaton),
+    /// The automaton of a RNGLR parser
+    Rnglr(RNGLRAutomaton)
+}
+
+/// The buffers of the parsers, kept between parses
+struct ParserBuffers {
+    /// The buffers for a LR(k) parser
+    lrk: Option<LRkParserBuffers>,
+    /// The buffers for a RNGLR parser
+    rnglr: Option<RNGLRParserBuffers>
+}
+
+/// A reusable context for parsing many inputs with the same parser
+/// The context keeps the allocations of its parse result and of the parser between parses.
+pub struct ParseContext {
+    /// The result of the last parse
+    result: ParseResult,
+    /// The buffers of the parser
+    buffers: ParserBuffers
+}
+
+impl ParseContext {
+    /// Gets the result of the last parse
+    pub fn get_result(&self) -> &ParseResult {
+        &self.result
+    }
+
+    /// Gets the mutable result of the last parse
+    pub fn get_result_mut(&mut self) -> &mut ParseResult {
+        &mut self.result
+    }
+}
+
+/// A parser loaded at runtime
+/// The automata are deserialized once when loading and shared between clones of the parser.
+#[derive(Clone)]
+pub struct LoadedParser {
+    /// The kind of lexer
+    lexer_kind: LexerKind,
+    /// The identifier of the separator terminal
+    separator: u32,
+    /// The grammar terminals
+    terminals: SymbolTable,
+    /// The grammar variables
+    variables: SymbolTable,
+    /// The grammar virtuals
+    virtuals: SymbolTable,
+    /// The lexer's automaton
+    lexer_automaton: Arc<Automaton>,
+    /// The parser's automaton
+    parser_automaton: Arc<ParserAutomaton>
+}
+
+impl LoadedParser {
+    /// Loads a parser from the binary data of its automata and the content of its symbol file
+    /// The automata are checked against the symbols so that parsing cannot fail on invalid data.
+    pub fn load(
+        lexer_automaton: &[u8],
+        parser_automaton: &[u8],
+        symbols: &str
+    ) -> Result<LoadedParser, LoaderError> {
+        LoadedParser::from_symbols(
+            lexer_automaton,
+            parser_automaton,
+            GrammarSymbols::parse(symbols)?
+        )
+    }
+
+    /// Loads a parser from the binary data of its automata and the description of its symbols
+    pub fn from_symbols(
+        lexer_automaton: &[u8],
+        parser_automaton: &[u8],
+        symbols: GrammarSymbols
+    ) -> Result<LoadedParser, LoaderError> {
+        if symbols.terminals.len() < 2
+            || symbols.terminals[0].id != SID_EPSILON
+            || symbols.terminals[1].id != SID_DOLLAR
+        {
+            return Err(LoaderError::Terminals);
+        }
+        let lexer_automaton = Automaton::try_new(lexer_automaton, &symbols.terminals)?;
+        let parser_automaton = match symbols.parser {
+            ParserKind::LRk => ParserAutomaton::LRk(LRkAutomaton::try_new(
+                parser_automaton,
+                &symbols.terminals,
+                &symbols.variables,
+                &symbols.virtuals
+            )?),
+            ParserKind::Rnglr => ParserAutomaton::Rnglr(RNGLRAutomaton::try_new(
+                parser_automaton,
+                &symbols.terminals,
+                &symbols.variables,
+                &symbols.virtuals
+            )?)
+        };
+        Ok(LoadedParser {
+            lexer_kind: symbols.lexer,
+            separator: symbols.separator,
+            terminals: SymbolTable::new(symbols.terminals),
+            variables: SymbolTable::new(symbols.variables),
+            virtuals: SymbolTable::new(symbols.virtuals),
+            lexer_automaton: Arc::new(lexer_automaton),
+            parser_automaton: Arc::new(parser_automaton)
+        })
+    }
+
+    /// Gets the kind of lexer
+    pub fn get_lexer_kind(&self) -> LexerKind {
+        self.lexer_kind
+    }
+
+    /// Gets the kind of parser
+    pub fn get_parser_kind(&self) -> ParserKind {
+        match *self.parser_automaton {
+            ParserAutomaton::LRk(_) => ParserKind::LRk,
+            ParserAutomaton::Rnglr(_) => ParserKind::Rnglr
+        }
+    }
+
+    /// Gets the grammar terminals
+    pub fn get_terminals(&self) -> &[Symbol] {
+        &self.terminals
+    }
+
+    /// Gets the grammar variables
+    pub fn get_variables(&self) -> &[Symbol] {
+        &self.variables
+    }
+
+    /// Gets the grammar virtuals
+    pub fn get_virtuals(&self) -> &[Symbol] {
+        &self.virtuals
+    }
+
+    /// Tokenizes the specified string with this parser's lexer, without parsing
+    pub fn tokenize_string(&self, input: &str) -> TokenizeResult {
+        self.tokenize_text(Text::new(input))
+    }
+
+    /// Tokenizes the specified stream of UTF-8 with this parser's lexer, without parsing
+    pub fn tokenize_utf8(&self, input: &mut dyn Read) -> TokenizeResult {
+        self.tokenize_text(Text::from_utf8_stream(input))
+    }
+
+    /// Tokenizes the specified text with this parser's lexer, without parsing
+    pub fn tokenize_text(&self, text: Text) -> TokenizeResult {
+        let mut result = TokenizeResult::new(self.terminals.clone(), text);
+        {
+            let data = result.get_lexing_data();
+            let automaton = &self.lexer_automaton;
+            match self.lexer_kind {
+                LexerKind::ContextFree => {
+                    let mut lexer =
+                        ContextFreeLexer::new(data.0, data.1, automaton, self.separator);
+                    run_lexer(&mut lexer);
+                }
+                LexerKind::ContextSensitive => {
+                    let mut lexer =
+                        ContextSensitiveLexer::new(data.0, data.1, automaton, self.separator);
+                    run_lexer(&mut lexer);
+                }
+            }
+        }
+        result
+    }
+
+    /// Parses the specified string with this parser
+    pub fn parse_string(&self, input: &str) -> ParseResult {
+        self.parse_text(Text::new(input))
+    }
+
+    /// Parses the specified stream of UTF-16 with this parser
+    pub fn parse_utf16(&self, input: &mut dyn Read, big_endian: bool) -> ParseResult {
+        self.parse_text(Text::from_utf16_stream(input, big_endian))
+    }
+
+    /// Parses the specified stream of UTF-8 with this parser
+    pub fn parse_utf8(&self, input: &mut dyn Read) -> ParseResult {
+        self.parse_text(Text::from_utf8_stream(input))
+    }
+
+    /// Parses the specified text with this parser
+    pub fn parse_text(&self, text: Text) -> ParseResult {
+        let mut result = ParseResult::new(
+            self.terminals.clone(),
+            self.variables.clone(),
+            self.virtuals.clone(),
+            text
+        );
+        let mut buffers = ParserBuffers {
+            lrk: None,
+            rnglr: None
+        };
+        self.parse_into(&mut result, &mut buffers);
+        result
+    }
+
+    /// Creates a new context for parsing many inputs with this parser while reusing allocations
+    pub fn new_context(&self) -> ParseContext {
+        ParseContext {
+            result: ParseResult::new(
+                self.terminals.clone(),
+                self.variables.clone(),
+                self.virtuals.clone(),
+                Text::new("")
+            ),
+            buffers: ParserBuffers {
+                lrk: None,
+                rnglr: None
+            }
+        }
+    }
+
+    /// Parses the specified string with this parser in a reusable context
+    /// The previous result in the context is reset and replaced by the new one.
+    pub fn parse_string_in<'c>(
+        &self,
+        context: &'c mut ParseContext,
+        input: &str
+    ) -> &'c ParseResult {
+        context.result.reset(input);
+        self.parse_into(&mut context.result, &mut context.buffers);
+        &context.result
+    }
+
+    /// Parses the specified stream of UTF-8 with this parser in a reusable context
+    /// The previous result in the context is reset and replaced by the new one.
+    pub fn parse_utf8_in<'c>(
+        &self,
+        context: &'c mut ParseContext,
+        input: &mut dyn Read
+    ) -> &'c ParseResult {
+        context.result.reset_from_utf8_stream(input);
+        self.parse_into(&mut context.result, &mut context.buffers);
+        &context.result
+    }
+
+    /// Parses the text of the specified result and fills it
+    fn parse_into(&self, result: &mut ParseResult, buffers: &mut ParserBuffers) {
+        let mut my_actions = |_index: usize, _head: Symbol, _body: &dyn SemanticBody| ();
+        let data = result.get_parsing_data();
+        let automaton = &self.lexer_automaton;
+        match self.lexer_kind {
+            LexerKind::ContextFree => {
+                let mut lexer = ContextFreeLexer::new(data.0, data.1, automaton, self.separator);
+                self.run_parser(&mut lexer, data.2, &mut my_actions, buffers);
+            }
+            LexerKind::ContextSensitive => {
+                let mut lexer =
+                    ContextSensitiveLexer::new(data.0, data.1, automaton, self.separator);
+                self.run_parser(&mut lexer, data.2, &mut my_actions, buffers);
+            }
+        }
+    }
+
+    /// Runs this parser on the specified lexer
+    fn run_parser<'l>(
+        &'l self,
+        lexer: &'l mut dyn Lexer<'l>,
+        ast: Ast<'l>,
+        actions: &'l mut dyn FnMut(usize, Symbol, &dyn SemanticBody),
+        buffers: &mut ParserBuffers
+    ) {
+        match *self.parser_automaton {
+            ParserAutomaton::LRk(ref automaton) => {
+                let previous = buffers.lrk.take().unwrap_or_default();
+                let mut parser =
+                    LRkParser::new_with_buffers(lexer, automaton, ast, actions, previous);
+                parser.parse();
+                buffers.lrk = Some(parser.into_buffers());
+            }
+            ParserAutomaton::Rnglr(ref automaton) => {
+                let previous = buffers.rnglr.take().unwrap_or_default();
+                let mut parser =
+                    RNGLRParser::new_with_buffers(lexer, automaton, ast, actions, previous);
+                parser.parse();
+                buffers.rnglr = Some(parser.into_buffers());
+            }
+        }
+    }
+}
+
+#[test]
+fn test_grammar_symbols_parse() {
+    let symbols = GrammarSymbols::parse(
+        "# test grammar\nlexer context-sensitive\nparser rnglr\nseparator 0x0003\n\nterminal 0x0001 ε\nterminal 0x0002 $\nterminal 0x0003 white space\nvariable 0x0010 expression\nvirtual 32 operator\n"
+    ).unwrap();
+    assert_eq!(symbols.lexer, LexerKind::ContextSensitive);
+    assert_eq!(symbols.parser, ParserKind::Rnglr);
+    assert_eq!(symbols.separator, 3);
+    assert_eq!(symbols.terminals.len(), 3);
+    assert_eq!(symbols.terminals[2].id, 3);
+    assert_eq!(symbols.terminals[2].name, "white space");
+    assert_eq!(symbols.variables[0].name, "expression");
+    assert_eq!(symbols.virtuals[0].id, 0x20);
+    match GrammarSymbols::parse("terminal 0x0001 ε\nterminal 0x0002") {
+        Err(LoaderError::Symbols { line, .. }) => assert_eq!(line, 2),
+        _ => panic!("expected an error at line 2")
+    }
+}
+
+#[test]
+fn test_loaded_parser_is_send_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Automaton>();
+    assert_send_sync::<LRkAutomaton>();
+    assert_send_sync::<RNGLRAutomaton>();
+    assert_send_sync::<LoadedParser>();
+}
+
+#[test]
+fn test_loaded_parser_hime_grammar() {
+    use super::symbols::SemanticElementTrait;
+    let parser = LoadedParser::load(
+        include_bytes!("../../sdk-net/Sources/Input/HimeGrammarLexer.bin"),
+        include_bytes!("../../sdk-net/Sources/Input/HimeGrammarParser.bin"),
+        include_str!("../../sdk-net/Sources/Input/HimeGrammarSymbols.txt")
+    )
+    .unwrap();
+    assert_eq!(parser.get_lexer_kind(), LexerKind::ContextFree);
+    assert_eq!(parser.get_parser_kind(), ParserKind::LRk);
+    let result = parser.parse_string(include_str!("../../sdk-net/Sources/Input/HimeGrammar.gram"));
+    assert!(result.is_success());
+    let ast = result.get_ast();
+    let root = ast.get_root();
+    assert_eq!(root.get_symbol().name, "file");
+    let grammars = root.children();
+    let grammar = grammars.at(0);
+    assert_eq!(grammar.get_symbol().name, "cf_grammar");
+    let parts = grammar.children();
+    assert_eq!(parts.len(), 5);
+    assert_eq!(parts.at(0).get_value().unwrap(), "HimeGrammar");
+    assert_eq!(parts.at(4).get_symbol().name, "BLOCK_RULES");
+}
+
+#[test]
+fn test_loaded_parser_invalid_terminals() {
+    let mut symbols = GrammarSymbols::parse(include_str!(
+        "../../sdk-net/Sources/Input/HimeGrammarSymbols.txt"
+    ))
+    .unwrap();
+    symbols.terminals.swap(0, 1);
+    let error = LoadedParser::from_symbols(
+        include_bytes!("../../sdk-net/Sources/Input/HimeGrammarLexer.bin"),
+        include_bytes!("../../sdk-net/Sources/Input/HimeGrammarParser.bin"),
+        symbols
+    )
+    .err();
+    assert_eq!(error, Some(LoaderError::Terminals));
+    let symbols = GrammarSymbols::parse("terminal 0x0001 ε\n").unwrap();
+    let error = LoadedParser::from_symbols(&[], &[], symbols).err();
+    assert_eq!(error, Some(LoaderError::Terminals));
+}
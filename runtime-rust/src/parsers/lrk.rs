@@ -17,11 +17,15 @@
 
 //! Module for LR(k) parsers
 
+use std::borrow::Cow;
+use std::io::Error;
+use std::io::Write;
 use std::usize;
 
 use super::super::ast::Ast;
 use super::super::ast::TableElemRef;
 use super::super::ast::TableType;
+use super::super::errors::AutomatonError;
 use super::super::errors::ParseErrorUnexpectedToken;
 use super::super::lexers::Lexer;
 use super::super::lexers::TokenKernel;
@@ -33,7 +37,10 @@ use super::subtree::SubTree;
 use super::*;
 
 /// Represents the LR(k) parsing table and productions
+/// The LR table is read in place from the binary data.
 pub struct LRkAutomaton {
+    /// The binary data of the automaton, without header
+    data: Cow<'static, [u8]>,
     /// The number of columns in the LR table
     columns_count: usize,
     /// The number of states in the LR table
@@ -42,46 +49,176 @@ pub struct LRkAutomaton {
     columns_map: LRColumnMap,
     /// The contexts information
     contexts: Vec<LRContexts>,
-    /// The LR table
-    table: Vec<u16>,
+    /// The offset of the LR table in the data
+    table_offset: usize,
     /// The table of LR productions
     productions: Vec<LRProduction>
 }
 
 impl LRkAutomaton {
     /// Initializes a new automaton from the given binary data
+    /// The data is trusted as is, use `try_new` for data from untrusted sources.
+    /// The header of the data, if any, is verified.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the header of the data does not match the data.
     pub fn new(data: &[u8]) -> LRkAutomaton {
-        let columns_count = read_u16(data, 0) as usize;
-        let states_count = read_u16(data, 2) as usize;
-        let productions_count = read_u16(data, 4) as usize;
-        let columns_map = LRColumnMap::new(data, 6, columns_count);
+        let automaton = strip_header(data, AutomatonKind::LRk)
+            .and_then(|(header, data)| LRkAutomaton::load(Cow::Owned(data.to_vec()), header));
+        match automaton {
+            Ok(automaton) => automaton,
+            Err(error) => panic!("{}", error)
+        }
+    }
+
+    /// Initializes a new automaton that reads its LR table in place from the given static data
+    /// The data is trusted as is and the checksum of its header, if any, is not verified.
+    /// Only the column map, the contexts and the productions, which are small, are decoded.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the header of the data does not match the data.
+    pub fn from_static(data: &'static [u8]) -> LRkAutomaton {
+        let automaton = split_header(data, AutomatonKind::LRk)
+            .and_then(|(header, data)| LRkAutomaton::load(Cow::Borrowed(data), header));
+        match automaton {
+            Ok(automaton) => automaton,
+            Err(error) => panic!("{}", error)
+        }
+    }
+
+    /// Loads an automaton from the given binary data, without a header
+    fn load(
+        data: Cow<'static, [u8]>,
+        header: Option<AutomatonHeader>
+    ) -> Result<LRkAutomaton, AutomatonError> {
+        let columns_count = read_u16(&data, 0) as usize;
+        let states_count = read_u16(&data, 2) as usize;
+        let productions_count = read_u16(&data, 4) as usize;
+        check_states_count(header, states_count)?;
+        let columns_map = LRColumnMap::new(&data, 6, columns_count);
         let mut contexts = Vec::<LRContexts>::with_capacity(states_count);
         let mut index = 6 + columns_count * 2;
         for _i in 0..states_count {
             let mut context = LRContexts::new();
-            let count = read_u16(data, index);
+            let count = read_u16(&data, index);
             index += 2;
             for _j in 0..count {
-                context.add(read_u16(data, index), read_u16(data, index + 2));
+                context.add(read_u16(&data, index), read_u16(&data, index + 2));
                 index += 4
             }
             contexts.push(context);
         }
-        let table = read_table_u16(data, index, states_count * columns_count * 2);
+        let table_offset = index;
         index += states_count * columns_count * 4;
         let mut productions = Vec::<LRProduction>::with_capacity(productions_count);
         for _i in 0..productions_count {
-            let production = LRProduction::new(data, &mut index);
+            let production = LRProduction::new(&data, &mut index);
             productions.push(production);
         }
-        LRkAutomaton {
+        Ok(LRkAutomaton {
+            data,
             columns_count,
             states_count,
             columns_map,
             contexts,
-            table,
+            table_offset,
             productions
+        })
+    }
+
+    /// Initializes a new automaton from the given binary data, checked against the parser's symbols
+    pub fn try_new(
+        data: &[u8],
+        terminals: &[Symbol],
+        variables: &[Symbol],
+        virtuals: &[Symbol]
+    ) -> Result<LRkAutomaton, AutomatonError> {
+        let (header, data) = strip_header(data, AutomatonKind::LRk)?;
+        check_table(data, "LR(k) counters", 0, 6)?;
+        let columns_count = read_u16(data, 0) as usize;
+        let states_count = read_u16(data, 2) as usize;
+        let productions_count = read_u16(data, 4) as usize;
+        if states_count == 0 {
+            // the initial state is required
+            return Err(AutomatonError::StateOutOfRange {
+                from: 0,
+                state: 0,
+                count: 0
+            });
+        }
+        check_columns(data, 6, columns_count, terminals, variables)?;
+        let mut index = check_contexts(data, 6 + columns_count * 2, states_count)?;
+        // the product of two u16 counters fits in a usize
+        index += check_array(data, "LR(k) table", index, states_count * columns_count, 4)?;
+        index = check_productions(data, index, productions_count)?;
+        check_end(data, index)?;
+        let automaton = LRkAutomaton::load(Cow::Owned(data.to_vec()), header)?;
+        for state in 0..states_count {
+            for column in 0..columns_count {
+                let action = LRAction {
+                    table: automaton.get_table(),
+                    offset: (state * columns_count + column) * 2
+                };
+                check_action(
+                    action,
+                    state as u32,
+                    column,
+                    states_count,
+                    productions_count
+                )?;
+            }
+        }
+        for (i, production) in automaton.productions.iter().enumerate() {
+            production.check(i, variables, virtuals, None)?;
+        }
+        Ok(automaton)
+    }
+
+    /// Writes this automaton in its binary form, without a header
+    pub fn write(&self) -> Vec<u8> {
+        let mut result = Vec::<u8>::new();
+        write_u16(&mut result, self.columns_count as u16);
+        write_u16(&mut result, self.states_count as u16);
+        write_u16(&mut result, self.productions.len() as u16);
+        self.columns_map.write(&mut result);
+        for context in self.contexts.iter() {
+            context.write(&mut result);
+        }
+        result.extend_from_slice(self.get_table().as_bytes());
+        for production in self.productions.iter() {
+            production.write(&mut result);
         }
+        result
+    }
+
+    /// Writes this automaton in its binary form, with a header
+    pub fn write_with_header(&self) -> Vec<u8> {
+        write_header(&self.write(), AutomatonKind::LRk, self.states_count as u32)
+    }
+
+    /// Dumps this automaton in a human-readable text form
+    /// The symbols are those of the parser, as given by the generated `TERMINALS`, `VARIABLES` and `VIRTUALS` tables.
+    pub fn dump_text(
+        &self,
+        output: &mut dyn Write,
+        terminals: &[Symbol],
+        variables: &[Symbol],
+        virtuals: &[Symbol]
+    ) -> Result<(), Error> {
+        dump_tables_text(self, output, terminals, variables, virtuals)
+    }
+
+    /// Dumps this automaton in the GraphViz DOT format
+    /// The symbols are those of the parser, as given by the generated `TERMINALS` and `VARIABLES` tables.
+    pub fn dump_dot(
+        &self,
+        output: &mut dyn Write,
+        terminals: &[Symbol],
+        variables: &[Symbol]
+    ) -> Result<(), Error> {
+        dump_tables_dot(self, output, terminals, variables)
     }
 
     /// Gets the number of states in this automaton
@@ -89,6 +226,15 @@ impl LRkAutomaton {
         self.states_count
     }
 
+    /// Gets the LR table
+    fn get_table(&self) -> TableU16<'_> {
+        TableU16::new(
+            &self.data,
+            self.table_offset,
+            self.states_count * self.columns_count * 2
+        )
+    }
+
     /// Gets the contexts opened by the specified state
     pub fn get_contexts(&self, state: u32) -> &LRContexts {
         &self.contexts[state as usize]
@@ -98,7 +244,7 @@ impl LRkAutomaton {
     pub fn get_action(&self, state: u32, identifier: u32) -> LRAction {
         let column = self.columns_map.get(identifier) as usize;
         LRAction {
-            table: &self.table,
+            table: self.get_table(),
             offset: (state as usize * self.columns_count + column) * 2
         }
     }
@@ -109,15 +255,16 @@ impl LRkAutomaton {
     }
 
     /// Gets the expected terminals for the specified state
-    pub fn get_expected(&self, state: u32, terminals: &'static [Symbol]) -> LRExpected {
+    pub fn get_expected(&self, state: u32, terminals: &[Symbol]) -> LRExpected {
         let mut expected = LRExpected::new();
+        let table = self.get_table();
         let mut offset = self.columns_count * state as usize * 2;
         for terminal in terminals.iter() {
-            let action = self.table[offset];
+            let action = table.get(offset);
             if action == LR_ACTION_CODE_SHIFT {
-                expected.shifts.push(*terminal);
+                expected.shifts.push(terminal.clone());
             } else if action == LR_ACTION_CODE_REDUCE {
-                expected.reductions.push(*terminal);
+                expected.reductions.push(terminal.clone());
             }
             offset += 2;
         }
@@ -125,6 +272,31 @@ impl LRkAutomaton {
     }
 }
 
+impl LRTables for LRkAutomaton {
+    fn get_states_count(&self) -> usize {
+        self.states_count
+    }
+
+    fn get_columns(&self) -> &LRColumnMap {
+        &self.columns_map
+    }
+
+    fn get_contexts(&self, state: u32) -> &LRContexts {
+        &self.contexts[state as usize]
+    }
+
+    fn get_actions(&self, state: usize, column: usize) -> Vec<LRAction<'_>> {
+        vec![LRAction {
+            table: self.get_table(),
+            offset: (state * self.columns_count + column) * 2
+        }]
+    }
+
+    fn get_productions(&self) -> &[LRProduction] {
+        &self.productions
+    }
+}
+
 const ESTIMATION_BIAS: usize = 5;
 
 /// The data about a reduction
@@ -155,7 +327,9 @@ struct LRkAstBuilder<'l> {
     /// The reduction handle represented as the indices of the sub-trees in the cache
     handle: Vec<usize>,
     /// The data of the current reduction
-    reduction: Option<LRkAstReduction>
+    reduction: Option<LRkAstReduction>,
+    /// The sub-trees no longer in use, kept for reuse
+    spare: Vec<SubTree>
 }
 
 impl<'l> SemanticBody for LRkAstBuilder<'l> {
@@ -168,13 +342,15 @@ impl<'l> SemanticBody for LRkAstBuilder<'l> {
                     TableType::Token => {
                         SemanticElement::Token(self.lexer.get_output().get_token(label.get_index()))
                     }
-                    TableType::Variable => {
-                        SemanticElement::Variable(self.result.get_variables()[label.get_index()])
-                    }
-                    TableType::Virtual => {
-                        SemanticElement::Virtual(self.result.get_virtuals()[label.get_index()])
+                    TableType::Variable => SemanticElement::Variable(
+                        self.result.get_variables()[label.get_index()].clone()
+                    ),
+                    TableType::Virtual => SemanticElement::Virtual(
+                        self.result.get_virtuals()[label.get_index()].clone()
+                    ),
+                    TableType::None => {
+                        SemanticElement::Terminal(self.lexer.get_terminals()[0].clone())
                     }
-                    TableType::None => SemanticElement::Terminal(self.lexer.get_terminals()[0])
                 }
             }
         }
@@ -186,25 +362,41 @@ impl<'l> SemanticBody for LRkAstBuilder<'l> {
 }
 
 impl<'l> LRkAstBuilder<'l> {
-    /// Initializes the builder with the given stack size
-    pub fn new(lexer: &'l mut Lexer<'l>, result: Ast<'l>) -> LRkAstBuilder<'l> {
+    /// Initializes the builder with the given buffers
+    pub fn new(
+        lexer: &'l mut dyn Lexer<'l>,
+        result: Ast<'l>,
+        buffers: LRkParserBuffers
+    ) -> LRkAstBuilder<'l> {
         LRkAstBuilder {
             lexer,
-            stack: Vec::<SubTree>::new(),
+            stack: buffers.semantic,
             result,
-            handle: Vec::<usize>::new(),
-            reduction: None
+            handle: buffers.handle,
+            reduction: None,
+            spare: buffers.spare
+        }
+    }
+
+    /// Gets a new sub-tree with the expected size, reusing a spare one if any
+    fn new_subtree(&mut self, size: usize) -> SubTree {
+        match self.spare.pop() {
+            None => SubTree::new(size),
+            Some(mut subtree) => {
+                subtree.reset(size);
+                subtree
+            }
         }
     }
 
     /// Gets the grammar variables for this AST
-    pub fn get_variables(&self) -> &'static [Symbol] {
+    pub fn get_variables(&self) -> &'l [Symbol] {
         self.result.get_variables()
     }
 
     /// Push a token onto the stack
     pub fn push_token(&mut self, index: usize) {
-        let mut single = SubTree::new(1);
+        let mut single = self.new_subtree(1);
         single.push(TableElemRef::new(TableType::Token, index), TREE_ACTION_NONE);
         self.stack.push(single);
     }
@@ -215,7 +407,7 @@ impl<'l> LRkAstBuilder<'l> {
         for i in 0..length {
             estimation += self.stack[self.stack.len() - length + i].get_size();
         }
-        let mut cache = SubTree::new(estimation);
+        let mut cache = self.new_subtree(estimation);
         cache.setup_root(
             TableElemRef::new(TableType::Variable, variable_index),
             action
@@ -297,7 +489,8 @@ impl<'l> LRkAstBuilder<'l> {
                     LRkAstBuilder::reduce_tree(reduction, &self.handle, &mut self.result);
                 }
                 // Put it on the stack
-                self.stack.truncate(stack_size - reduction.length);
+                let start = stack_size - reduction.length;
+                self.spare.extend(self.stack.drain(start..));
             }
         }
         let result = ::std::mem::replace(&mut self.reduction, None)
@@ -373,18 +566,18 @@ struct LRkHead {
     identifier: u32
 }
 
-struct LRkParserData<'a> {
-    /// The parser's automaton
-    automaton: LRkAutomaton,
+struct LRkParserData<'l, 'a> {
+    /// The parser's automaton, shared between parsers
+    automaton: &'l LRkAutomaton,
     /// The parser's stack
     stack: Vec<LRkHead>,
     /// The grammar variables
-    variables: &'static [Symbol],
+    variables: &'l [Symbol],
     /// The semantic actions
     actions: &'a mut FnMut(usize, Symbol, &SemanticBody)
 }
 
-impl<'a> ContextProvider for LRkParserData<'a> {
+impl<'l, 'a> ContextProvider for LRkParserData<'l, 'a> {
     /// Gets the priority of the specified context required by the specified terminal
     /// The priority is an unsigned integer. The lesser the value the higher the priority.
     /// The absence of value represents the unavailability of the required context.
@@ -463,7 +656,7 @@ impl<'a> ContextProvider for LRkParserData<'a> {
         while action.get_code() == LR_ACTION_CODE_REDUCE {
             // execute the reduction
             let production = self.automaton.get_production(action.get_data() as usize);
-            let variable = self.variables[production.head];
+            let variable = self.variables[production.head].clone();
             let length = my_stack.len();
             my_stack.truncate(length - production.reduction_length);
             // this must be a shift
@@ -491,9 +684,24 @@ impl<'a> ContextProvider for LRkParserData<'a> {
             None
         }
     }
+
+    /// Gets the contexts that are currently active, for debugging purposes
+    /// These are the contexts opened by the transitions on the parser's stack.
+    fn get_active_contexts(&self) -> Vec<u16> {
+        let mut result = vec![DEFAULT_CONTEXT];
+        for i in 1..self.stack.len() {
+            let contexts = self.automaton.get_contexts(self.stack[i - 1].state);
+            for context in contexts.get_opened_by(self.stack[i].identifier) {
+                if !result.contains(&context) {
+                    result.push(context);
+                }
+            }
+        }
+        result
+    }
 }
 
-impl<'a> LRkParserData<'a> {
+impl<'l, 'a> LRkParserData<'l, 'a> {
     /// Checks whether the specified terminal is indeed expected for a reduction
     /// This check is required because in the case of a base LALR graph,
     /// some terminals expected for reduction in the automaton are coming from other paths.
@@ -511,7 +719,7 @@ impl<'a> LRkParserData<'a> {
             if action.get_code() == LR_ACTION_CODE_REDUCE {
                 // execute the reduction
                 let production = self.automaton.get_production(action.get_data() as usize);
-                let variable = self.variables[production.head];
+                let variable = self.variables[production.head].clone();
                 let length = my_stack.len();
                 my_stack.truncate(length - production.reduction_length);
                 // this must be a shift
@@ -572,7 +780,7 @@ impl<'a> LRkParserData<'a> {
         builder: &mut LRkAstBuilder,
         actions: &mut FnMut(usize, Symbol, &SemanticBody)
     ) -> Symbol {
-        let variable = builder.get_variables()[production.head];
+        let variable = builder.get_variables()[production.head].clone();
         builder.reduction_prepare(
             production.head,
             production.reduction_length,
@@ -586,7 +794,7 @@ impl<'a> LRkParserData<'a> {
                 LR_OP_CODE_BASE_SEMANTIC_ACTION => {
                     let index = production.bytecode[i] as usize;
                     i += 1;
-                    actions(index, variable, builder);
+                    actions(index, variable.clone(), builder);
                 }
                 LR_OP_CODE_BASE_ADD_VIRTUAL => {
                     let index = production.bytecode[i] as usize;
@@ -603,10 +811,41 @@ impl<'a> LRkParserData<'a> {
     }
 }
 
+/// The buffers of a LR(k) parser
+/// A parser gives its buffers back after parsing so that another parser reuses their allocations.
+pub struct LRkParserBuffers {
+    /// The parser's stack
+    stack: Vec<LRkHead>,
+    /// The stack of semantic objects
+    semantic: Vec<SubTree>,
+    /// The reduction handle
+    handle: Vec<usize>,
+    /// The sub-trees no longer in use
+    spare: Vec<SubTree>
+}
+
+impl LRkParserBuffers {
+    /// Creates new empty buffers
+    pub fn new() -> LRkParserBuffers {
+        LRkParserBuffers {
+            stack: Vec::<LRkHead>::new(),
+            semantic: Vec::<SubTree>::new(),
+            handle: Vec::<usize>::new(),
+            spare: Vec::<SubTree>::new()
+        }
+    }
+}
+
+impl Default for LRkParserBuffers {
+    fn default() -> LRkParserBuffers {
+        LRkParserBuffers::new()
+    }
+}
+
 /// Represents a base for all LR(k) parsers
 pub struct LRkParser<'l, 'a: 'l> {
     /// The parser's data
-    data: LRkParserData<'a>,
+    data: LRkParserData<'l, 'a>,
     /// The AST builder
     builder: LRkAstBuilder<'l>
 }
@@ -615,11 +854,23 @@ impl<'l, 'a: 'l> LRkParser<'l, 'a> {
     /// Initializes a new instance of the parser
     pub fn new(
         lexer: &'l mut Lexer<'l>,
-        automaton: LRkAutomaton,
+        automaton: &'l LRkAutomaton,
         ast: Ast<'l>,
         actions: &'a mut FnMut(usize, Symbol, &SemanticBody)
     ) -> LRkParser<'l, 'a> {
-        let mut stack = Vec::<LRkHead>::new();
+        LRkParser::new_with_buffers(lexer, automaton, ast, actions, LRkParserBuffers::new())
+    }
+
+    /// Initializes a new instance of the parser that reuses the specified buffers
+    pub fn new_with_buffers(
+        lexer: &'l mut dyn Lexer<'l>,
+        automaton: &'l LRkAutomaton,
+        ast: Ast<'l>,
+        actions: &'a mut dyn FnMut(usize, Symbol, &dyn SemanticBody),
+        mut buffers: LRkParserBuffers
+    ) -> LRkParser<'l, 'a> {
+        let mut stack = ::std::mem::take(&mut buffers.stack);
+        stack.clear();
         stack.push(LRkHead {
             state: 0,
             identifier: 0
@@ -631,7 +882,21 @@ impl<'l, 'a: 'l> LRkParser<'l, 'a> {
                 variables: ast.get_variables(),
                 actions
             },
-            builder: LRkAstBuilder::new(lexer, ast)
+            builder: LRkAstBuilder::new(lexer, ast, buffers)
+        }
+    }
+
+    /// Gives back the buffers of this parser for reuse by another parser
+    pub fn into_buffers(self) -> LRkParserBuffers {
+        let mut builder = self.builder;
+        let mut spare = builder.spare;
+        spare.append(&mut builder.stack);
+        builder.handle.clear();
+        LRkParserBuffers {
+            stack: self.data.stack,
+            semantic: builder.stack,
+            handle: builder.handle,
+            spare
         }
     }
 
@@ -654,11 +919,11 @@ impl<'l, 'a: 'l> LRkParser<'l, 'a> {
         );
         let mut my_expected = Vec::<Symbol>::new();
         for x in expected_on_head.shifts.iter() {
-            my_expected.push(*x);
+            my_expected.push(x.clone());
         }
         for x in expected_on_head.reductions.iter() {
-            if self.data.check_is_expected(*x) {
-                my_expected.push(*x);
+            if self.data.check_is_expected(x.clone()) {
+                my_expected.push(x.clone());
             }
         }
         ParseErrorUnexpectedToken::new(
@@ -704,3 +969,161 @@ impl<'l, 'a> Parser for LRkParser<'l, 'a> {
         }
     }
 }
+
+#[test]
+fn test_lrk_automaton_checked() {
+    use super::super::loader::GrammarSymbols;
+    let symbols = GrammarSymbols::parse(include_str!(
+        "../../../sdk-net/Sources/Input/HimeGrammarSymbols.txt"
+    ))
+    .unwrap();
+    let data = include_bytes!("../../../sdk-net/Sources/Input/HimeGrammarParser.bin");
+    let check = |data: &[u8]| {
+        LRkAutomaton::try_new(
+            data,
+            &symbols.terminals,
+            &symbols.variables,
+            &symbols.virtuals
+        )
+    };
+    assert!(check(data).is_ok());
+    let automaton = LRkAutomaton::new(data);
+    let productions_offset =
+        automaton.table_offset + automaton.states_count * automaton.columns_count * 4;
+    // a truncated LR table
+    match check(&data[..productions_offset - 2]) {
+        Err(AutomatonError::TruncatedTable {
+            table: "LR(k) table",
+            ..
+        }) => {}
+        _ => panic!("expected a truncated table")
+    }
+    // the first action has an unknown code
+    let mut unknown = data.to_vec();
+    unknown[automaton.table_offset] = 9;
+    match check(&unknown) {
+        Err(AutomatonError::UnknownActionCode {
+            state: 0,
+            column: 0,
+            code: 9
+        }) => {}
+        _ => panic!("expected an unknown action code")
+    }
+    // the last op-code of the first production misses its argument
+    let mut overrun = data.to_vec();
+    let length = automaton.productions[0].bytecode.len();
+    assert!(length > 0);
+    overrun[productions_offset + 5 + (length - 1) * 2] = LR_OP_CODE_BASE_SEMANTIC_ACTION as u8;
+    match check(&overrun) {
+        Err(AutomatonError::BytecodeOverrun {
+            production: 0,
+            offset
+        }) => {
+            assert_eq!(offset, length - 1)
+        }
+        _ => panic!("expected a bytecode overrun")
+    }
+}
+
+#[test]
+fn test_lrk_automaton_header() {
+    use super::super::loader::GrammarSymbols;
+    let symbols = GrammarSymbols::parse(include_str!(
+        "../../../sdk-net/Sources/Input/HimeGrammarSymbols.txt"
+    ))
+    .unwrap();
+    let data = include_bytes!("../../../sdk-net/Sources/Input/HimeGrammarParser.bin");
+    let check = |data: &[u8]| {
+        LRkAutomaton::try_new(
+            data,
+            &symbols.terminals,
+            &symbols.variables,
+            &symbols.virtuals
+        )
+    };
+    let automaton = LRkAutomaton::new(data);
+    let states_count = automaton.states_count;
+    let mut with_header = automaton.write_with_header();
+    assert!(has_header(&with_header));
+    assert!(check(&with_header).is_ok());
+    assert_eq!(LRkAutomaton::new(&with_header).states_count, states_count);
+    let wrong_kind = write_header(data, AutomatonKind::Rnglr, states_count as u32);
+    match check(&wrong_kind) {
+        Err(AutomatonError::WrongKind { found: 3, .. }) => {}
+        _ => panic!("expected a wrong kind")
+    }
+    match check(&write_header(data, AutomatonKind::LRk, 1)) {
+        Err(AutomatonError::StatesCountMismatch { expected: 1, .. }) => {}
+        _ => panic!("expected a mismatch of the number of states")
+    }
+    let last = with_header.len() - 1;
+    with_header[last] ^= 1;
+    match check(&with_header) {
+        Err(AutomatonError::ChecksumMismatch { .. }) => {}
+        _ => panic!("expected a checksum mismatch")
+    }
+}
+
+#[test]
+fn test_lrk_automaton_write() {
+    let data = include_bytes!("../../../sdk-net/Sources/Input/HimeGrammarParser.bin");
+    let automaton = LRkAutomaton::new(data);
+    assert_eq!(&automaton.write()[..], &data[..]);
+    let with_header = automaton.write_with_header();
+    assert_eq!(&LRkAutomaton::new(&with_header).write()[..], &data[..]);
+    assert_eq!(
+        LRkAutomaton::from_static(data).write_with_header(),
+        with_header
+    );
+}
+
+#[test]
+fn test_lrk_automaton_dump() {
+    use super::super::loader::GrammarSymbols;
+    let symbols = GrammarSymbols::parse(include_str!(
+        "../../../sdk-net/Sources/Input/HimeGrammarSymbols.txt"
+    ))
+    .unwrap();
+    let automaton = LRkAutomaton::new(include_bytes!(
+        "../../../sdk-net/Sources/Input/HimeGrammarParser.bin"
+    ));
+    let mut text = Vec::<u8>::new();
+    automaton
+        .dump_text(
+            &mut text,
+            &symbols.terminals,
+            &symbols.variables,
+            &symbols.virtuals
+        )
+        .unwrap();
+    let text = String::from_utf8(text).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    let states = lines.iter().filter(|x| x.starts_with("State ")).count();
+    let productions = lines
+        .iter()
+        .filter(|x| x.starts_with("Production "))
+        .count();
+    assert_eq!(states, automaton.states_count);
+    assert_eq!(productions, automaton.productions.len());
+    assert_eq!(lines[0], "State 0");
+    assert!(lines.contains(&"    on ε: accept"));
+    // each production is followed by its reduction length and its bytecode
+    for (i, line) in lines.iter().enumerate() {
+        if line.starts_with("Production ") {
+            assert!(lines[i + 1].starts_with("    reduction length "));
+            assert!(lines[i + 2].starts_with("    bytecode: "));
+        }
+    }
+    let mut dot = Vec::<u8>::new();
+    automaton
+        .dump_dot(&mut dot, &symbols.terminals, &symbols.variables)
+        .unwrap();
+    let dot = String::from_utf8(dot).unwrap();
+    assert!(dot.starts_with("digraph parser {\n    node [shape=box];\n    s0 [label=\"State 0"));
+    assert!(dot.ends_with("}\n"));
+    let nodes = dot
+        .lines()
+        .filter(|x| x.contains(" [label=\"State "))
+        .count();
+    assert_eq!(nodes, automaton.states_count);
+}
@@ -21,9 +21,14 @@ pub mod lrk;
 pub mod rnglr;
 pub mod subtree;
 
+use std::io::Error;
+use std::io::Write;
+
+use super::errors::AutomatonError;
 use super::lexers::ContextProvider;
 use super::symbols::Symbol;
 use super::utils::bin::*;
+use super::utils::escape_dot;
 
 /// The maximum number of errors
 pub const MAX_ERROR_COUNT: usize = 100;
@@ -93,7 +98,9 @@ struct LRColumnMap {
     /// The cache for Ids from 0x0000 to 0x01FF
     cache: [u16; 512],
     /// Mapping for the other symbols
-    others: Vec<LRColumnMapCell>
+    others: Vec<LRColumnMapCell>,
+    /// The symbol identifiers for the columns, in order
+    identifiers: Vec<u16>
 }
 
 impl LRColumnMap {
@@ -101,16 +108,28 @@ impl LRColumnMap {
     pub fn new(data: &[u8], offset: usize, column_count: usize) -> LRColumnMap {
         let mut result = LRColumnMap {
             cache: [0; 512],
-            others: Vec::<LRColumnMapCell>::new()
+            others: Vec::<LRColumnMapCell>::new(),
+            identifiers: Vec::<u16>::with_capacity(column_count)
         };
         for i in 0..column_count {
             let index = offset + i * 2;
             let identifier = read_u16(data, index);
             result.add(i as u16, identifier);
+            result.identifiers.push(identifier);
         }
         result
     }
 
+    /// Gets the symbol identifiers for the columns, in order
+    pub fn get_identifiers(&self) -> &[u16] {
+        &self.identifiers
+    }
+
+    /// Writes this map in its binary form at the end of a byte buffer
+    pub fn write(&self, buffer: &mut Vec<u8>) {
+        write_table_u16(buffer, &self.identifiers);
+    }
+
     /// Adds a new mapping to the map
     fn add(&mut self, column: u16, identifier: u16) {
         if identifier <= 0x1FF {
@@ -191,13 +210,47 @@ impl LRContexts {
             }
         }
     }
+
+    /// Gets the contexts openings as pairs of a symbol identifier and a context
+    pub fn get_openings(&self) -> Vec<(u16, u16)> {
+        match self.openings {
+            None => Vec::new(),
+            Some(ref data) => data.iter().map(|x| (x.identifier, x.context)).collect()
+        }
+    }
+
+    /// Writes these contexts in their binary form at the end of a byte buffer
+    pub fn write(&self, buffer: &mut Vec<u8>) {
+        match self.openings {
+            None => write_u16(buffer, 0),
+            Some(ref data) => {
+                write_u16(buffer, data.len() as u16);
+                for x in data.iter() {
+                    write_u16(buffer, x.identifier);
+                    write_u16(buffer, x.context);
+                }
+            }
+        }
+    }
+
+    /// Gets the contexts opened by a transition using the specified symbol ID
+    pub fn get_opened_by(&self, identifier: u32) -> Vec<u16> {
+        match self.openings {
+            None => Vec::new(),
+            Some(ref data) => data
+                .iter()
+                .filter(|x| x.identifier as u32 == identifier)
+                .map(|x| x.context)
+                .collect()
+        }
+    }
 }
 
 /// Represents a LR action in a LR parse table
 #[derive(Copy, Clone)]
 pub struct LRAction<'a> {
     /// The automaton table
-    table: &'a [u16],
+    table: TableU16<'a>,
     /// The offset of this state within the table
     offset: usize
 }
@@ -205,14 +258,14 @@ pub struct LRAction<'a> {
 impl<'a> LRAction<'a> {
     /// Gets the action code
     pub fn get_code(&self) -> LRActionCode {
-        self.table[self.offset]
+        self.table.get(self.offset)
     }
 
     /// Gets the data associated with the action
     /// If the code is Reduce, it is the index of the LRProduction
     /// If the code is Shift, it is the index of the next state
     pub fn get_data(&self) -> u16 {
-        self.table[self.offset + 1]
+        self.table.get(self.offset + 1)
     }
 }
 
@@ -259,6 +312,388 @@ impl LRProduction {
             bytecode
         }
     }
+
+    /// Decodes the bytecode of this production into human-readable operations
+    pub fn get_operations(&self, variables: &[Symbol], virtuals: &[Symbol]) -> Vec<String> {
+        let mut result = Vec::<String>::new();
+        let mut i = 0;
+        while i < self.bytecode.len() {
+            let op_code = self.bytecode[i];
+            i += 1;
+            let base = get_op_code_base(op_code);
+            let argument = if base != LR_OP_CODE_BASE_POP_STACK && i < self.bytecode.len() {
+                i += 1;
+                self.bytecode[i - 1] as usize
+            } else {
+                0
+            };
+            let operation = match base {
+                LR_OP_CODE_BASE_POP_STACK => String::from("pop"),
+                LR_OP_CODE_BASE_ADD_VIRTUAL => {
+                    format!("virtual {}", get_symbol_name_at(virtuals, argument))
+                }
+                LR_OP_CODE_BASE_SEMANTIC_ACTION => format!("action {}", argument),
+                LR_OP_CODE_BASE_ADD_NULLABLE_VARIABLE => {
+                    format!("nullable {}", get_symbol_name_at(variables, argument))
+                }
+                _ => format!("unknown {}", op_code)
+            };
+            result.push(match get_op_code_tree_action(op_code) {
+                TREE_ACTION_NONE => operation,
+                action => format!("{} ({})", operation, get_tree_action_name(action))
+            });
+        }
+        result
+    }
+
+    /// Writes this production in its binary form at the end of a byte buffer
+    pub fn write(&self, buffer: &mut Vec<u8>) {
+        write_u16(buffer, self.head as u16);
+        buffer.push(self.head_action as u8);
+        buffer.push(self.reduction_length as u8);
+        buffer.push(self.bytecode.len() as u8);
+        write_table_u16(buffer, &self.bytecode);
+    }
+
+    /// Checks this production against the symbol tables
+    /// The number of nullable variables is only given for RNGLR productions.
+    pub fn check(
+        &self,
+        index: usize,
+        variables: &[Symbol],
+        virtuals: &[Symbol],
+        nullables: Option<usize>
+    ) -> Result<(), AutomatonError> {
+        if self.head >= variables.len() {
+            return Err(AutomatonError::VariableOutOfRange {
+                production: Some(index),
+                variable: self.head,
+                count: variables.len()
+            });
+        }
+        let mut i = 0;
+        while i < self.bytecode.len() {
+            let offset = i;
+            let op_code = self.bytecode[i];
+            i += 1;
+            let base = get_op_code_base(op_code);
+            if base == LR_OP_CODE_BASE_POP_STACK {
+                continue;
+            }
+            let has_nullables = nullables.is_some();
+            if base != LR_OP_CODE_BASE_ADD_VIRTUAL
+                && base != LR_OP_CODE_BASE_SEMANTIC_ACTION
+                && (base != LR_OP_CODE_BASE_ADD_NULLABLE_VARIABLE || !has_nullables)
+            {
+                return Err(AutomatonError::UnknownOpCode {
+                    production: index,
+                    offset,
+                    op_code
+                });
+            }
+            if i >= self.bytecode.len() {
+                return Err(AutomatonError::BytecodeOverrun {
+                    production: index,
+                    offset
+                });
+            }
+            let argument = self.bytecode[i] as usize;
+            i += 1;
+            if base == LR_OP_CODE_BASE_ADD_VIRTUAL && argument >= virtuals.len() {
+                return Err(AutomatonError::VirtualOutOfRange {
+                    production: index,
+                    virtual_index: argument,
+                    count: virtuals.len()
+                });
+            }
+            if base == LR_OP_CODE_BASE_ADD_NULLABLE_VARIABLE {
+                let count = nullables.unwrap().min(variables.len());
+                if argument >= count {
+                    return Err(AutomatonError::VariableOutOfRange {
+                        production: Some(index),
+                        variable: argument,
+                        count
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Checks the map of columns of a LR table against the symbol tables
+/// The columns for the terminals come first, in the order of the terminals.
+fn check_columns(
+    data: &[u8],
+    offset: usize,
+    columns_count: usize,
+    terminals: &[Symbol],
+    variables: &[Symbol]
+) -> Result<(), AutomatonError> {
+    check_table(data, "LR columns", offset, columns_count * 2)?;
+    for column in 0..columns_count {
+        let identifier = read_u16(data, offset + column * 2);
+        let known = if column < terminals.len() {
+            terminals[column].id == identifier as u32
+        } else {
+            variables.iter().any(|x| x.id == identifier as u32)
+        };
+        if !known {
+            return Err(AutomatonError::UnknownSymbol { column, identifier });
+        }
+    }
+    if columns_count < terminals.len() {
+        return Err(AutomatonError::TerminalOutOfRange {
+            state: 0,
+            terminal: columns_count,
+            count: terminals.len()
+        });
+    }
+    Ok(())
+}
+
+/// Checks the contexts of a LR automaton
+/// Returns the index in the data after the contexts
+fn check_contexts(
+    data: &[u8],
+    offset: usize,
+    states_count: usize
+) -> Result<usize, AutomatonError> {
+    let mut index = offset;
+    for _i in 0..states_count {
+        check_table(data, "LR contexts", index, 2)?;
+        let count = read_u16(data, index) as usize;
+        index += 2;
+        check_table(data, "LR contexts", index, count * 4)?;
+        index += count * 4;
+    }
+    Ok(index)
+}
+
+/// Checks the layout of the productions of a LR automaton
+/// Returns the index in the data after the productions
+fn check_productions(
+    data: &[u8],
+    offset: usize,
+    productions_count: usize
+) -> Result<usize, AutomatonError> {
+    let mut index = offset;
+    for _i in 0..productions_count {
+        check_table(data, "LR production", index, 5)?;
+        let bytecode_length = data[index + 4] as usize;
+        index += 5;
+        check_table(data, "LR production bytecode", index, bytecode_length * 2)?;
+        index += bytecode_length * 2;
+    }
+    Ok(index)
+}
+
+/// Checks an action in a LR table
+fn check_action(
+    action: LRAction,
+    state: u32,
+    column: usize,
+    states_count: usize,
+    productions_count: usize
+) -> Result<(), AutomatonError> {
+    match action.get_code() {
+        LR_ACTION_CODE_NONE | LR_ACTION_CODE_ACCEPT => Ok(()),
+        LR_ACTION_CODE_SHIFT if action.get_data() as usize >= states_count => {
+            Err(AutomatonError::StateOutOfRange {
+                from: state,
+                state: action.get_data() as u32,
+                count: states_count
+            })
+        }
+        LR_ACTION_CODE_REDUCE if action.get_data() as usize >= productions_count => {
+            Err(AutomatonError::ProductionOutOfRange {
+                state,
+                column,
+                production: action.get_data(),
+                count: productions_count
+            })
+        }
+        LR_ACTION_CODE_SHIFT | LR_ACTION_CODE_REDUCE => Ok(()),
+        code => Err(AutomatonError::UnknownActionCode {
+            state,
+            column,
+            code
+        })
+    }
+}
+
+/// Gets the name of the symbol at the specified index in a table for a dump
+fn get_symbol_name_at(symbols: &[Symbol], index: usize) -> String {
+    match symbols.get(index) {
+        Some(symbol) => symbol.name.to_string(),
+        None => format!("#{}", index)
+    }
+}
+
+/// Gets the name of the symbol with the specified identifier for a dump
+fn get_symbol_name(identifier: u16, terminals: &[Symbol], variables: &[Symbol]) -> String {
+    match terminals
+        .iter()
+        .chain(variables.iter())
+        .find(|x| x.id == identifier as u32)
+    {
+        Some(symbol) => symbol.name.to_string(),
+        None => format!("0x{:04X}", identifier)
+    }
+}
+
+/// Gets the name of a tree action for a dump
+fn get_tree_action_name(action: TreeAction) -> &'static str {
+    match action {
+        TREE_ACTION_NONE => "none",
+        TREE_ACTION_REPLACE_BY_CHILDREN => "replace by children",
+        TREE_ACTION_DROP => "drop",
+        TREE_ACTION_PROMOTE => "promote",
+        TREE_ACTION_REPLACE_BY_EPSILON => "replace by epsilon",
+        _ => "unknown"
+    }
+}
+
+/// The tables of a LR automaton, as seen by the dumps
+trait LRTables {
+    /// Gets the number of states
+    fn get_states_count(&self) -> usize;
+
+    /// Gets the map of the columns
+    fn get_columns(&self) -> &LRColumnMap;
+
+    /// Gets the contexts opened by the specified state
+    fn get_contexts(&self, state: u32) -> &LRContexts;
+
+    /// Gets the actions for the specified state and column
+    fn get_actions(&self, state: usize, column: usize) -> Vec<LRAction<'_>>;
+
+    /// Gets the productions
+    fn get_productions(&self) -> &[LRProduction];
+}
+
+/// Gets the description of a LR action for a dump
+fn get_action_description(
+    action: LRAction,
+    productions: &[LRProduction],
+    variables: &[Symbol]
+) -> String {
+    match action.get_code() {
+        LR_ACTION_CODE_NONE => String::from("error"),
+        LR_ACTION_CODE_SHIFT => format!("shift to {}", action.get_data()),
+        LR_ACTION_CODE_REDUCE => {
+            let head = match productions.get(action.get_data() as usize) {
+                Some(production) => get_symbol_name_at(variables, production.head),
+                None => String::from("?")
+            };
+            format!("reduce by {} ({})", action.get_data(), head)
+        }
+        LR_ACTION_CODE_ACCEPT => String::from("accept"),
+        code => format!("unknown {}", code)
+    }
+}
+
+/// Dumps the tables of a LR automaton in a human-readable text form
+fn dump_tables_text<T: LRTables>(
+    automaton: &T,
+    output: &mut dyn Write,
+    terminals: &[Symbol],
+    variables: &[Symbol],
+    virtuals: &[Symbol]
+) -> Result<(), Error> {
+    let columns = automaton.get_columns().get_identifiers();
+    let productions = automaton.get_productions();
+    for state in 0..automaton.get_states_count() {
+        writeln!(output, "State {}", state)?;
+        for (identifier, context) in automaton.get_contexts(state as u32).get_openings() {
+            writeln!(
+                output,
+                "    opens context {} on {}",
+                context,
+                get_symbol_name(identifier, terminals, variables)
+            )?;
+        }
+        for (column, identifier) in columns.iter().enumerate() {
+            for action in automaton.get_actions(state, column) {
+                if action.get_code() == LR_ACTION_CODE_NONE {
+                    continue;
+                }
+                writeln!(
+                    output,
+                    "    on {}: {}",
+                    get_symbol_name(*identifier, terminals, variables),
+                    get_action_description(action, productions, variables)
+                )?;
+            }
+        }
+    }
+    for (index, production) in productions.iter().enumerate() {
+        writeln!(
+            output,
+            "Production {}: {} ({})",
+            index,
+            get_symbol_name_at(variables, production.head),
+            get_tree_action_name(production.head_action)
+        )?;
+        writeln!(
+            output,
+            "    reduction length {}",
+            production.reduction_length
+        )?;
+        writeln!(
+            output,
+            "    bytecode: {}",
+            production.get_operations(variables, virtuals).join(", ")
+        )?;
+    }
+    Ok(())
+}
+
+/// Dumps the tables of a LR automaton in the GraphViz DOT format
+/// The shifts are represented by edges, the other actions are listed in the states.
+fn dump_tables_dot<T: LRTables>(
+    automaton: &T,
+    output: &mut dyn Write,
+    terminals: &[Symbol],
+    variables: &[Symbol]
+) -> Result<(), Error> {
+    let columns = automaton.get_columns().get_identifiers();
+    let productions = automaton.get_productions();
+    writeln!(output, "digraph parser {{")?;
+    writeln!(output, "    node [shape=box];")?;
+    for state in 0..automaton.get_states_count() {
+        let mut label = format!("State {}", state);
+        for (column, identifier) in columns.iter().enumerate() {
+            for action in automaton.get_actions(state, column) {
+                let code = action.get_code();
+                if code == LR_ACTION_CODE_NONE || code == LR_ACTION_CODE_SHIFT {
+                    continue;
+                }
+                label.push_str(&format!(
+                    "\\l{}: {}",
+                    escape_dot(&get_symbol_name(*identifier, terminals, variables)),
+                    escape_dot(&get_action_description(action, productions, variables))
+                ));
+            }
+        }
+        writeln!(output, "    s{} [label=\"{}\\l\"];", state, label)?;
+    }
+    for state in 0..automaton.get_states_count() {
+        for (column, identifier) in columns.iter().enumerate() {
+            for action in automaton.get_actions(state, column) {
+                if action.get_code() == LR_ACTION_CODE_SHIFT {
+                    writeln!(
+                        output,
+                        "    s{} -> s{} [label=\"{}\"];",
+                        state,
+                        action.get_data(),
+                        escape_dot(&get_symbol_name(*identifier, terminals, variables))
+                    )?;
+                }
+            }
+        }
+    }
+    writeln!(output, "}}")
 }
 
 /// Container for the expected terminals for a LR state
@@ -303,3 +738,37 @@ pub trait Parser {
     /// Parses the input
     fn parse(&mut self);
 }
+
+#[test]
+fn test_lr_production_get_operations() {
+    let variables = [Symbol::new_static(0x0005, "e")];
+    let virtuals = [Symbol::new_static(0x0020, "op")];
+    let production = LRProduction {
+        head: 0,
+        head_action: TREE_ACTION_NONE,
+        reduction_length: 1,
+        bytecode: vec![
+            LR_OP_CODE_BASE_POP_STACK + TREE_ACTION_DROP,
+            LR_OP_CODE_BASE_ADD_VIRTUAL + TREE_ACTION_PROMOTE,
+            0,
+            LR_OP_CODE_BASE_ADD_VIRTUAL,
+            5,
+            LR_OP_CODE_BASE_SEMANTIC_ACTION,
+            1,
+            LR_OP_CODE_BASE_ADD_NULLABLE_VARIABLE + TREE_ACTION_REPLACE_BY_CHILDREN,
+            0,
+            0x0020
+        ]
+    };
+    assert_eq!(
+        production.get_operations(&variables, &virtuals),
+        vec![
+            "pop (drop)",
+            "virtual op (promote)",
+            "virtual #5",
+            "action 1",
+            "nullable e (replace by children)",
+            "unknown 32"
+        ]
+    );
+}
@@ -17,13 +17,18 @@
 
 //! Module for RNGLR parsers
 
+use std::borrow::Cow;
+use std::collections::HashSet;
 use std::collections::VecDeque;
+use std::io::Error;
+use std::io::Write;
 use std::usize;
 
 use super::super::ast::Ast;
 use super::super::ast::AstCell;
 use super::super::ast::TableElemRef;
 use super::super::ast::TableType;
+use super::super::errors::AutomatonError;
 use super::super::errors::ParseErrorUnexpectedToken;
 use super::super::lexers::Lexer;
 use super::super::lexers::TokenKernel;
@@ -45,7 +50,10 @@ struct RNGLRAutomatonCell {
 }
 
 /// Represents the RNGLR parsing table and productions
+/// The RNGLR table, the actions and the nullables are read in place from the binary data.
 pub struct RNGLRAutomaton {
+    /// The binary data of the automaton, without header
+    data: Cow<'static, [u8]>,
     /// Index of the axiom variable
     axiom: usize,
     /// The number of columns in the LR table
@@ -56,74 +64,269 @@ pub struct RNGLRAutomaton {
     columns_map: LRColumnMap,
     /// The contexts information
     contexts: Vec<LRContexts>,
-    /// The RNGLR table
-    cells: Vec<RNGLRAutomatonCell>,
-    /// The LR action table
-    table: Vec<u16>,
+    /// The offset of the RNGLR table in the data
+    cells_offset: usize,
+    /// The offset of the LR action table in the data
+    actions_offset: usize,
+    /// The number of actions in the LR action table
+    actions_count: usize,
     /// The table of LR productions
     productions: Vec<LRProduction>,
-    /// The table of nullable variables
-    nullables: Vec<u16>
+    /// The offset of the table of nullable variables in the data
+    nullables_offset: usize,
+    /// The number of nullable variables
+    nullables_count: usize
 }
 
 impl RNGLRAutomaton {
     /// Initializes a new automaton from the given binary data
+    /// The data is trusted as is, use `try_new` for data from untrusted sources.
+    /// The header of the data, if any, is verified.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the header of the data does not match the data.
     pub fn new(data: &[u8]) -> RNGLRAutomaton {
+        let automaton = strip_header(data, AutomatonKind::Rnglr)
+            .and_then(|(header, data)| RNGLRAutomaton::load(Cow::Owned(data.to_vec()), header));
+        match automaton {
+            Ok(automaton) => automaton,
+            Err(error) => panic!("{}", error)
+        }
+    }
+
+    /// Initializes a new automaton that reads its tables in place from the given static data
+    /// The data is trusted as is and the checksum of its header, if any, is not verified.
+    /// Only the column map, the contexts and the productions, which are small, are decoded.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the header of the data does not match the data.
+    pub fn from_static(data: &'static [u8]) -> RNGLRAutomaton {
+        let automaton = split_header(data, AutomatonKind::Rnglr)
+            .and_then(|(header, data)| RNGLRAutomaton::load(Cow::Borrowed(data), header));
+        match automaton {
+            Ok(automaton) => automaton,
+            Err(error) => panic!("{}", error)
+        }
+    }
+
+    /// Loads an automaton from the given binary data, without a header
+    fn load(
+        data: Cow<'static, [u8]>,
+        header: Option<AutomatonHeader>
+    ) -> Result<RNGLRAutomaton, AutomatonError> {
         // read basic counters
-        let axiom_index = read_u16(data, 0) as usize;
-        let columns_count = read_u16(data, 2) as usize;
-        let states_count = read_u16(data, 4) as usize;
-        let actions_count = read_u32(data, 6) as usize;
-        let productions_count = read_u16(data, 10) as usize;
-        let nullables_count = read_u16(data, 12) as usize;
+        let axiom_index = read_u16(&data, 0) as usize;
+        let columns_count = read_u16(&data, 2) as usize;
+        let states_count = read_u16(&data, 4) as usize;
+        let actions_count = read_u32(&data, 6) as usize;
+        let productions_count = read_u16(&data, 10) as usize;
+        let nullables_count = read_u16(&data, 12) as usize;
+        check_states_count(header, states_count)?;
         // reads the column map
-        let columns_map = LRColumnMap::new(data, 14, columns_count);
+        let columns_map = LRColumnMap::new(&data, 14, columns_count);
         // read the contexts table
         let mut contexts = Vec::<LRContexts>::with_capacity(states_count);
         let mut index = 14 + columns_count * 2;
         for _i in 0..states_count {
             let mut context = LRContexts::new();
-            let count = read_u16(data, index);
+            let count = read_u16(&data, index);
             index += 2;
             for _j in 0..count {
-                context.add(read_u16(data, index), read_u16(data, index + 2));
+                context.add(read_u16(&data, index), read_u16(&data, index + 2));
                 index += 4
             }
             contexts.push(context);
         }
-        // read the automaton cells
-        let mut cells = Vec::<RNGLRAutomatonCell>::with_capacity(columns_count * states_count);
-        for _i in 0..(columns_count * states_count) {
-            cells.push(RNGLRAutomatonCell {
-                count: read_u16(data, index) as u32,
-                index: read_u32(data, index + 2)
-            });
-            index += 6;
-        }
-        // read the actions table for the automaton
-        let table = read_table_u16(data, index, actions_count * 2);
+        // skip the automaton cells
+        let cells_offset = index;
+        index += columns_count * states_count * 6;
+        // skip the actions table for the automaton
+        let actions_offset = index;
         index += actions_count * 4;
         // read the production table
         let mut productions = Vec::<LRProduction>::with_capacity(productions_count);
         for _i in 0..productions_count {
-            let production = LRProduction::new(data, &mut index);
+            let production = LRProduction::new(&data, &mut index);
             productions.push(production);
         }
-        // read the nullables table
-        let nullables = read_table_u16(data, index, nullables_count);
+        // skip the nullables table
+        let nullables_offset = index;
         index += nullables_count * 2;
         assert_eq!(index, data.len());
-        RNGLRAutomaton {
+        Ok(RNGLRAutomaton {
+            data,
             axiom: axiom_index,
             columns_count,
             states_count,
             columns_map,
             contexts,
-            cells,
-            table,
+            cells_offset,
+            actions_offset,
+            actions_count,
             productions,
-            nullables
+            nullables_offset,
+            nullables_count
+        })
+    }
+
+    /// Initializes a new automaton from the given binary data, checked against the parser's symbols
+    pub fn try_new(
+        data: &[u8],
+        terminals: &[Symbol],
+        variables: &[Symbol],
+        virtuals: &[Symbol]
+    ) -> Result<RNGLRAutomaton, AutomatonError> {
+        let (header, data) = strip_header(data, AutomatonKind::Rnglr)?;
+        // check the basic counters
+        check_table(data, "RNGLR counters", 0, 14)?;
+        let axiom_index = read_u16(data, 0) as usize;
+        let columns_count = read_u16(data, 2) as usize;
+        let states_count = read_u16(data, 4) as usize;
+        let actions_count = read_u32(data, 6) as usize;
+        let productions_count = read_u16(data, 10) as usize;
+        let nullables_count = read_u16(data, 12) as usize;
+        if axiom_index >= variables.len() {
+            return Err(AutomatonError::VariableOutOfRange {
+                production: None,
+                variable: axiom_index,
+                count: variables.len()
+            });
+        }
+        if states_count == 0 {
+            // the initial state is required
+            return Err(AutomatonError::StateOutOfRange {
+                from: 0,
+                state: 0,
+                count: 0
+            });
+        }
+        // check the layout of the tables
+        check_columns(data, 14, columns_count, terminals, variables)?;
+        let mut index = check_contexts(data, 14 + columns_count * 2, states_count)?;
+        // the product of two u16 counters fits in a usize
+        index += check_array(data, "RNGLR cells", index, columns_count * states_count, 6)?;
+        let actions_offset = index;
+        index += check_array(data, "RNGLR actions", index, actions_count, 4)?;
+        index = check_productions(data, index, productions_count)?;
+        index += check_array(data, "RNGLR nullables", index, nullables_count, 2)?;
+        check_end(data, index)?;
+        // check the content of the tables
+        let automaton = RNGLRAutomaton::load(Cow::Owned(data.to_vec()), header)?;
+        for state in 0..states_count {
+            for column in 0..columns_count {
+                let cell = automaton.get_cell(state * columns_count + column);
+                if cell.index as usize + cell.count as usize > actions_count {
+                    return Err(AutomatonError::TruncatedTable {
+                        table: "RNGLR actions",
+                        offset: actions_offset,
+                        required: (cell.index as usize + cell.count as usize) * 4,
+                        available: actions_count * 4
+                    });
+                }
+                for i in 0..cell.count as usize {
+                    let action = LRAction {
+                        table: automaton.get_table(),
+                        offset: (cell.index as usize + i) * 2
+                    };
+                    check_action(
+                        action,
+                        state as u32,
+                        column,
+                        states_count,
+                        productions_count
+                    )?;
+                }
+            }
+        }
+        for (i, production) in automaton.productions.iter().enumerate() {
+            production.check(i, variables, virtuals, Some(nullables_count))?;
+        }
+        let nullables = automaton.get_nullables();
+        for variable in 0..nullables.len() {
+            let production = nullables.get(variable);
+            if production != 0xFFFF && production as usize >= productions_count {
+                return Err(AutomatonError::NullableOutOfRange {
+                    variable,
+                    production,
+                    count: productions_count
+                });
+            }
+        }
+        Ok(automaton)
+    }
+
+    /// Writes this automaton in its binary form, without a header
+    pub fn write(&self) -> Vec<u8> {
+        let mut result = Vec::<u8>::new();
+        // write basic counters
+        write_u16(&mut result, self.axiom as u16);
+        write_u16(&mut result, self.columns_count as u16);
+        write_u16(&mut result, self.states_count as u16);
+        write_u32(&mut result, self.actions_count as u32);
+        write_u16(&mut result, self.productions.len() as u16);
+        write_u16(&mut result, self.nullables_count as u16);
+        // write the column map and the contexts table
+        self.columns_map.write(&mut result);
+        for context in self.contexts.iter() {
+            context.write(&mut result);
+        }
+        // write the automaton cells and the actions table
+        let cells_length = self.columns_count * self.states_count * 6;
+        result.extend_from_slice(&self.data[self.cells_offset..(self.cells_offset + cells_length)]);
+        result.extend_from_slice(self.get_table().as_bytes());
+        // write the production and nullables tables
+        for production in self.productions.iter() {
+            production.write(&mut result);
+        }
+        result.extend_from_slice(self.get_nullables().as_bytes());
+        result
+    }
+
+    /// Writes this automaton in its binary form, with a header
+    pub fn write_with_header(&self) -> Vec<u8> {
+        write_header(
+            &self.write(),
+            AutomatonKind::Rnglr,
+            self.states_count as u32
+        )
+    }
+
+    /// Dumps this automaton in a human-readable text form
+    /// The symbols are those of the parser, as given by the generated `TERMINALS`, `VARIABLES` and `VIRTUALS` tables.
+    pub fn dump_text(
+        &self,
+        output: &mut dyn Write,
+        terminals: &[Symbol],
+        variables: &[Symbol],
+        virtuals: &[Symbol]
+    ) -> Result<(), Error> {
+        dump_tables_text(self, output, terminals, variables, virtuals)?;
+        let nullables = self.get_nullables();
+        for variable in 0..nullables.len() {
+            let production = nullables.get(variable);
+            if production != 0xFFFF {
+                writeln!(
+                    output,
+                    "Nullable {}: production {}",
+                    get_symbol_name_at(variables, variable),
+                    production
+                )?;
+            }
         }
+        Ok(())
+    }
+
+    /// Dumps this automaton in the GraphViz DOT format
+    /// The symbols are those of the parser, as given by the generated `TERMINALS` and `VARIABLES` tables.
+    pub fn dump_dot(
+        &self,
+        output: &mut dyn Write,
+        terminals: &[Symbol],
+        variables: &[Symbol]
+    ) -> Result<(), Error> {
+        dump_tables_dot(self, output, terminals, variables)
     }
 
     /// Gets the index of the axiom
@@ -141,18 +344,38 @@ impl RNGLRAutomaton {
         &self.contexts[state as usize]
     }
 
+    /// Gets the cell at the specified index in the RNGLR table
+    fn get_cell(&self, index: usize) -> RNGLRAutomatonCell {
+        let offset = self.cells_offset + index * 6;
+        RNGLRAutomatonCell {
+            count: read_u16(&self.data, offset) as u32,
+            index: read_u32(&self.data, offset + 2)
+        }
+    }
+
+    /// Gets the LR action table
+    fn get_table(&self) -> TableU16<'_> {
+        TableU16::new(&self.data, self.actions_offset, self.actions_count * 2)
+    }
+
+    /// Gets the table of nullable variables
+    fn get_nullables(&self) -> TableU16<'_> {
+        TableU16::new(&self.data, self.nullables_offset, self.nullables_count)
+    }
+
     /// Gets the number of GLR actions for the given state and symbol identifier
     pub fn get_actions_count(&self, state: u32, identifier: u32) -> usize {
         let column = self.columns_map.get(identifier) as usize;
-        self.cells[state as usize * self.columns_count + column].count as usize
+        let cell = self.get_cell(state as usize * self.columns_count + column);
+        cell.count as usize
     }
 
     /// Gets the i-th GLR action for the given state and sid
     pub fn get_action(&self, state: u32, identifier: u32, index: usize) -> LRAction {
         let column = self.columns_map.get(identifier) as usize;
-        let cell = self.cells[state as usize * self.columns_count + column];
+        let cell = self.get_cell(state as usize * self.columns_count + column);
         LRAction {
-            table: &self.table,
+            table: self.get_table(),
             offset: (cell.index as usize + index) * 2
         }
     }
@@ -164,7 +387,7 @@ impl RNGLRAutomaton {
 
     /// Gets the production for the nullable variable with the given index
     pub fn get_nullable_production(&self, index: usize) -> Option<&LRProduction> {
-        match self.nullables[index] {
+        match self.get_nullables().get(index) {
             0xFFFF => None,
             prod_index => Some(&self.productions[prod_index as usize])
         }
@@ -172,28 +395,28 @@ impl RNGLRAutomaton {
 
     /// Determine whether the given state is the accepting state
     pub fn is_accepting_state(&self, state: u32) -> bool {
-        let cell = self.cells[state as usize * self.columns_count];
+        let cell = self.get_cell(state as usize * self.columns_count);
         if cell.count != 1 {
             false
         } else {
-            self.table[(cell.index as usize) * 2] == LR_ACTION_CODE_ACCEPT
+            self.get_table().get((cell.index as usize) * 2) == LR_ACTION_CODE_ACCEPT
         }
     }
 
     /// Gets the expected terminals for the specified state
-    pub fn get_expected(&self, state: u32, terminals: &'static [Symbol]) -> LRExpected {
+    pub fn get_expected(&self, state: u32, terminals: &[Symbol]) -> LRExpected {
         let mut expected = LRExpected::new();
         for (column, terminal) in terminals.iter().enumerate() {
-            let cell = self.cells[state as usize * self.columns_count + column];
+            let cell = self.get_cell(state as usize * self.columns_count + column);
             for i in 0..cell.count as usize {
                 let action = LRAction {
-                    table: &self.table,
+                    table: self.get_table(),
                     offset: (cell.index as usize + i) * 2
                 };
                 if action.get_code() == LR_ACTION_CODE_SHIFT {
-                    expected.add_unique_shift(*terminal);
+                    expected.add_unique_shift(terminal.clone());
                 } else if action.get_code() == LR_ACTION_CODE_REDUCE {
-                    expected.add_unique_reduction(*terminal);
+                    expected.add_unique_reduction(terminal.clone());
                 }
             }
         }
@@ -201,6 +424,34 @@ impl RNGLRAutomaton {
     }
 }
 
+impl LRTables for RNGLRAutomaton {
+    fn get_states_count(&self) -> usize {
+        self.states_count
+    }
+
+    fn get_columns(&self) -> &LRColumnMap {
+        &self.columns_map
+    }
+
+    fn get_contexts(&self, state: u32) -> &LRContexts {
+        &self.contexts[state as usize]
+    }
+
+    fn get_actions(&self, state: usize, column: usize) -> Vec<LRAction<'_>> {
+        let cell = self.get_cell(state * self.columns_count + column);
+        (0..cell.count as usize)
+            .map(|i| LRAction {
+                table: self.get_table(),
+                offset: (cell.index as usize + i) * 2
+            })
+            .collect()
+    }
+
+    fn get_productions(&self) -> &[LRProduction] {
+        &self.productions
+    }
+}
+
 /// Represents a label for a GSS edge
 #[derive(Copy, Clone)]
 struct GSSLabel {
@@ -323,6 +574,15 @@ impl GSS {
         }
     }
 
+    /// Clears this GSS for reuse, keeping the allocated storage
+    pub fn clear(&mut self) {
+        self.node_labels.clear();
+        self.node_generations.clear();
+        self.edges.clear();
+        self.edges_generations.clear();
+        self.current_generation = 0;
+    }
+
     /// Gets the data of the current generation
     pub fn get_current_generation(&self) -> GSSGeneration {
         self.node_generations[self.current_generation]
@@ -361,6 +621,15 @@ impl GSS {
         false
     }
 
+    /// Gets the edges starting from the specified node
+    pub fn get_edges_from(&self, node: usize) -> Vec<GSSEdge> {
+        let data = self.edges_generations[self.get_generation_of(node)];
+        (data.start..(data.start + data.count))
+            .map(|i| self.edges[i])
+            .filter(|edge| edge.from as usize == node)
+            .collect()
+    }
+
     /// Opens a new generation in this GSS
     pub fn create_generation(&mut self) -> usize {
         self.node_generations.push(GSSGeneration {
@@ -706,6 +975,11 @@ impl SPPF {
         }
     }
 
+    /// Clears this SPPF for reuse
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+    }
+
     /// Gets the SPPF node for the specified identifier
     pub fn get_node(&self, identifier: usize) -> &SPPFNode {
         &self.nodes[identifier]
@@ -814,12 +1088,12 @@ impl<'l> SemanticBody for SPPFBuilder<'l> {
                 SemanticElement::Token(self.lexer.get_output().get_token(label.get_index()))
             }
             TableType::Variable => {
-                SemanticElement::Variable(self.result.get_variables()[label.get_index()])
+                SemanticElement::Variable(self.result.get_variables()[label.get_index()].clone())
             }
             TableType::Virtual => {
-                SemanticElement::Virtual(self.result.get_virtuals()[label.get_index()])
+                SemanticElement::Virtual(self.result.get_virtuals()[label.get_index()].clone())
             }
-            TableType::None => SemanticElement::Terminal(self.lexer.get_terminals()[0])
+            TableType::None => SemanticElement::Terminal(self.lexer.get_terminals()[0].clone())
         }
     }
 
@@ -833,19 +1107,24 @@ impl<'l> SemanticBody for SPPFBuilder<'l> {
 }
 
 impl<'l> SPPFBuilder<'l> {
-    /// Initializes the builder with the given stack size
-    pub fn new(lexer: &'l mut Lexer<'l>, result: Ast<'l>) -> SPPFBuilder<'l> {
+    /// Initializes the builder with the given history and SPPF
+    pub fn new(
+        lexer: &'l mut dyn Lexer<'l>,
+        result: Ast<'l>,
+        history: Vec<HistoryPart>,
+        sppf: SPPF
+    ) -> SPPFBuilder<'l> {
         SPPFBuilder {
             lexer,
-            history: Vec::<HistoryPart>::new(),
-            sppf: SPPF::new(),
+            history,
+            sppf,
             reduction: None,
             result
         }
     }
 
     /// Gets the grammar variables for this AST
-    pub fn get_variables(&self) -> &'static [Symbol] {
+    pub fn get_variables(&self) -> &'l [Symbol] {
         self.result.get_variables()
     }
 
@@ -1189,9 +1468,9 @@ struct RNGLRShift {
     to: usize
 }
 
-struct RNGLRParserData<'a> {
-    /// The parser's automaton
-    automaton: RNGLRAutomaton,
+struct RNGLRParserData<'l, 'a> {
+    /// The parser's automaton, shared between parsers
+    automaton: &'l RNGLRAutomaton,
     /// The GSS for this parser
     gss: GSS,
     /// The next token
@@ -1201,12 +1480,12 @@ struct RNGLRParserData<'a> {
     /// The queue of shift operations
     shifts: VecDeque<RNGLRShift>,
     /// The grammar variables
-    variables: &'static [Symbol],
+    variables: &'l [Symbol],
     /// The semantic actions
     actions: &'a mut FnMut(usize, Symbol, &SemanticBody)
 }
 
-impl<'a> ContextProvider for RNGLRParserData<'a> {
+impl<'l, 'a> ContextProvider for RNGLRParserData<'l, 'a> {
     /// Gets the priority of the specified context required by the specified terminal
     /// The priority is an unsigned integer. The lesser the value the higher the priority.
     /// The absence of value represents the unavailability of the required context.
@@ -1417,9 +1696,39 @@ impl<'a> ContextProvider for RNGLRParserData<'a> {
         // the context is still unavailable
         None
     }
+
+    /// Gets the contexts that are currently active, for debugging purposes
+    /// These are the contexts opened by the transitions on the GSS paths from the current generation.
+    fn get_active_contexts(&self) -> Vec<u16> {
+        let mut result = vec![DEFAULT_CONTEXT];
+        let generation = self.gss.get_current_generation();
+        let mut visited: HashSet<usize> = HashSet::new();
+        let mut queue: VecDeque<usize> = VecDeque::new();
+        for node in generation.start..(generation.start + generation.count) {
+            if visited.insert(node) {
+                queue.push_back(node);
+            }
+        }
+        while let Some(node) = queue.pop_front() {
+            for edge in self.gss.get_edges_from(node) {
+                let contexts = self
+                    .automaton
+                    .get_contexts(self.gss.get_represented_state(edge.to as usize));
+                for context in contexts.get_opened_by(edge.label.symbol_id) {
+                    if !result.contains(&context) {
+                        result.push(context);
+                    }
+                }
+                if visited.insert(edge.to as usize) {
+                    queue.push_back(edge.to as usize);
+                }
+            }
+        }
+        result
+    }
 }
 
-impl<'a> RNGLRParserData<'a> {
+impl<'l, 'a> RNGLRParserData<'l, 'a> {
     /// Gets the terminal's identifier for the next token
     fn get_next_token_id(&self) -> u32 {
         match &self.next_token {
@@ -1616,10 +1925,56 @@ impl<'a> RNGLRParserData<'a> {
     }
 }
 
+/// The GSS, SPPF and operation queues of a RNGLR parser, kept for reuse between parses
+pub struct RNGLRParserBuffers {
+    /// The GSS
+    gss: GSS,
+    /// The SPPF
+    sppf: SPPF,
+    /// The history
+    history: Vec<HistoryPart>,
+    /// The queue of reduction operations
+    reductions: VecDeque<RNGLRReduction>,
+    /// The queue of shift operations
+    shifts: VecDeque<RNGLRShift>,
+    /// The sub-trees for the constant nullable variables
+    nullables: Vec<usize>
+}
+
+impl RNGLRParserBuffers {
+    /// Creates new empty buffers
+    pub fn new() -> RNGLRParserBuffers {
+        RNGLRParserBuffers {
+            gss: GSS::new(),
+            sppf: SPPF::new(),
+            history: Vec::<HistoryPart>::new(),
+            reductions: VecDeque::<RNGLRReduction>::new(),
+            shifts: VecDeque::<RNGLRShift>::new(),
+            nullables: Vec::<usize>::new()
+        }
+    }
+
+    /// Clears these buffers, keeping the allocated storage
+    fn clear(&mut self) {
+        self.gss.clear();
+        self.sppf.clear();
+        self.history.clear();
+        self.reductions.clear();
+        self.shifts.clear();
+        self.nullables.clear();
+    }
+}
+
+impl Default for RNGLRParserBuffers {
+    fn default() -> RNGLRParserBuffers {
+        RNGLRParserBuffers::new()
+    }
+}
+
 /// Represents a base for all RNGLR parsers
 pub struct RNGLRParser<'l, 'a: 'l> {
     /// The parser's data
-    data: RNGLRParserData<'a>,
+    data: RNGLRParserData<'l, 'a>,
     /// The AST builder
     builder: SPPFBuilder<'l>,
     /// The sub-trees for the constant nullable variables
@@ -1630,22 +1985,34 @@ impl<'l, 'a: 'l> RNGLRParser<'l, 'a> {
     /// Initializes a new instance of the parser
     pub fn new(
         lexer: &'l mut Lexer<'l>,
-        automaton: RNGLRAutomaton,
+        automaton: &'l RNGLRAutomaton,
         ast: Ast<'l>,
         actions: &'a mut FnMut(usize, Symbol, &SemanticBody)
     ) -> RNGLRParser<'l, 'a> {
+        RNGLRParser::new_with_buffers(lexer, automaton, ast, actions, RNGLRParserBuffers::new())
+    }
+
+    /// Initializes a new instance of the parser that reuses the specified buffers
+    pub fn new_with_buffers(
+        lexer: &'l mut dyn Lexer<'l>,
+        automaton: &'l RNGLRAutomaton,
+        ast: Ast<'l>,
+        actions: &'a mut dyn FnMut(usize, Symbol, &dyn SemanticBody),
+        mut buffers: RNGLRParserBuffers
+    ) -> RNGLRParser<'l, 'a> {
+        buffers.clear();
         let mut parser = RNGLRParser {
             data: RNGLRParserData {
                 automaton,
-                gss: GSS::new(),
+                gss: buffers.gss,
                 next_token: None,
-                reductions: VecDeque::<RNGLRReduction>::new(),
-                shifts: VecDeque::<RNGLRShift>::new(),
+                reductions: buffers.reductions,
+                shifts: buffers.shifts,
                 variables: ast.get_variables(),
                 actions
             },
-            builder: SPPFBuilder::new(lexer, ast),
-            nullables: Vec::<usize>::new()
+            builder: SPPFBuilder::new(lexer, ast, buffers.history, buffers.sppf),
+            nullables: buffers.nullables
         };
         RNGLRParser::build_nullables(
             &mut parser.builder,
@@ -1657,6 +2024,20 @@ impl<'l, 'a: 'l> RNGLRParser<'l, 'a> {
         parser
     }
 
+    /// Gives back the buffers of this parser for reuse by another parser
+    pub fn into_buffers(self) -> RNGLRParserBuffers {
+        let mut buffers = RNGLRParserBuffers {
+            gss: self.data.gss,
+            sppf: self.builder.sppf,
+            history: self.builder.history,
+            reductions: self.data.reductions,
+            shifts: self.data.shifts,
+            nullables: self.nullables
+        };
+        buffers.clear();
+        buffers
+    }
+
     /// Builds the constant sub-trees of nullable variables
     fn build_nullables(
         builder: &mut SPPFBuilder<'l>,
@@ -1778,7 +2159,7 @@ impl<'l, 'a: 'l> RNGLRParser<'l, 'a> {
         first: GSSLabel,
         path: &GSSPath
     ) -> usize {
-        let variable = builder.get_variables()[production.head];
+        let variable = builder.get_variables()[production.head].clone();
         builder.reduction_prepare(first, path, production.reduction_length);
         let mut i = 0;
         while i < production.bytecode.len() {
@@ -1788,7 +2169,7 @@ impl<'l, 'a: 'l> RNGLRParser<'l, 'a> {
                 LR_OP_CODE_BASE_SEMANTIC_ACTION => {
                     let index = production.bytecode[i] as usize;
                     i += 1;
-                    actions(index, variable, builder);
+                    actions(index, variable.clone(), builder);
                 }
                 LR_OP_CODE_BASE_ADD_VIRTUAL => {
                     let index = production.bytecode[i] as usize;
@@ -1854,7 +2235,7 @@ impl<'l, 'a: 'l> RNGLRParser<'l, 'a> {
     ) {
         let production = self.data.automaton.get_production(reduction.production);
         // Get the rule's head
-        let head = self.data.variables[production.head];
+        let head = self.data.variables[production.head].clone();
         // Resolve the sub-root
         let maybe_sppf = self.builder.get_label_for(
             path.generation,
@@ -2002,7 +2383,7 @@ impl<'l, 'a: 'l> RNGLRParser<'l, 'a> {
             // register the terminals for shift actions
             for symbol in expected_on_head.shifts.iter() {
                 if !my_expected.contains(symbol) {
-                    my_expected.push(*symbol);
+                    my_expected.push(symbol.clone());
                 }
             }
             if i < stem {
@@ -2011,9 +2392,9 @@ impl<'l, 'a: 'l> RNGLRParser<'l, 'a> {
                     if !my_expected.contains(symbol)
                         && self
                             .data
-                            .check_is_expected(generation_data.start + i, *symbol)
+                            .check_is_expected(generation_data.start + i, symbol.clone())
                     {
-                        my_expected.push(*symbol);
+                        my_expected.push(symbol.clone());
                     }
                 }
             }
@@ -2096,3 +2477,361 @@ impl<'l, 'a> Parser for RNGLRParser<'l, 'a> {
         return;
     }
 }
+
+/// Builds the data of a RNGLR automaton for an ambiguous grammar of expressions
+/// The terminals are ε, $, NAME (0x0003) and `|` (0x0004),
+/// the variables are `e` (0x0005) and the axiom (0x0006).
+/// The grammar is `e -> e '|' e | NAME` and the state 5 has a shift/reduce conflict on `|`.
+/// A NAME after `|` opens the lexical context 1.
+#[cfg(test)]
+pub fn build_ambiguous_automaton() -> Vec<u8> {
+    // the actions as (state, column, code, data), ordered by state and column
+    let actions: [(usize, usize, u16, u16); 12] = [
+        (0, 2, LR_ACTION_CODE_SHIFT, 1),
+        (0, 4, LR_ACTION_CODE_SHIFT, 2),
+        (1, 1, LR_ACTION_CODE_REDUCE, 2),
+        (1, 3, LR_ACTION_CODE_REDUCE, 2),
+        (2, 1, LR_ACTION_CODE_SHIFT, 3),
+        (2, 3, LR_ACTION_CODE_SHIFT, 4),
+        (3, 0, LR_ACTION_CODE_ACCEPT, 0),
+        (4, 2, LR_ACTION_CODE_SHIFT, 1),
+        (4, 4, LR_ACTION_CODE_SHIFT, 5),
+        (5, 1, LR_ACTION_CODE_REDUCE, 1),
+        (5, 3, LR_ACTION_CODE_SHIFT, 4),
+        (5, 3, LR_ACTION_CODE_REDUCE, 1)
+    ];
+    // the productions as (head, reduction length)
+    let productions: [(u16, u8); 3] = [(1, 2), (0, 3), (0, 1)];
+    let mut data = Vec::<u8>::new();
+    // the axiom, the numbers of columns, states, actions, productions and nullables
+    for value in [1u16, 6, 6].iter() {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    data.extend_from_slice(&(actions.len() as u32).to_le_bytes());
+    data.extend_from_slice(&(productions.len() as u16).to_le_bytes());
+    data.extend_from_slice(&2u16.to_le_bytes());
+    // the columns, then the contexts of each state, only the state 4 opens the context 1 on NAME
+    for value in [1u16, 2, 3, 4, 5, 6, 0, 0, 0, 0, 1, 3, 1, 0].iter() {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    for state in 0..6 {
+        for column in 0..6 {
+            let first = actions
+                .iter()
+                .position(|&(s, c, _, _)| s == state && c == column)
+                .unwrap_or(0);
+            let count = actions
+                .iter()
+                .filter(|&&(s, c, _, _)| s == state && c == column)
+                .count();
+            data.extend_from_slice(&(count as u16).to_le_bytes());
+            data.extend_from_slice(&(first as u32).to_le_bytes());
+        }
+    }
+    for &(_, _, code, target) in actions.iter() {
+        data.extend_from_slice(&code.to_le_bytes());
+        data.extend_from_slice(&target.to_le_bytes());
+    }
+    for &(head, length) in productions.iter() {
+        data.extend_from_slice(&head.to_le_bytes());
+        data.extend_from_slice(&[TREE_ACTION_NONE as u8, length, length]);
+        for _i in 0..length {
+            data.extend_from_slice(&LR_OP_CODE_BASE_POP_STACK.to_le_bytes());
+        }
+    }
+    data.extend_from_slice(&[0xFF, 0xFF, 0xFF, 0xFF]);
+    data
+}
+
+/// Gets the terminals, the variables and the virtuals of the ambiguous grammar of expressions
+#[cfg(test)]
+pub fn get_ambiguous_symbols() -> (Vec<Symbol>, Vec<Symbol>, Vec<Symbol>) {
+    (
+        vec![
+            Symbol::new_static(0x0001, "ε"),
+            Symbol::new_static(0x0002, "$"),
+            Symbol::new_static(0x0003, "NAME"),
+            Symbol::new_static(0x0004, "|")
+        ],
+        vec![
+            Symbol::new_static(0x0005, "e"),
+            Symbol::new_static(0x0006, "__Axiom")
+        ],
+        Vec::new()
+    )
+}
+
+/// Builds the data of a lexer automaton for the ambiguous grammar of expressions
+/// A NAME is a run of lowercase letters and there is no separator.
+#[cfg(test)]
+pub fn build_ambiguous_lexer() -> Vec<u8> {
+    // the matched terminal index and the number of transitions of each state
+    let states: [(Option<u16>, u16); 3] = [(None, 27), (Some(2), 26), (Some(3), 0)];
+    let mut table = Vec::<u16>::new();
+    let mut offsets = Vec::<u32>::new();
+    for (i, &(terminal, transitions)) in states.iter().enumerate() {
+        offsets.push(table.len() as u32);
+        table.extend_from_slice(&[terminal.is_some() as u16, transitions, 0]);
+        if let Some(index) = terminal {
+            table.extend_from_slice(&[DEFAULT_CONTEXT, index]);
+        }
+        for c in 0..256 {
+            table.push(match (i, c as u8) {
+                (0, b'|') => 2,
+                (0, b'a'..=b'z') | (1, b'a'..=b'z') => 1,
+                _ => 0xFFFF
+            });
+        }
+    }
+    let mut data = Vec::<u8>::new();
+    data.extend_from_slice(&(states.len() as u32).to_le_bytes());
+    for offset in offsets.iter() {
+        data.extend_from_slice(&offset.to_le_bytes());
+    }
+    for value in table.iter() {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    data
+}
+
+#[test]
+fn test_rnglr_automaton_checked() {
+    let (terminals, variables, virtuals) = get_ambiguous_symbols();
+    let data = build_ambiguous_automaton();
+    let check = |data: &[u8]| RNGLRAutomaton::try_new(data, &terminals, &variables, &virtuals);
+    assert!(check(&data).is_ok());
+    let automaton = RNGLRAutomaton::new(&data);
+    assert_eq!(automaton.get_states_count(), 6);
+    assert!(automaton.is_accepting_state(3));
+    // a truncated table of actions
+    match check(&data[..automaton.actions_offset + 2]) {
+        Err(AutomatonError::TruncatedTable {
+            table: "RNGLR actions",
+            ..
+        }) => {}
+        _ => panic!("expected a truncated table")
+    }
+    // a number of actions overflowing the length of the table
+    match check_array(&data, "RNGLR actions", 0, usize::MAX, 4) {
+        Err(AutomatonError::TruncatedTable { required, .. }) => {
+            assert_eq!(required, usize::MAX)
+        }
+        _ => panic!("expected a truncated table")
+    }
+    // the first action of the state 5 on `|` has an unknown code
+    let mut unknown = data.clone();
+    unknown[automaton.actions_offset + 10 * 4] = 9;
+    match check(&unknown) {
+        Err(AutomatonError::UnknownActionCode {
+            state: 5,
+            column: 3,
+            code: 9
+        }) => {}
+        _ => panic!("expected an unknown action code")
+    }
+    // the last op-code of the first production misses its argument
+    let mut overrun = data.clone();
+    let productions_offset = automaton.actions_offset + 12 * 4;
+    overrun[productions_offset + 5 + 2] = LR_OP_CODE_BASE_SEMANTIC_ACTION as u8;
+    match check(&overrun) {
+        Err(AutomatonError::BytecodeOverrun {
+            production: 0,
+            offset: 1
+        }) => {}
+        _ => panic!("expected a bytecode overrun")
+    }
+}
+
+#[test]
+fn test_rnglr_automaton_header() {
+    let (terminals, variables, virtuals) = get_ambiguous_symbols();
+    let data = build_ambiguous_automaton();
+    let check = |data: &[u8]| RNGLRAutomaton::try_new(data, &terminals, &variables, &virtuals);
+    let mut with_header = RNGLRAutomaton::new(&data).write_with_header();
+    assert!(has_header(&with_header));
+    assert!(check(&with_header).is_ok());
+    assert_eq!(RNGLRAutomaton::new(&with_header).get_states_count(), 6);
+    match check(&write_header(&data, AutomatonKind::LRk, 6)) {
+        Err(AutomatonError::WrongKind { found: 2, .. }) => {}
+        _ => panic!("expected a wrong kind")
+    }
+    match check(&write_header(&data, AutomatonKind::Rnglr, 1)) {
+        Err(AutomatonError::StatesCountMismatch {
+            expected: 1,
+            found: 6
+        }) => {}
+        _ => panic!("expected a mismatch of the number of states")
+    }
+    let last = with_header.len() - 1;
+    with_header[last] ^= 1;
+    match check(&with_header) {
+        Err(AutomatonError::ChecksumMismatch { .. }) => {}
+        _ => panic!("expected a checksum mismatch")
+    }
+}
+
+#[test]
+fn test_rnglr_automaton_write() {
+    let data = build_ambiguous_automaton();
+    let automaton = RNGLRAutomaton::new(&data);
+    assert_eq!(automaton.get_contexts(4).get_openings(), vec![(0x0003, 1)]);
+    assert_eq!(automaton.write(), data);
+    let with_header = automaton.write_with_header();
+    assert_eq!(RNGLRAutomaton::new(&with_header).write(), data);
+    let data: &'static [u8] = Box::leak(data.into_boxed_slice());
+    assert_eq!(
+        RNGLRAutomaton::from_static(data).write_with_header(),
+        with_header
+    );
+}
+
+#[test]
+fn test_rnglr_automaton_dump() {
+    let (terminals, variables, virtuals) = get_ambiguous_symbols();
+    let automaton = RNGLRAutomaton::new(&build_ambiguous_automaton());
+    let mut text = Vec::<u8>::new();
+    automaton
+        .dump_text(&mut text, &terminals, &variables, &virtuals)
+        .unwrap();
+    let text = String::from_utf8(text).unwrap();
+    assert_eq!(
+        text.lines().collect::<Vec<&str>>(),
+        vec![
+            "State 0",
+            "    on NAME: shift to 1",
+            "    on e: shift to 2",
+            "State 1",
+            "    on $: reduce by 2 (e)",
+            "    on |: reduce by 2 (e)",
+            "State 2",
+            "    on $: shift to 3",
+            "    on |: shift to 4",
+            "State 3",
+            "    on ε: accept",
+            "State 4",
+            "    opens context 1 on NAME",
+            "    on NAME: shift to 1",
+            "    on e: shift to 5",
+            "State 5",
+            "    on $: reduce by 1 (e)",
+            "    on |: shift to 4",
+            "    on |: reduce by 1 (e)",
+            "Production 0: __Axiom (none)",
+            "    reduction length 2",
+            "    bytecode: pop, pop",
+            "Production 1: e (none)",
+            "    reduction length 3",
+            "    bytecode: pop, pop, pop",
+            "Production 2: e (none)",
+            "    reduction length 1",
+            "    bytecode: pop"
+        ]
+    );
+    // the shifts are edges and the other actions are listed in the states
+    let mut dot = Vec::<u8>::new();
+    automaton
+        .dump_dot(&mut dot, &terminals, &variables)
+        .unwrap();
+    let dot = String::from_utf8(dot).unwrap();
+    assert_eq!(
+        dot.lines().collect::<Vec<&str>>(),
+        vec![
+            "digraph parser {",
+            "    node [shape=box];",
+            "    s0 [label=\"State 0\\l\"];",
+            "    s1 [label=\"State 1\\l$: reduce by 2 (e)\\l|: reduce by 2 (e)\\l\"];",
+            "    s2 [label=\"State 2\\l\"];",
+            "    s3 [label=\"State 3\\lε: accept\\l\"];",
+            "    s4 [label=\"State 4\\l\"];",
+            "    s5 [label=\"State 5\\l$: reduce by 1 (e)\\l|: reduce by 1 (e)\\l\"];",
+            "    s0 -> s1 [label=\"NAME\"];",
+            "    s0 -> s2 [label=\"e\"];",
+            "    s2 -> s3 [label=\"$\"];",
+            "    s2 -> s4 [label=\"|\"];",
+            "    s4 -> s1 [label=\"NAME\"];",
+            "    s4 -> s5 [label=\"e\"];",
+            "    s5 -> s4 [label=\"|\"];",
+            "}"
+        ]
+    );
+}
+
+#[test]
+fn test_rnglr_parser_reuse_buffers() {
+    use super::super::ast::AstNode;
+    use super::super::lexers::automaton::Automaton;
+    use super::super::lexers::impls::ContextFreeLexer;
+    use super::super::result::ParseResult;
+    use super::super::text::Text;
+    use super::super::utils::iterable::Iterable;
+
+    /// Prints the specified node and its descendants
+    fn print_tree(node: AstNode, output: &mut String) {
+        output.push_str(&format!("{}(", node));
+        let children = node.children();
+        for child in children.iter() {
+            print_tree(child, output);
+        }
+        output.push(')');
+    }
+
+    /// Parses the text of the result with the specified buffers and gives them back
+    fn parse_into(
+        result: &mut ParseResult,
+        lexer_automaton: &Automaton,
+        automaton: &RNGLRAutomaton,
+        buffers: RNGLRParserBuffers
+    ) -> RNGLRParserBuffers {
+        let mut actions = |_index: usize, _head: Symbol, _body: &dyn SemanticBody| ();
+        let data = result.get_parsing_data();
+        let mut lexer = ContextFreeLexer::new(data.0, data.1, lexer_automaton, 0);
+        let mut parser =
+            RNGLRParser::new_with_buffers(&mut lexer, automaton, data.2, &mut actions, buffers);
+        parser.parse();
+        parser.into_buffers()
+    }
+
+    /// Summarizes the result as its errors or its AST
+    fn summarize(result: &ParseResult) -> String {
+        if !result.is_success() {
+            return format!("{} errors", result.get_errors().get_count());
+        }
+        let mut output = String::new();
+        print_tree(result.get_ast().get_root(), &mut output);
+        output
+    }
+
+    let (terminals, variables, virtuals) = get_ambiguous_symbols();
+    let lexer_automaton = Automaton::new(&build_ambiguous_lexer());
+    let automaton = RNGLRAutomaton::new(&build_ambiguous_automaton());
+    let mut result = ParseResult::new(
+        terminals.clone(),
+        variables.clone(),
+        virtuals.clone(),
+        Text::new("")
+    );
+    let mut buffers = RNGLRParserBuffers::default();
+    for input in ["a|b|c", "x", "a||b", "a|bc|d|e", "a|b|c"].iter() {
+        result.reset(input);
+        buffers = parse_into(&mut result, &lexer_automaton, &automaton, buffers);
+        let mut fresh = ParseResult::new(
+            terminals.clone(),
+            variables.clone(),
+            virtuals.clone(),
+            Text::new(input)
+        );
+        parse_into(
+            &mut fresh,
+            &lexer_automaton,
+            &automaton,
+            RNGLRParserBuffers::default()
+        );
+        assert_eq!(summarize(&result), summarize(&fresh), "input {}", input);
+        assert_eq!(result.is_success(), *input != "a||b");
+    }
+    // the ambiguity is resolved as left-associative
+    assert_eq!(
+        summarize(&result),
+        "e(e(e(NAME = a())| = |()e(NAME = b()))| = |()e(NAME = c()))"
+    );
+}
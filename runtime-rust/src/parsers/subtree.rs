@@ -47,6 +47,14 @@ impl SubTree {
         }
     }
 
+    /// Clears this sub-tree for reuse, with the expected size
+    pub fn reset(&mut self, size: usize) {
+        self.nodes.clear();
+        self.actions.clear();
+        self.nodes.reserve(size);
+        self.actions.reserve(size);
+    }
+
     /// Gets the label of the node at the given index
     pub fn get_label_at(&self, index: usize) -> TableElemRef {
         self.nodes[index].label
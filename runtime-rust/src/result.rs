@@ -15,12 +15,16 @@
  * If not, see <http://www.gnu.org/licenses/>.
  ******************************************************************************/
 
-//! Module for the definition of a parse result
+//! Module for the definition of the results of parsers and lexers
+
+use std::io::Read;
 
 use super::ast::Ast;
 use super::ast::AstImpl;
 use super::errors::ParseErrors;
+use super::lexers::LexicalAmbiguity;
 use super::symbols::Symbol;
+use super::symbols::SymbolTable;
 use super::text::Text;
 use super::tokens::TokenRepository;
 use super::tokens::TokenRepositoryImpl;
@@ -28,11 +32,11 @@ use super::tokens::TokenRepositoryImpl;
 /// Represents the output of a parser
 pub struct ParseResult {
     /// The table of grammar terminals
-    terminals: &'static [Symbol],
+    terminals: SymbolTable,
     /// The table of grammar variables
-    variables: &'static [Symbol],
+    variables: SymbolTable,
     /// The table of grammar virtuals
-    virtuals: &'static [Symbol],
+    virtuals: SymbolTable,
     /// The input text
     text: Text,
     /// The errors found in the input
@@ -45,16 +49,17 @@ pub struct ParseResult {
 
 impl<'a> ParseResult {
     /// Initialize a new parse result
-    pub fn new(
-        terminals: &'static [Symbol],
-        variables: &'static [Symbol],
-        virtuals: &'static [Symbol],
-        text: Text
-    ) -> ParseResult {
+    /// The symbol tables are either static, as for generated parsers, or shared.
+    pub fn new<T, V, W>(terminals: T, variables: V, virtuals: W, text: Text) -> ParseResult
+    where
+        T: Into<SymbolTable>,
+        V: Into<SymbolTable>,
+        W: Into<SymbolTable>
+    {
         ParseResult {
-            terminals,
-            variables,
-            virtuals,
+            terminals: terminals.into(),
+            variables: variables.into(),
+            virtuals: virtuals.into(),
             text,
             errors: ParseErrors::new(),
             tokens: TokenRepositoryImpl::new(),
@@ -63,18 +68,18 @@ impl<'a> ParseResult {
     }
 
     /// Gets the grammar terminals
-    pub fn get_terminals(&self) -> &'static [Symbol] {
-        self.terminals
+    pub fn get_terminals(&self) -> &[Symbol] {
+        &self.terminals
     }
 
     /// Gets the grammar variables
-    pub fn get_variables(&self) -> &'static [Symbol] {
-        self.variables
+    pub fn get_variables(&self) -> &[Symbol] {
+        &self.variables
     }
 
     /// Gets the grammar virtuals
-    pub fn get_virtuals(&self) -> &'static [Symbol] {
-        self.virtuals
+    pub fn get_virtuals(&self) -> &[Symbol] {
+        &self.virtuals
     }
 
     /// Gets the input text for this result
@@ -82,6 +87,33 @@ impl<'a> ParseResult {
         &self.text
     }
 
+    /// Gets the input text for this result, for editing
+    /// After an edit, the tokens and AST of this result are stale.
+    pub fn get_input_mut(&mut self) -> &mut Text {
+        &mut self.text
+    }
+
+    /// Resets this result with the specified string as input, keeping its allocations
+    /// The errors, tokens and AST are cleared so that the result can be filled again by a parser.
+    pub fn reset(&mut self, input: &str) {
+        self.text.reset(input);
+        self.clear();
+    }
+
+    /// Resets this result with the content of a UTF-8 stream as input, keeping its allocations
+    /// The errors, tokens and AST are cleared so that the result can be filled again by a parser.
+    pub fn reset_from_utf8_stream(&mut self, input: &mut dyn Read) {
+        self.text.reset_from_utf8_stream(input);
+        self.clear();
+    }
+
+    /// Clears the errors, tokens and AST of this result
+    fn clear(&mut self) {
+        self.errors.clear();
+        self.tokens.clear();
+        self.ast.clear();
+    }
+
     /// Gets whether this result denotes a successful parsing
     pub fn is_success(&self) -> bool {
         self.ast.has_root()
@@ -92,6 +124,11 @@ impl<'a> ParseResult {
         &self.errors
     }
 
+    /// Gets the lexical ambiguities recorded by the lexer, when the lexer records them
+    pub fn get_ambiguities(&self) -> &[LexicalAmbiguity] {
+        self.tokens.get_ambiguities()
+    }
+
     /// Gets the token repository associated with this result
     pub fn get_tokens(&self) -> TokenRepository {
         TokenRepository::new(&self.terminals, &self.text, &self.tokens)
@@ -101,8 +138,8 @@ impl<'a> ParseResult {
     pub fn get_ast(&self) -> Ast {
         Ast::new(
             TokenRepository::new(&self.terminals, &self.text, &self.tokens),
-            self.variables,
-            self.virtuals,
+            &self.variables,
+            &self.virtuals,
             &self.ast
         )
     }
@@ -112,7 +149,108 @@ impl<'a> ParseResult {
         (
             TokenRepository::new_mut(&self.terminals, &self.text, &mut self.tokens),
             &mut self.errors,
-            Ast::new_mut(self.variables, self.virtuals, &mut self.ast)
+            Ast::new_mut(&self.variables, &self.virtuals, &mut self.ast)
         )
     }
 }
+
+/// Represents the output of a lexer run on its own, without a parser
+pub struct TokenizeResult {
+    /// The table of grammar terminals
+    terminals: SymbolTable,
+    /// The input text
+    text: Text,
+    /// The errors found in the input
+    errors: ParseErrors,
+    /// The table of matched tokens
+    tokens: TokenRepositoryImpl
+}
+
+impl TokenizeResult {
+    /// Initialize a new tokenization result
+    /// The table of terminals is either static, as for generated lexers, or shared.
+    pub fn new<T: Into<SymbolTable>>(terminals: T, text: Text) -> TokenizeResult {
+        TokenizeResult {
+            terminals: terminals.into(),
+            text,
+            errors: ParseErrors::new(),
+            tokens: TokenRepositoryImpl::new()
+        }
+    }
+
+    /// Gets the grammar terminals
+    pub fn get_terminals(&self) -> &[Symbol] {
+        &self.terminals
+    }
+
+    /// Gets the input text for this result
+    pub fn get_input(&self) -> &Text {
+        &self.text
+    }
+
+    /// Resets this result with the specified string as input, keeping its allocations
+    /// The errors and tokens are cleared so that the result can be filled again by a lexer.
+    pub fn reset(&mut self, input: &str) {
+        self.text.reset(input);
+        self.errors.clear();
+        self.tokens.clear();
+    }
+
+    /// Gets whether this result denotes a successful tokenization, i.e. without lexical errors
+    pub fn is_success(&self) -> bool {
+        self.errors.get_count() == 0
+    }
+
+    /// Gets the collection of errors
+    pub fn get_errors(&self) -> &ParseErrors {
+        &self.errors
+    }
+
+    /// Gets the lexical ambiguities recorded by the lexer, when the lexer records them
+    pub fn get_ambiguities(&self) -> &[LexicalAmbiguity] {
+        self.tokens.get_ambiguities()
+    }
+
+    /// Gets the token repository associated with this result
+    pub fn get_tokens(&self) -> TokenRepository<'_> {
+        TokenRepository::new(&self.terminals, &self.text, &self.tokens)
+    }
+
+    /// Gets the mutable data required for lexing
+    pub fn get_lexing_data(&mut self) -> (TokenRepository<'_>, &mut ParseErrors) {
+        (
+            TokenRepository::new_mut(&self.terminals, &self.text, &mut self.tokens),
+            &mut self.errors
+        )
+    }
+}
+
+#[test]
+fn test_tokenize_result() {
+    use super::fixtures::HimeGrammarLexer;
+    use super::lexers::run_lexer;
+    use super::symbols::SemanticElementTrait;
+    use super::utils::iterable::Iterable;
+    let fixture = HimeGrammarLexer::load();
+    let mut result = fixture.tokenize("grammar Test { }");
+    assert_eq!(result.get_terminals().len(), 39);
+    assert!(result.is_success());
+    let values: Vec<String> = result
+        .get_tokens()
+        .iter()
+        .map(|token| token.get_value().unwrap())
+        .collect();
+    assert_eq!(values, vec!["grammar", "Test", "{", "}", ""]);
+    // reuse the result for another input
+    result.reset("grammar Test { } §");
+    assert_eq!(result.get_input().get_value(0, 18), "grammar Test { } §");
+    assert!(result.is_success());
+    assert_eq!(result.get_tokens().get_tokens_count(), 0);
+    {
+        let mut lexer = fixture.new_lexer(result.get_lexing_data());
+        run_lexer(&mut lexer);
+    }
+    assert!(!result.is_success());
+    assert_eq!(result.get_errors().get_count(), 1);
+    assert_eq!(result.get_tokens().get_tokens_count(), 6);
+}
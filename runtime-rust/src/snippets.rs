@@ -0,0 +1,644 @@
+/*******************************************************************************
+ * Copyright (c) 2017 Association Cénotélie (cenotelie.fr)
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Lesser General Public License as
+ * published by the Free Software Foundation, either version 3
+ * of the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General
+ * Public License along with this program.
+ * If not, see <http://www.gnu.org/licenses/>.
+ ******************************************************************************/
+
+//! Module for rendering excerpts of an input text with annotations on spans, as found in compiler diagnostics
+
+use std::fmt::Display;
+use std::fmt::Error;
+use std::fmt::Formatter;
+
+use super::errors::ParseError;
+use super::errors::ParseErrorDataTrait;
+use super::text::ColumnMode;
+use super::text::Text;
+use super::text::TextSpan;
+
+/// The tab width used when the input text does not use a display column mode
+const DEFAULT_TAB_WIDTH: usize = 4;
+
+/// ANSI escape sequence for the style of the snippet's title
+const STYLE_TITLE: &str = "\x1b[1;31m";
+/// ANSI escape sequence for the style of the gutter
+const STYLE_GUTTER: &str = "\x1b[1;34m";
+/// ANSI escape sequence for the style of primary labels
+const STYLE_PRIMARY: &str = "\x1b[1;31m";
+/// ANSI escape sequence for the style of secondary labels
+const STYLE_SECONDARY: &str = "\x1b[1;34m";
+/// ANSI escape sequence for resetting the style
+const STYLE_RESET: &str = "\x1b[0m";
+
+/// Represents an annotated span in a snippet
+#[derive(Clone)]
+pub struct SnippetLabel {
+    /// The annotated span in the input text
+    pub span: TextSpan,
+    /// The message for the span, may be empty
+    pub message: String,
+    /// Whether this is a primary label (underlined with `^`), or a secondary one (underlined with `-`)
+    pub primary: bool
+}
+
+/// The resolved location of a label in the rendered lines
+struct LabelLocation {
+    /// The first line (1-based)
+    start_line: usize,
+    /// The display column of the start on the first line (0-based)
+    start_column: usize,
+    /// The last line (1-based)
+    end_line: usize,
+    /// The display column of the end on the last line (0-based, excluded)
+    end_column: usize,
+    /// The margin slot for a multi-line label
+    slot: usize
+}
+
+/// A line of the input text, as it is displayed
+struct DisplayedLine {
+    /// The displayed content, with tabs expanded
+    content: String,
+    /// The display column for each UTF-16 offset within the line
+    columns: Vec<usize>
+}
+
+/// Represents an excerpt of an input text with annotated spans, rendered in the style of compiler diagnostics.
+/// For example:
+///
+/// ```hime
+/// error: Unexpected token "b"
+///  --> 2:3
+///   |
+/// 1 | x = 1
+/// 2 | a b
+///   |   ^ unexpected
+/// ```
+pub struct Snippet<'a> {
+    /// The input text
+    text: &'a Text,
+    /// The title of the snippet, if any
+    title: Option<String>,
+    /// The annotated spans
+    labels: Vec<SnippetLabel>,
+    /// The number of lines to show before and after the annotated lines
+    context_lines: usize,
+    /// Whether to emit ANSI color codes
+    colored: bool
+}
+
+impl<'a> Snippet<'a> {
+    /// Creates a new snippet for the specified text
+    pub fn new(text: &'a Text) -> Snippet<'a> {
+        Snippet {
+            text,
+            title: None,
+            labels: Vec::<SnippetLabel>::new(),
+            context_lines: 1,
+            colored: false
+        }
+    }
+
+    /// Creates a new snippet for a lexical or syntactic error in the specified text
+    pub fn for_error(text: &'a Text, error: &ParseError) -> Snippet<'a> {
+        let mut snippet = Snippet::new(text);
+        snippet.set_title(&format!("error: {}", error.get_message()));
+        snippet.add_label(
+            TextSpan {
+                index: text.get_index_at(error.get_position()),
+                length: error.get_length()
+            },
+            "",
+            true
+        );
+        snippet
+    }
+
+    /// Sets the title of this snippet
+    pub fn set_title(&mut self, title: &str) {
+        self.title = Some(String::from(title));
+    }
+
+    /// Adds an annotated span to this snippet
+    pub fn add_label(&mut self, span: TextSpan, message: &str, primary: bool) {
+        self.labels.push(SnippetLabel {
+            span,
+            message: String::from(message),
+            primary
+        });
+    }
+
+    /// Gets the number of lines shown before and after the annotated lines
+    pub fn get_context_lines(&self) -> usize {
+        self.context_lines
+    }
+
+    /// Sets the number of lines shown before and after the annotated lines
+    pub fn set_context_lines(&mut self, count: usize) {
+        self.context_lines = count;
+    }
+
+    /// Gets whether the output contains ANSI color codes
+    pub fn is_colored(&self) -> bool {
+        self.colored
+    }
+
+    /// Sets whether the output contains ANSI color codes
+    pub fn set_colored(&mut self, colored: bool) {
+        self.colored = colored;
+    }
+
+    /// Renders this snippet
+    pub fn render(&self) -> String {
+        let mut output = String::new();
+        if let Some(ref title) = self.title {
+            output.push_str(&self.styled(title, STYLE_TITLE));
+            output.push('\n');
+        }
+        if self.labels.is_empty() {
+            return output;
+        }
+        let locations = self.get_locations();
+        let slots = locations
+            .iter()
+            .filter(|l| l.start_line != l.end_line)
+            .count();
+        let lines = self.get_shown_lines(&locations);
+        let gutter_width = format!("{}", lines[lines.len() - 1]).len();
+        let blank_gutter = self.styled(&format!("{} |", " ".repeat(gutter_width)), STYLE_GUTTER);
+
+        // header with the location of the first primary label, in the original source if possible
+        let main = self.labels.iter().position(|l| l.primary).unwrap_or(0);
+        let index = self.labels[main].span.index;
+        output.push_str(&self.styled(&format!("{}-->", " ".repeat(gutter_width)), STYLE_GUTTER));
+        match self.text.get_source_location_at(index) {
+            Some(location) => output.push_str(&format!(" {}\n", location)),
+            None => {
+                let position = self.text.get_position_at(index);
+                output.push_str(&format!(" {}:{}\n", position.line, position.column));
+            }
+        }
+        output.push_str(&blank_gutter);
+        output.push('\n');
+
+        let mut previous = 0;
+        for line in lines.iter() {
+            if previous != 0 && *line != previous + 1 {
+                output.push_str(&self.styled("...", STYLE_GUTTER));
+                output.push('\n');
+            }
+            previous = *line;
+            let displayed = self.get_displayed_line(*line);
+            // the line itself
+            output.push_str(&self.styled(
+                &format!("{:>width$} |", line, width = gutter_width),
+                STYLE_GUTTER
+            ));
+            output.push(' ');
+            let margin = self.get_margin(&locations, slots, *line);
+            for cell in margin.iter() {
+                output.push_str(&self.styled(&cell.0.to_string(), cell.1));
+            }
+            output.push_str(&displayed.content);
+            output.push('\n');
+            // the annotations for this line
+            for (label, location) in self.labels.iter().zip(locations.iter()) {
+                let row = self.get_annotation(label, location, margin.clone(), *line);
+                if let Some(row) = row {
+                    output.push_str(&blank_gutter);
+                    output.push(' ');
+                    output.push_str(&row);
+                    output.push('\n');
+                }
+            }
+        }
+        output.push_str(&blank_gutter);
+        output.push('\n');
+        output
+    }
+
+    /// Applies the specified style to a piece of text, if colors are activated
+    fn styled(&self, value: &str, style: &str) -> String {
+        if self.colored && !style.is_empty() {
+            format!("{}{}{}", style, value, STYLE_RESET)
+        } else {
+            String::from(value)
+        }
+    }
+
+    /// Gets the tab width to use for rendering
+    fn get_tab_width(&self) -> usize {
+        match self.text.get_column_mode() {
+            ColumnMode::Display { tab_width, .. } => tab_width,
+            _ => DEFAULT_TAB_WIDTH
+        }
+    }
+
+    /// Gets the line of the input text at the specified index
+    fn get_line_at(&self, index: usize) -> usize {
+        self.text.get_position_at(index).line
+    }
+
+    /// Gets the i-th line of the text as it is displayed
+    fn get_displayed_line(&self, line: usize) -> DisplayedLine {
+        let mode = ColumnMode::Display {
+            tab_width: self.get_tab_width(),
+            wide_chars: true
+        };
+        let raw = self.text.get_line_content(line);
+        let raw = raw.trim_end_matches(|c| {
+            c == '\n'
+                || c == '\r'
+                || c == '\u{0B}'
+                || c == '\u{0C}'
+                || c == '\u{85}'
+                || c == '\u{2028}'
+                || c == '\u{2029}'
+        });
+        let mut content = String::new();
+        let mut columns = Vec::<usize>::new();
+        let mut column = 0;
+        for c in raw.chars() {
+            for _i in 0..c.len_utf16() {
+                columns.push(column);
+            }
+            let width = mode.get_width_of(c as u32, column);
+            if c == '\t' {
+                content.push_str(&" ".repeat(width));
+            } else {
+                content.push(c);
+            }
+            column += width;
+        }
+        columns.push(column);
+        DisplayedLine { content, columns }
+    }
+
+    /// Gets the display column for the specified index on the specified line
+    fn get_column_at(&self, line: usize, index: usize) -> usize {
+        let displayed = self.get_displayed_line(line);
+        let offset = index - self.text.get_line_index(line);
+        if offset < displayed.columns.len() {
+            displayed.columns[offset]
+        } else {
+            // beyond the content, for example on the line ending
+            displayed.columns[displayed.columns.len() - 1] + offset + 1 - displayed.columns.len()
+        }
+    }
+
+    /// Resolves the location of the labels
+    fn get_locations(&self) -> Vec<LabelLocation> {
+        let mut result = Vec::<LabelLocation>::with_capacity(self.labels.len());
+        let mut slot = 0;
+        for label in self.labels.iter() {
+            let start = label.span.index;
+            let end = start + label.span.length;
+            let start_line = self.get_line_at(start);
+            let end_line = if label.span.length == 0 {
+                start_line
+            } else {
+                self.get_line_at(end - 1)
+            };
+            let start_column = self.get_column_at(start_line, start);
+            let mut end_column = self.get_column_at(end_line, end);
+            let past_content = end_line != self.get_line_at(end) || self.text.is_end(end);
+            if label.span.length > 0 && past_content {
+                // the end is on the line ending, or after the content
+                let last = self.get_column_at(end_line, end - 1);
+                end_column = end_column.max(last + 1);
+            }
+            if start_line == end_line && end_column <= start_column {
+                end_column = start_column + 1;
+            }
+            result.push(LabelLocation {
+                start_line,
+                start_column,
+                end_line,
+                end_column,
+                slot
+            });
+            if start_line != end_line {
+                slot += 1;
+            }
+        }
+        result
+    }
+
+    /// Gets the sorted numbers of the lines to be shown
+    fn get_shown_lines(&self, locations: &[LabelLocation]) -> Vec<usize> {
+        let count = self.text.get_line_count();
+        let mut lines = Vec::<usize>::new();
+        for location in locations.iter() {
+            let first = if location.start_line > self.context_lines {
+                location.start_line - self.context_lines
+            } else {
+                1
+            };
+            let last = (location.end_line + self.context_lines).min(count);
+            for line in first..(last + 1) {
+                lines.push(line);
+            }
+        }
+        lines.sort();
+        lines.dedup();
+        lines
+    }
+
+    /// Gets the margin at the left of a line that shows the multi-line labels spanning over the line
+    fn get_margin(
+        &self,
+        locations: &[LabelLocation],
+        slots: usize,
+        line: usize
+    ) -> Vec<(char, &'static str)> {
+        let mut margin = vec![(' ', ""); slots * 2];
+        for (label, location) in self.labels.iter().zip(locations.iter()) {
+            if location.start_line != location.end_line
+                && location.start_line < line
+                && line <= location.end_line
+            {
+                margin[location.slot * 2] = ('|', get_style(label));
+            }
+        }
+        margin
+    }
+
+    /// Gets the annotation row for a label on the specified line, if any
+    fn get_annotation(
+        &self,
+        label: &SnippetLabel,
+        location: &LabelLocation,
+        mut row: Vec<(char, &'static str)>,
+        line: usize
+    ) -> Option<String> {
+        let style = get_style(label);
+        let mark = if label.primary { '^' } else { '-' };
+        let margin = row.len();
+        if location.start_line == location.end_line {
+            if line != location.start_line {
+                return None;
+            }
+            for _i in 0..location.start_column {
+                row.push((' ', ""));
+            }
+            for _i in location.start_column..location.end_column {
+                row.push((mark, style));
+            }
+        } else if line == location.start_line {
+            // the opening of a multi-line label: connect the margin slot to the start
+            let target = margin + location.start_column;
+            while row.len() <= target {
+                row.push((' ', ""));
+            }
+            for cell in row.iter_mut().take(target).skip(location.slot * 2 + 1) {
+                *cell = ('_', style);
+            }
+            row[target] = (mark, style);
+        } else if line == location.end_line {
+            // the closing of a multi-line label: connect the margin slot to the end
+            let target = margin + location.end_column.max(1) - 1;
+            while row.len() <= target {
+                row.push((' ', ""));
+            }
+            for cell in row.iter_mut().take(target).skip(location.slot * 2 + 1) {
+                *cell = ('_', style);
+            }
+            row[target] = (mark, style);
+        } else {
+            return None;
+        }
+        let mut result = String::new();
+        let mut i = 0;
+        while i < row.len() {
+            // group the cells with the same style
+            let current = row[i].1;
+            let mut group = String::new();
+            while i < row.len() && row[i].1 == current {
+                group.push(row[i].0);
+                i += 1;
+            }
+            result.push_str(&self.styled(&group, current));
+        }
+        if !label.message.is_empty() && line == location.end_line {
+            result.push(' ');
+            result.push_str(&self.styled(&label.message, style));
+        }
+        Some(result)
+    }
+}
+
+/// Gets the style for the specified label
+fn get_style(label: &SnippetLabel) -> &'static str {
+    if label.primary {
+        STYLE_PRIMARY
+    } else {
+        STYLE_SECONDARY
+    }
+}
+
+/// Implementation of `Display` for `Snippet`
+impl<'a> Display for Snippet<'a> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        write!(f, "{}", self.render())
+    }
+}
+
+#[test]
+fn test_snippet_render() {
+    let text = Text::new("let a = 1;\nfoo(\n  bar\n);\nend");
+    let mut snippet = Snippet::new(&text);
+    snippet.set_title("error: oops");
+    snippet.add_label(
+        TextSpan {
+            index: 4,
+            length: 1
+        },
+        "first",
+        false
+    );
+    snippet.add_label(
+        TextSpan {
+            index: 14,
+            length: 10
+        },
+        "call",
+        true
+    );
+    let expected = "error: oops
+ --> 2:4
+  |
+1 |   let a = 1;
+  |       - first
+2 |   foo(
+  |  ____^
+3 | |   bar
+4 | | );
+  | |__^ call
+5 |   end
+  |
+";
+    assert_eq!(snippet.render(), expected);
+}
+
+#[test]
+fn test_snippet_colored() {
+    let text = Text::new("a b");
+    let mut snippet = Snippet::new(&text);
+    snippet.set_title("error: oops");
+    snippet.set_colored(true);
+    assert!(snippet.is_colored());
+    snippet.add_label(
+        TextSpan {
+            index: 2,
+            length: 1
+        },
+        "here",
+        true
+    );
+    let expected = "\x1b[1;31merror: oops\x1b[0m
+\x1b[1;34m -->\x1b[0m 1:3
+\x1b[1;34m  |\x1b[0m
+\x1b[1;34m1 |\x1b[0m a b
+\x1b[1;34m  |\x1b[0m   \x1b[1;31m^\x1b[0m \x1b[1;31mhere\x1b[0m
+\x1b[1;34m  |\x1b[0m
+";
+    assert_eq!(snippet.render(), expected);
+}
+
+#[test]
+fn test_snippet_multi_line_margins() {
+    let text = Text::new("a(\nb(\nc)\nd)");
+    let mut snippet = Snippet::new(&text);
+    // two nested multi-line labels take two slots in the margin
+    snippet.add_label(
+        TextSpan {
+            index: 1,
+            length: 10
+        },
+        "outer",
+        true
+    );
+    snippet.add_label(
+        TextSpan {
+            index: 4,
+            length: 4
+        },
+        "inner",
+        false
+    );
+    let expected = " --> 1:2
+  |
+1 |     a(
+  |  ____^
+2 | |   b(
+  | |  __-
+3 | | | c)
+  | | |__- inner
+4 | |   d)
+  | |____^ outer
+  |
+";
+    assert_eq!(snippet.render(), expected);
+}
+
+#[test]
+fn test_snippet_labels_on_same_line() {
+    let text = Text::new("x = foo(bar)");
+    let mut snippet = Snippet::new(&text);
+    snippet.add_label(
+        TextSpan {
+            index: 4,
+            length: 3
+        },
+        "function",
+        false
+    );
+    snippet.add_label(
+        TextSpan {
+            index: 8,
+            length: 3
+        },
+        "argument",
+        true
+    );
+    let expected = " --> 1:9
+  |
+1 | x = foo(bar)
+  |     --- function
+  |         ^^^ argument
+  |
+";
+    assert_eq!(snippet.render(), expected);
+}
+
+#[test]
+fn test_snippet_for_error() {
+    use super::errors::ParseErrorUnexpectedChar;
+    use super::text::TextPosition;
+    let text = Text::new("a = 1;\nb = %;");
+    let error = ParseError::UnexpectedChar(ParseErrorUnexpectedChar::new(
+        TextPosition { line: 2, column: 5 },
+        ['%' as u16, 0]
+    ));
+    let snippet = Snippet::for_error(&text, &error);
+    let expected = "error: Unexpected character '%' (U+25)
+ --> 2:5
+  |
+1 | a = 1;
+2 | b = %;
+  |     ^
+  |
+";
+    assert_eq!(snippet.render(), expected);
+}
+
+#[test]
+fn test_snippet_context_lines_clamped() {
+    let text = Text::new("one\ntwo\nthree\nfour\nfive\nsix\nseven");
+    let mut snippet = Snippet::new(&text);
+    snippet.set_context_lines(2);
+    assert_eq!(snippet.get_context_lines(), 2);
+    // the context of the first line starts at the first line
+    snippet.add_label(
+        TextSpan {
+            index: 0,
+            length: 3
+        },
+        "first",
+        true
+    );
+    // the context of the last line stops at the last line
+    snippet.add_label(
+        TextSpan {
+            index: 28,
+            length: 5
+        },
+        "last",
+        false
+    );
+    let expected = " --> 1:1
+  |
+1 | one
+  | ^^^ first
+2 | two
+3 | three
+...
+5 | five
+6 | six
+7 | seven
+  | ----- last
+  |
+";
+    assert_eq!(snippet.render(), expected);
+}
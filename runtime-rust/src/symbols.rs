@@ -17,9 +17,12 @@
 
 //! Module for the definition of grammar symbols
 
+use std::fmt::Debug;
 use std::fmt::Display;
 use std::fmt::Error;
 use std::fmt::Formatter;
+use std::ops::Deref;
+use std::sync::Arc;
 
 use super::text::TextContext;
 use super::text::TextPosition;
@@ -44,13 +47,82 @@ pub const SID_EPSILON: u32 = 1;
 /// Symbol ID of the Dollar terminal
 pub const SID_DOLLAR: u32 = 2;
 
+/// The name of a grammar symbol
+/// The name is either static, as for generated parsers, or owned and shared, as for parsers loaded at runtime.
+#[derive(Clone, Eq)]
+pub enum SymbolName {
+    /// A static name, usually generated
+    Static(&'static str),
+    /// An owned name, shared between the copies of a symbol
+    Shared(Arc<str>)
+}
+
+impl Deref for SymbolName {
+    type Target = str;
+    fn deref(&self) -> &str {
+        match *self {
+            SymbolName::Static(name) => name,
+            SymbolName::Shared(ref name) => name
+        }
+    }
+}
+
+/// Implementation of `PartialEq` for `SymbolName`
+impl PartialEq for SymbolName {
+    fn eq(&self, other: &SymbolName) -> bool {
+        **self == **other
+    }
+}
+
+/// Implementation of `PartialEq` for comparing `SymbolName` with string slices
+impl<'a> PartialEq<&'a str> for SymbolName {
+    fn eq(&self, other: &&'a str) -> bool {
+        **self == **other
+    }
+}
+
+/// Implementation of `PartialEq` for comparing `SymbolName` with strings
+impl PartialEq<String> for SymbolName {
+    fn eq(&self, other: &String) -> bool {
+        **self == **other
+    }
+}
+
+/// Implementation of `Display` for `SymbolName`
+impl Display for SymbolName {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        f.write_str(self)
+    }
+}
+
+/// Implementation of `Debug` for `SymbolName`
+impl Debug for SymbolName {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        Debug::fmt(&**self, f)
+    }
+}
+
+impl From<&'static str> for SymbolName {
+    fn from(name: &'static str) -> SymbolName {
+        SymbolName::Static(name)
+    }
+}
+
+impl From<String> for SymbolName {
+    fn from(name: String) -> SymbolName {
+        SymbolName::Shared(Arc::from(name))
+    }
+}
+
 /// Represents a grammar symbol (terminal, variable or virtual)
-#[derive(Copy, Clone, Eq, PartialEq)]
+/// Symbols are cheap to clone: the names of static symbols are static strings,
+/// and the names of symbols created at runtime are shared.
+#[derive(Clone, Eq, PartialEq)]
 pub struct Symbol {
     /// The symbol's unique identifier
     pub id: u32,
     /// The symbol's name
-    pub name: &'static str
+    pub name: SymbolName
 }
 
 /// Implementation of `Display` for `Symbol`
@@ -60,6 +132,70 @@ impl Display for Symbol {
     }
 }
 
+impl Symbol {
+    /// Creates a new symbol with a static name
+    pub const fn new_static(id: u32, name: &'static str) -> Symbol {
+        Symbol {
+            id,
+            name: SymbolName::Static(name)
+        }
+    }
+
+    /// Creates a new symbol with a name known at runtime
+    /// The name is owned by the symbol and shared by its clones.
+    pub fn new_shared(id: u32, name: &str) -> Symbol {
+        Symbol {
+            id,
+            name: SymbolName::Shared(Arc::from(name))
+        }
+    }
+}
+
+/// A table of grammar symbols (terminals, variables or virtuals)
+/// The table is either static, as for generated parsers, or owned and shared, as for parsers loaded at runtime.
+#[derive(Clone)]
+pub enum SymbolTable {
+    /// A static table, usually generated
+    Static(&'static [Symbol]),
+    /// An owned table, shared between its users
+    Shared(Arc<Vec<Symbol>>)
+}
+
+impl SymbolTable {
+    /// Creates a new shared table with the specified symbols
+    pub fn new(symbols: Vec<Symbol>) -> SymbolTable {
+        SymbolTable::Shared(Arc::new(symbols))
+    }
+}
+
+impl Deref for SymbolTable {
+    type Target = [Symbol];
+    fn deref(&self) -> &[Symbol] {
+        match *self {
+            SymbolTable::Static(symbols) => symbols,
+            SymbolTable::Shared(ref symbols) => symbols
+        }
+    }
+}
+
+impl From<&'static [Symbol]> for SymbolTable {
+    fn from(symbols: &'static [Symbol]) -> SymbolTable {
+        SymbolTable::Static(symbols)
+    }
+}
+
+impl From<Vec<Symbol>> for SymbolTable {
+    fn from(symbols: Vec<Symbol>) -> SymbolTable {
+        SymbolTable::new(symbols)
+    }
+}
+
+impl From<Arc<Vec<Symbol>>> for SymbolTable {
+    fn from(symbols: Arc<Vec<Symbol>>) -> SymbolTable {
+        SymbolTable::Shared(symbols)
+    }
+}
+
 /// A trait for a parsing element
 pub trait SemanticElementTrait {
     /// Gets the position in the input text of this element
@@ -119,11 +255,11 @@ impl<'a> SemanticElementTrait for SemanticElement<'a> {
     }
 
     fn get_symbol(&self) -> Symbol {
-        match self {
-            &SemanticElement::Token(ref token) => token.get_symbol(),
-            &SemanticElement::Terminal(ref symbol) => *symbol,
-            &SemanticElement::Variable(ref symbol) => *symbol,
-            &SemanticElement::Virtual(ref symbol) => *symbol
+        match *self {
+            SemanticElement::Token(ref token) => token.get_symbol(),
+            SemanticElement::Terminal(ref symbol) => symbol.clone(),
+            SemanticElement::Variable(ref symbol) => symbol.clone(),
+            SemanticElement::Virtual(ref symbol) => symbol.clone()
         }
     }
 
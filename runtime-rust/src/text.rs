@@ -22,6 +22,7 @@ use std::fmt::Error;
 use std::fmt::Formatter;
 use std::io::BufReader;
 use std::io::Read;
+use std::ops::Range;
 use std::result::Result;
 
 use super::utils::biglist::BigList;
@@ -70,6 +71,77 @@ impl Display for TextPosition {
     }
 }
 
+/// The unit in which the column numbers of positions in a text are expressed
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub enum ColumnMode {
+    /// Columns count UTF-16 code units (this is also what the Language Server Protocol uses)
+    Utf16,
+    /// Columns count Unicode scalar values (code points)
+    CodePoints,
+    /// Columns count bytes in the UTF-8 encoding of the line
+    Utf8Bytes,
+    /// Columns count the cells used when displaying the line in a terminal
+    Display {
+        /// The width of tab stops
+        tab_width: usize,
+        /// Whether East-Asian wide and full-width characters take two cells
+        wide_chars: bool
+    }
+}
+
+/// Implementation of `Default` for `ColumnMode`
+impl Default for ColumnMode {
+    fn default() -> Self {
+        ColumnMode::Utf16
+    }
+}
+
+impl ColumnMode {
+    /// Gets the number of columns taken by the specified code point
+    /// when it appears at the specified (0-based) column
+    pub fn get_width_of(&self, c: u32, column: usize) -> usize {
+        match *self {
+            ColumnMode::Utf16 => {
+                if c > 0xFFFF {
+                    2
+                } else {
+                    1
+                }
+            }
+            ColumnMode::CodePoints => 1,
+            ColumnMode::Utf8Bytes => {
+                if c < 0x80 {
+                    1
+                } else if c < 0x800 {
+                    2
+                } else if c <= 0xFFFF {
+                    3
+                } else {
+                    4
+                }
+            }
+            ColumnMode::Display {
+                tab_width,
+                wide_chars
+            } => {
+                if c == 0x0009 {
+                    if tab_width == 0 {
+                        0
+                    } else {
+                        tab_width - column % tab_width
+                    }
+                } else if is_zero_width(c) {
+                    0
+                } else if wide_chars && is_wide(c) {
+                    2
+                } else {
+                    1
+                }
+            }
+        }
+    }
+}
+
 /// Represents the context description of a position in a piece of text.
 /// A context is composed of two pieces of text, the line content and the pointer.
 /// For example, given the piece of text:
@@ -88,7 +160,47 @@ pub struct TextContext {
     /// The text content being represented
     pub content: String,
     /// The pointer textual representation
-    pub pointer: String
+    pub pointer: String,
+    /// The location in the original source, when the text has a source map
+    pub source: Option<SourceLocation>
+}
+
+/// Represents a segment of a text that has been copied verbatim from an original source file,
+/// for example by a preprocessor that resolves includes.
+#[derive(Clone)]
+pub struct SourceSegment {
+    /// The starting index of the segment in the text
+    pub index: usize,
+    /// The length of the segment
+    pub length: usize,
+    /// The name of the original source file
+    pub file: String,
+    /// The starting offset of the segment in the original source file
+    pub offset: usize,
+    /// The position of the segment's start in the original source file
+    pub position: TextPosition
+}
+
+/// Represents a location in an original source file
+#[derive(Clone)]
+pub struct SourceLocation {
+    /// The name of the original source file
+    pub file: String,
+    /// The offset in the original source file
+    pub offset: usize,
+    /// The position in the original source file
+    pub position: TextPosition
+}
+
+/// Implementation of `Display` for `SourceLocation`
+impl Display for SourceLocation {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        write!(
+            f,
+            "{}:{}:{}",
+            self.file, self.position.line, self.position.column
+        )
+    }
 }
 
 /// Represents the input of parser with some metadata for line endings
@@ -98,27 +210,31 @@ pub struct Text {
     /// The full content of the input
     content: BigList<Utf16C>,
     /// Cache of the starting indices of each line within the text
-    lines: Vec<usize>
+    lines: Vec<usize>,
+    /// Cache of the starting offsets of each line within the UTF-8 encoding of the text
+    utf8_lines: Vec<usize>,
+    /// The unit for column numbers
+    column_mode: ColumnMode,
+    /// The segments mapping this text to the original source files, sorted by index
+    source_map: Vec<SourceSegment>,
+    /// The version of the content, incremented by each edit
+    version: usize
 }
 
 impl Text {
     /// Initializes this text
     pub fn new(input: &str) -> Text {
         let mut content = BigList::<Utf16C>::new(0);
-        for c in input.chars() {
-            let value = c as u32;
-            if value <= 0xFFFF {
-                content.push(value as u16);
-            } else {
-                let temp = value - 0x10000;
-                let lead = (temp >> 10) + 0xD800;
-                let trail = (temp & 0x03FF) + 0xDC00;
-                content.push(lead as Utf16C);
-                content.push(trail as Utf16C);
-            }
+        push_str(&mut content, input);
+        let (lines, utf8_lines) = find_lines_in(&content);
+        Text {
+            content,
+            lines,
+            utf8_lines,
+            column_mode: ColumnMode::default(),
+            source_map: Vec::<SourceSegment>::new(),
+            version: 0
         }
-        let lines = find_lines_in(&content);
-        Text { content, lines }
     }
 
     /// Initializes this text from a UTF-16 stream
@@ -129,8 +245,15 @@ impl Text {
         for c in iterator {
             content.push(c);
         }
-        let lines = find_lines_in(&content);
-        Text { content, lines }
+        let (lines, utf8_lines) = find_lines_in(&content);
+        Text {
+            content,
+            lines,
+            utf8_lines,
+            column_mode: ColumnMode::default(),
+            source_map: Vec::<SourceSegment>::new(),
+            version: 0
+        }
     }
 
     /// Initializes this text from a UTF-8 stream
@@ -141,8 +264,196 @@ impl Text {
         for c in iterator {
             content.push(c);
         }
-        let lines = find_lines_in(&content);
-        Text { content, lines }
+        let (lines, utf8_lines) = find_lines_in(&content);
+        Text {
+            content,
+            lines,
+            utf8_lines,
+            column_mode: ColumnMode::default(),
+            source_map: Vec::<SourceSegment>::new(),
+            version: 0
+        }
+    }
+
+    /// Resets this text with the specified string, keeping its allocations
+    /// The source map is cleared and the version is incremented.
+    pub fn reset(&mut self, input: &str) {
+        self.content.clear();
+        push_str(&mut self.content, input);
+        self.on_reset();
+    }
+
+    /// Resets this text with the content of a UTF-8 stream, keeping its allocations
+    /// The source map is cleared and the version is incremented.
+    pub fn reset_from_utf8_stream(&mut self, input: &mut dyn Read) {
+        self.content.clear();
+        {
+            let reader = &mut BufReader::new(input);
+            let iterator = Utf16IteratorOverUtf8::new(reader);
+            for c in iterator {
+                self.content.push(c);
+            }
+        }
+        self.on_reset();
+    }
+
+    /// Updates the caches after the content of this text has been reset
+    fn on_reset(&mut self) {
+        find_lines_into(&self.content, &mut self.lines, &mut self.utf8_lines);
+        self.source_map.clear();
+        self.version += 1;
+    }
+
+    /// Gets the unit for the column numbers of positions in this text
+    pub fn get_column_mode(&self) -> ColumnMode {
+        self.column_mode
+    }
+
+    /// Sets the unit for the column numbers of positions in this text
+    pub fn set_column_mode(&mut self, mode: ColumnMode) {
+        self.column_mode = mode;
+    }
+
+    /// Gets the segments mapping this text to the original source files
+    pub fn get_source_map(&self) -> &[SourceSegment] {
+        &self.source_map
+    }
+
+    /// Registers a segment of this text that comes from an original source file
+    pub fn add_source_segment(&mut self, segment: SourceSegment) {
+        let position = self
+            .source_map
+            .iter()
+            .position(|x| x.index > segment.index)
+            .unwrap_or(self.source_map.len());
+        self.source_map.insert(position, segment);
+    }
+
+    /// Gets the location in the original source files for the given index, if any
+    pub fn get_source_location_at(&self, index: usize) -> Option<SourceLocation> {
+        // find the last segment starting at or before the index
+        let candidate = match self.source_map.binary_search_by(|x| x.index.cmp(&index)) {
+            Ok(i) => i,
+            Err(0) => return None,
+            Err(i) => i - 1
+        };
+        let segment = &self.source_map[candidate];
+        if index > segment.index + segment.length
+            || (index == segment.index + segment.length && !self.is_end(index))
+        {
+            // in a gap between segments
+            return None;
+        }
+        let segment_line = find_line_at(&self.lines, segment.index);
+        let line = find_line_at(&self.lines, index);
+        let position = if line == segment_line {
+            TextPosition {
+                line: segment.position.line,
+                column: segment.position.column + self.get_columns_between(segment.index, index)
+            }
+        } else {
+            TextPosition {
+                line: segment.position.line + line - segment_line,
+                column: self.get_columns_between(self.lines[line], index) + 1
+            }
+        };
+        Some(SourceLocation {
+            file: segment.file.clone(),
+            offset: segment.offset + index - segment.index,
+            position
+        })
+    }
+
+    /// Gets the location in the original source files for the given position in this text, if any
+    pub fn get_source_location_for(&self, position: TextPosition) -> Option<SourceLocation> {
+        self.get_source_location_at(self.get_index_at(position))
+    }
+
+    /// Gets the version of this text
+    /// The version is incremented each time an edit is applied to the text.
+    pub fn get_version(&self) -> usize {
+        self.version
+    }
+
+    /// Replaces the content of the specified span with the replacement
+    /// The line index is updated incrementally, only the lines around the edit are re-examined.
+    /// Segments of the source map that overlap the edit are dropped, the ones after are shifted.
+    pub fn apply_edit(&mut self, span: TextSpan, replacement: &str) {
+        let old_end = span.index + span.length;
+        let inserted: Vec<Utf16C> = replacement.encode_utf16().collect();
+        let new_end = span.index + inserted.len();
+        let mut removed_utf8 = 0;
+        for i in span.index..old_end {
+            removed_utf8 += get_utf8_length(self.content[i]);
+        }
+        let inserted_utf8 = replacement.len();
+
+        // splice the content
+        let mut tail = Vec::<Utf16C>::with_capacity(self.content.len() - old_end);
+        for i in old_end..self.content.len() {
+            tail.push(self.content[i]);
+        }
+        self.content.truncate(span.index);
+        for c in inserted.iter() {
+            self.content.push(*c);
+        }
+        for c in tail.iter() {
+            self.content.push(*c);
+        }
+
+        // keep the line starts that cannot be affected by the edit, shifted
+        let line = find_line_at(&self.lines, span.index);
+        let first = if line > 0 { line - 1 } else { 0 };
+        let mut kept = Vec::<usize>::new();
+        let mut kept_utf8 = Vec::<usize>::new();
+        for i in (first + 1)..self.lines.len() {
+            if self.lines[i] >= old_end + 2 {
+                kept.push(self.lines[i] - old_end + new_end);
+                kept_utf8.push(self.utf8_lines[i] - removed_utf8 + inserted_utf8);
+            }
+        }
+        // re-examine the lines from the one before the edit, up to the first kept one
+        let from = self.lines[first];
+        let mut offset = self.utf8_lines[first];
+        let to = if kept.is_empty() {
+            self.content.len()
+        } else {
+            kept[0]
+        };
+        self.lines.truncate(first + 1);
+        self.utf8_lines.truncate(first + 1);
+        for i in from..to {
+            let c1 = if i > 0 { self.content[i - 1] } else { 0 };
+            let c2 = self.content[i];
+            if is_line_ending(c1, c2) {
+                let (start, start_utf8) = if c1 == 0x000D && c2 != 0x000A {
+                    (i, offset)
+                } else {
+                    (i + 1, offset + get_utf8_length(c2))
+                };
+                // a lone CR just before the range already starts the first re-examined line
+                if start > from && (kept.is_empty() || start < to) {
+                    self.lines.push(start);
+                    self.utf8_lines.push(start_utf8);
+                }
+            }
+            offset += get_utf8_length(c2);
+        }
+        self.lines.extend(kept);
+        self.utf8_lines.extend(kept_utf8);
+
+        // update the source map
+        let mut segments = Vec::<SourceSegment>::with_capacity(self.source_map.len());
+        for mut segment in self.source_map.drain(..) {
+            if segment.index + segment.length <= span.index {
+                segments.push(segment);
+            } else if segment.index >= old_end {
+                segment.index = segment.index - old_end + new_end;
+                segments.push(segment);
+            }
+        }
+        self.source_map = segments;
+        self.version += 1;
     }
 
     /// Gets the number of lines
@@ -199,10 +510,109 @@ impl Text {
         let line = find_line_at(&self.lines, index);
         TextPosition {
             line: line + 1,
-            column: index - self.lines[line] + 1
+            column: self.get_columns_between(self.lines[line], index) + 1
         }
     }
 
+    /// Gets the offset in the UTF-8 encoding of this text for the given index
+    pub fn get_utf8_offset(&self, index: usize) -> usize {
+        let line = find_line_at(&self.lines, index);
+        let mut offset = self.utf8_lines[line];
+        for i in self.lines[line]..index {
+            offset += get_utf8_length(self.content[i]);
+        }
+        offset
+    }
+
+    /// Gets the index in the content for the given offset in the UTF-8 encoding of this text
+    /// An offset within the encoding of a character is moved back to the start of the character.
+    pub fn get_index_at_utf8_offset(&self, offset: usize) -> usize {
+        let line = match self.utf8_lines.binary_search(&offset) {
+            Ok(line) => return self.lines[line],
+            Err(line) => line - 1
+        };
+        let mut index = self.lines[line];
+        let mut current = self.utf8_lines[line];
+        while !self.is_end(index) {
+            let size = get_utf8_length(self.content[index]);
+            if current + size > offset {
+                break;
+            }
+            current += size;
+            index += 1;
+        }
+        if index > 0
+            && !self.is_end(index)
+            && is_low_surrogate(self.content[index])
+            && is_high_surrogate(self.content[index - 1])
+        {
+            // do not split a surrogate pair
+            index -= 1;
+        }
+        index
+    }
+
+    /// Gets the range in the UTF-8 encoding of this text that corresponds to the specified span
+    pub fn get_utf8_range(&self, span: TextSpan) -> Range<usize> {
+        let start = self.get_utf8_offset(span.index);
+        let mut end = start;
+        for i in span.index..(span.index + span.length) {
+            end += get_utf8_length(self.content[i]);
+        }
+        start..end
+    }
+
+    /// Gets the span that corresponds to the specified range in the UTF-8 encoding of this text
+    pub fn get_span_for_utf8_range(&self, range: Range<usize>) -> TextSpan {
+        let index = self.get_index_at_utf8_offset(range.start);
+        let end = self.get_index_at_utf8_offset(range.end);
+        TextSpan {
+            index,
+            length: end - index
+        }
+    }
+
+    /// Gets the index in the content for the given position
+    /// When the column is beyond the end of the line, the index of the line's end is returned
+    pub fn get_index_at(&self, position: TextPosition) -> usize {
+        let line_index = self.get_line_index(position.line);
+        let end = line_index + self.get_line_length(position.line);
+        let mut index = line_index;
+        let mut column = 0;
+        while index < end && column + 1 < position.column {
+            let (c, size) = self.get_code_point_at(index);
+            column += self.column_mode.get_width_of(c, column);
+            index += size;
+        }
+        index
+    }
+
+    /// Gets the number of columns between two indices on the same line
+    fn get_columns_between(&self, start: usize, end: usize) -> usize {
+        let mut index = start;
+        let mut column = 0;
+        while index < end {
+            let (c, size) = self.get_code_point_at(index);
+            column += self.column_mode.get_width_of(c, column);
+            index += size;
+        }
+        column
+    }
+
+    /// Gets the code point starting at the given index, as well as its size in code units
+    /// An unpaired surrogate is returned as is, with a size of one code unit.
+    fn get_code_point_at(&self, index: usize) -> (u32, usize) {
+        let lead = self.content[index];
+        if is_high_surrogate(lead) && !self.is_end(index + 1) {
+            let trail = self.content[index + 1];
+            if is_low_surrogate(trail) {
+                let c = (((lead as u32) - 0xD800) << 10 | ((trail as u32) - 0xDC00)) + 0x10000;
+                return (c, 2);
+            }
+        }
+        (lead as u32, 1)
+    }
+
     /// Gets the context description for the current text at the specified position
     pub fn get_context_at(&self, position: TextPosition) -> TextContext {
         self.get_context_for(position, 1)
@@ -216,7 +626,8 @@ impl Text {
         if line_length == 0 {
             return TextContext {
                 content: String::from(""),
-                pointer: String::from("^")
+                pointer: String::from("^"),
+                source: self.get_source_location_for(position)
             };
         }
 
@@ -237,28 +648,90 @@ impl Text {
         while start < end && is_white_space(self.content[start]) {
             start = start + 1;
         }
-        if line_index + position.column - 1 < start {
+        let index = self.get_index_at(position);
+        if index < start {
             start = line_index;
         }
-        if line_index + position.column - 1 > end {
+        if index > end {
             end = line_index + line_length - 1;
         }
 
         // build the pointer
         let mut pointer = String::new();
-        for i in start..(line_index + position.column - 1) {
-            pointer.push(if self.content[i] == 0x0009 { '\t' } else { ' ' });
+        let mut column = self.get_columns_between(line_index, start);
+        let mut i = start;
+        while i < index {
+            let (c, size) = self.get_code_point_at(i);
+            let width = self.get_pointer_width(c, column);
+            if self.is_display_mode() {
+                for _j in 0..width {
+                    pointer.push(' ');
+                }
+            } else {
+                pointer.push(if c == 0x0009 { '\t' } else { ' ' });
+            }
+            column += width;
+            i += size;
+        }
+        let mut carets = 0;
+        while i < index + length && !self.is_end(i) {
+            let (c, size) = self.get_code_point_at(i);
+            let width = self.get_pointer_width(c, column);
+            carets += width;
+            column += width;
+            i += size;
         }
         pointer.push('^');
-        for _i in 1..length {
+        for _i in 1..carets {
             pointer.push('^');
         }
 
         // return the output
         TextContext {
-            content: utf16_to_string(&self.content, start, end - start + 1),
-            pointer
+            content: if self.is_display_mode() {
+                self.get_displayed_value(line_index, start, end + 1)
+            } else {
+                utf16_to_string(&self.content, start, end - start + 1)
+            },
+            pointer,
+            source: self.get_source_location_at(index)
+        }
+    }
+
+    /// Gets whether the columns are expressed in display cells
+    fn is_display_mode(&self) -> bool {
+        matches!(self.column_mode, ColumnMode::Display { .. })
+    }
+
+    /// Gets the width in the pointer of a context for the specified code point
+    /// In display mode, this is the number of cells, otherwise one character per code point.
+    fn get_pointer_width(&self, c: u32, column: usize) -> usize {
+        if self.is_display_mode() {
+            self.column_mode.get_width_of(c, column)
+        } else {
+            1
+        }
+    }
+
+    /// Gets the value between two indices on the line starting at the given index, with tabs expanded to spaces
+    fn get_displayed_value(&self, line_index: usize, start: usize, end: usize) -> String {
+        let mut result = String::new();
+        let mut column = self.get_columns_between(line_index, start);
+        let mut i = start;
+        while i < end {
+            let (c, size) = self.get_code_point_at(i);
+            let width = self.column_mode.get_width_of(c, column);
+            if c == 0x0009 {
+                for _j in 0..width {
+                    result.push(' ');
+                }
+            } else {
+                result.push(::std::char::from_u32(c).unwrap_or('\u{FFFD}'));
+            }
+            column += width;
+            i += size;
         }
+        result
     }
 
     /// Gets the context description for the current text at the specified span
@@ -421,36 +894,119 @@ fn is_white_space(c: Utf16C) -> bool {
     c == 0x0020 || c == 0x0009 || c == 0x000B || c == 0x000C
 }
 
+/// Determines whether the code unit is a UTF-16 high surrogate
+fn is_high_surrogate(c: Utf16C) -> bool {
+    (0xD800..=0xDBFF).contains(&c)
+}
+
+/// Determines whether the code unit is a UTF-16 low surrogate
+fn is_low_surrogate(c: Utf16C) -> bool {
+    (0xDC00..=0xDFFF).contains(&c)
+}
+
+/// Gets the number of bytes contributed by a UTF-16 code unit to the UTF-8 encoding
+/// Each surrogate counts for two bytes, so that a surrogate pair yields the four bytes of the encoded code point.
+fn get_utf8_length(c: Utf16C) -> usize {
+    if c < 0x80 {
+        1
+    } else if c < 0x800 || (0xD800..=0xDFFF).contains(&c) {
+        2
+    } else {
+        3
+    }
+}
+
+/// Determines whether the code point takes no cell when displayed (combining marks and zero-width characters)
+fn is_zero_width(c: u32) -> bool {
+    (0x0300..=0x036F).contains(&c)
+        || (0x1AB0..=0x1AFF).contains(&c)
+        || (0x1DC0..=0x1DFF).contains(&c)
+        || (0x200B..=0x200F).contains(&c)
+        || (0x20D0..=0x20FF).contains(&c)
+        || (0xFE00..=0xFE0F).contains(&c)
+        || (0xFE20..=0xFE2F).contains(&c)
+        || c == 0xFEFF
+}
+
+/// Determines whether the code point is an East-Asian wide or full-width character
+fn is_wide(c: u32) -> bool {
+    (0x1100..=0x115F).contains(&c)
+        || (0x2E80..=0x303E).contains(&c)
+        || (0x3041..=0x33FF).contains(&c)
+        || (0x3400..=0x4DBF).contains(&c)
+        || (0x4E00..=0x9FFF).contains(&c)
+        || (0xA000..=0xA4CF).contains(&c)
+        || (0xAC00..=0xD7A3).contains(&c)
+        || (0xF900..=0xFAFF).contains(&c)
+        || (0xFE30..=0xFE4F).contains(&c)
+        || (0xFF00..=0xFF60).contains(&c)
+        || (0xFFE0..=0xFFE6).contains(&c)
+        || (0x1F300..=0x1F64F).contains(&c)
+        || (0x1F900..=0x1F9FF).contains(&c)
+        || (0x20000..=0x2FFFD).contains(&c)
+        || (0x30000..=0x3FFFD).contains(&c)
+}
+
 /// Finds all the lines in this content
-fn find_lines_in<'a, T: Iterable<'a, Item = Utf16C>>(iterable: &'a T) -> Vec<usize> {
+/// Returns the starting indices of the lines, as well as their starting offsets in the UTF-8 encoding
+fn find_lines_in<'a, T: Iterable<'a, Item = Utf16C>>(iterable: &'a T) -> (Vec<usize>, Vec<usize>) {
     let mut result = Vec::<usize>::new();
+    let mut offsets = Vec::<usize>::new();
+    find_lines_into(iterable, &mut result, &mut offsets);
+    (result, offsets)
+}
+
+/// Finds all the lines in this content into the specified buffers
+fn find_lines_into<'a, T: Iterable<'a, Item = Utf16C>>(
+    iterable: &'a T,
+    result: &mut Vec<usize>,
+    offsets: &mut Vec<usize>
+) {
+    result.clear();
+    offsets.clear();
     let mut c1;
     let mut c2 = 0;
     let mut i = 0;
+    let mut offset = 0;
     result.push(0);
+    offsets.push(0);
     for x in iterable.iter() {
         c1 = c2;
         c2 = x;
         if is_line_ending(c1, c2) {
-            result.push(if c1 == 0x000D && c2 != 0x000A {
-                i
+            if c1 == 0x000D && c2 != 0x000A {
+                result.push(i);
+                offsets.push(offset);
             } else {
-                i + 1
-            });
+                result.push(i + 1);
+                offsets.push(offset + get_utf8_length(c2));
+            }
         }
         i = i + 1;
+        offset += get_utf8_length(c2);
     }
-    result
 }
 
-/// Finds the index of the line at the given input index in the content
-fn find_line_at(lines: &Vec<usize>, index: usize) -> usize {
-    for i in 1..lines.len() {
-        if index < lines[i] {
-            return i - 1;
+/// Pushes the UTF-16 code units of a string at the end of a content
+fn push_str(content: &mut BigList<Utf16C>, input: &str) {
+    for c in input.chars() {
+        let value = c as u32;
+        if value <= 0xFFFF {
+            content.push(value as u16);
+        } else {
+            let temp = value - 0x10000;
+            let lead = (temp >> 10) + 0xD800;
+            let trail = (temp & 0x03FF) + 0xDC00;
+            content.push(lead as Utf16C);
+            content.push(trail as Utf16C);
         }
     }
-    return lines.len() - 1;
+}
+
+/// Finds the index of the line at the given input index in the content
+fn find_line_at(lines: &[usize], index: usize) -> usize {
+    // the first line always starts at 0
+    lines.partition_point(|&start| start <= index) - 1
 }
 
 /// Converts an excerpt of a UTF-16 buffer to a string
@@ -497,3 +1053,147 @@ fn test_read_utf8() {
     assert_eq!(0x79, content[5]);
     assert_eq!(0x78, content[6]);
 }
+
+#[test]
+fn test_text_column_modes() {
+    let mut text = Text::new("a\u{1F600}b\n\t\u{4E2D}x");
+    assert_eq!(text.get_position_at(3).column, 4);
+    text.set_column_mode(ColumnMode::CodePoints);
+    assert_eq!(text.get_position_at(3).column, 3);
+    text.set_column_mode(ColumnMode::Utf8Bytes);
+    assert_eq!(text.get_position_at(3).column, 6);
+    text.set_column_mode(ColumnMode::Display {
+        tab_width: 4,
+        wide_chars: true
+    });
+    let position = text.get_position_at(7);
+    assert_eq!(position.line, 2);
+    assert_eq!(position.column, 7);
+    assert_eq!(text.get_index_at(position), 7);
+    let context = text.get_context_for(position, 1);
+    assert_eq!(context.content, "\u{4E2D}x");
+    assert_eq!(context.pointer, "  ^");
+}
+
+#[test]
+fn test_text_utf8_offsets() {
+    let input = "é\u{1F600}\nab";
+    let text = Text::new(input);
+    assert_eq!(text.get_utf8_offset(1), 2);
+    assert_eq!(text.get_utf8_offset(4), 7);
+    assert_eq!(text.get_index_at_utf8_offset(4), 1);
+    assert_eq!(text.get_index_at_utf8_offset(8), 5);
+    let range = text.get_utf8_range(TextSpan {
+        index: 1,
+        length: 2
+    });
+    assert_eq!(&input[range.clone()], "\u{1F600}");
+    let span = text.get_span_for_utf8_range(range);
+    assert_eq!(span.index, 1);
+    assert_eq!(span.length, 2);
+}
+
+#[test]
+fn test_text_source_map() {
+    let mut text = Text::new("a\nbc\nd");
+    text.add_source_segment(SourceSegment {
+        index: 2,
+        length: 4,
+        file: String::from("included"),
+        offset: 10,
+        position: TextPosition { line: 4, column: 7 }
+    });
+    text.add_source_segment(SourceSegment {
+        index: 0,
+        length: 2,
+        file: String::from("main"),
+        offset: 0,
+        position: TextPosition { line: 1, column: 1 }
+    });
+    let location = text.get_source_location_at(1).unwrap();
+    assert_eq!(location.file, "main");
+    assert_eq!(location.position.line, 1);
+    assert_eq!(location.position.column, 2);
+    let location = text.get_source_location_at(3).unwrap();
+    assert_eq!(location.file, "included");
+    assert_eq!(location.offset, 11);
+    assert_eq!(location.position.column, 8);
+    let location = text.get_source_location_at(5).unwrap();
+    assert_eq!(location.position.line, 5);
+    assert_eq!(location.position.column, 1);
+    assert_eq!(format!("{}", location), "included:5:1");
+}
+
+#[test]
+fn test_text_apply_edit() {
+    let mut text = Text::new("ab\ncd\r\nef\ngh");
+    text.apply_edit(
+        TextSpan {
+            index: 3,
+            length: 4
+        },
+        "x\ny\rz"
+    );
+    let expected = Text::new("ab\nx\ny\rzef\ngh");
+    assert_eq!(text.get_version(), 1);
+    assert_eq!(text.len(), expected.len());
+    assert_eq!(text.lines, expected.lines);
+    assert_eq!(text.utf8_lines, expected.utf8_lines);
+    assert_eq!(text.get_line_content(4), "zef\n");
+    text.apply_edit(
+        TextSpan {
+            index: 0,
+            length: text.len()
+        },
+        "\u{E9}\r"
+    );
+    let expected = Text::new("\u{E9}\r");
+    assert_eq!(text.lines, expected.lines);
+    assert_eq!(text.utf8_lines, expected.utf8_lines);
+}
+
+#[test]
+fn test_text_apply_edit_after_lone_cr() {
+    let mut text = Text::new("a\u{E9}\r\n\r\u{1F600}\n");
+    text.apply_edit(
+        TextSpan {
+            index: 8,
+            length: 0
+        },
+        ""
+    );
+    assert_eq!(text.lines, vec![0, 4, 5, 8]);
+    assert_eq!(text.utf8_lines, vec![0, 5, 6, 11]);
+    text.apply_edit(
+        TextSpan {
+            index: 5,
+            length: 2
+        },
+        "\u{1F600}x"
+    );
+    let expected = Text::new("a\u{E9}\r\n\r\u{1F600}x\n");
+    assert_eq!(text.lines, expected.lines);
+    assert_eq!(text.utf8_lines, expected.utf8_lines);
+}
+
+#[test]
+fn test_text_reset() {
+    let mut text = Text::new("a first\ntext\nwith three lines");
+    text.add_source_segment(SourceSegment {
+        index: 0,
+        length: 7,
+        file: String::from("main"),
+        offset: 0,
+        position: TextPosition { line: 1, column: 1 }
+    });
+    let version = text.get_version();
+    text.reset("x\u{1F600}\ny");
+    assert_eq!(text.get_version(), version + 1);
+    assert_eq!(text.len(), 5);
+    assert_eq!(text.get_line_count(), 2);
+    assert_eq!(text.get_value(3, 2), "\ny");
+    assert!(text.get_source_location_at(0).is_none());
+    text.reset_from_utf8_stream(&mut "é\n".as_bytes());
+    assert_eq!(text.get_line_count(), 2);
+    assert_eq!(text.get_value(0, 1), "é");
+}
@@ -17,6 +17,9 @@
 
 //! Module for the definition of lexical tokens
 
+use std::ops::Range;
+
+use super::lexers::LexicalAmbiguity;
 use super::symbols::SemanticElementTrait;
 use super::symbols::Symbol;
 use super::text::Text;
@@ -27,40 +30,94 @@ use super::utils::biglist::BigList;
 use super::utils::iterable::Iterable;
 use super::utils::EitherMut;
 
+/// The channel of the tokens that are visible to the parser
+pub const CHANNEL_DEFAULT: u16 = 0;
+/// The channel of the separators retained as trivia
+pub const CHANNEL_TRIVIA: u16 = 1;
+
 /// Represents the metadata of a token
 #[derive(Copy, Clone)]
 struct TokenRepositoryCell {
     /// The terminal's index
     terminal: usize,
     /// The span of this token
-    span: TextSpan
+    span: TextSpan,
+    /// The number of hidden tokens matched before this token
+    hidden: usize
+}
+
+/// Represents the metadata of a hidden token
+#[derive(Copy, Clone)]
+struct HiddenTokenCell {
+    /// The terminal's index
+    terminal: usize,
+    /// The span of this token
+    span: TextSpan,
+    /// The channel of this token
+    channel: u16
 }
 
 /// Implementation data of a repository of matched tokens
+/// The tokens matched by the lexer are sorted by their span in the input and do not overlap,
+/// which is the invariant the lookups by position rely on.
+/// The synthetic tokens recorded by token filters are outside of this order
+/// and are not considered by the lookups by position.
 pub struct TokenRepositoryImpl {
-    /// The token data in this content
-    cells: BigList<TokenRepositoryCell>
+    /// The token data in this content, in the order they are given to the parser
+    cells: BigList<TokenRepositoryCell>,
+    /// The indices in the cells of the tokens matched by the lexer, in increasing order
+    lexed: Vec<usize>,
+    /// The hidden tokens (trivia and other side channels) retained between the tokens, if any
+    hidden: BigList<HiddenTokenCell>,
+    /// The lexical ambiguities recorded by the lexer, if any
+    ambiguities: Vec<LexicalAmbiguity>,
+    /// The version of the input text the tokens have been matched against
+    version: usize
 }
 
 impl TokenRepositoryImpl {
     /// Creates a new implementation of a token repository
     pub fn new() -> TokenRepositoryImpl {
+        let neutral = TokenRepositoryCell {
+            terminal: 0,
+            span: TextSpan {
+                index: 0,
+                length: 0
+            },
+            hidden: 0
+        };
         TokenRepositoryImpl {
-            cells: BigList::new(TokenRepositoryCell {
+            cells: BigList::new(neutral),
+            lexed: Vec::new(),
+            hidden: BigList::new(HiddenTokenCell {
                 terminal: 0,
-                span: TextSpan {
-                    index: 0,
-                    length: 0
-                }
-            })
+                span: neutral.span,
+                channel: CHANNEL_DEFAULT
+            }),
+            ambiguities: Vec::new(),
+            version: 0
         }
     }
+
+    /// Removes all the tokens, keeping the allocated storage
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.lexed.clear();
+        self.hidden.clear();
+        self.ambiguities.clear();
+        self.version = 0;
+    }
+
+    /// Gets the lexical ambiguities recorded by the lexer
+    pub fn get_ambiguities(&self) -> &[LexicalAmbiguity] {
+        &self.ambiguities
+    }
 }
 
 /// The proxy structure for a repository of matched tokens
 pub struct TokenRepository<'a> {
     /// The table of grammar terminals
-    terminals: &'static [Symbol],
+    terminals: &'a [Symbol],
     /// The input text
     text: &'a Text,
     /// The table of matched tokens
@@ -88,6 +145,90 @@ impl<'a> Clone for Token<'a> {
 /// Implementation of `Copy` for `Token`
 impl<'a> Copy for Token<'a> {}
 
+/// Represents a hidden token, i.e. a token retained by a lexer on a side channel but not shown to the parser.
+/// Separators retained as trivia are on the `CHANNEL_TRIVIA` channel.
+pub struct HiddenToken<'a> {
+    /// The repository containing this token
+    repository: &'a TokenRepository<'a>,
+    /// The index of this token among the hidden tokens of the repository
+    pub index: usize
+}
+
+impl<'a> HiddenToken<'a> {
+    /// Gets the channel of this hidden token
+    pub fn get_channel(&self) -> u16 {
+        self.repository.data.get().hidden[self.index].channel
+    }
+}
+
+impl<'a> SemanticElementTrait for HiddenToken<'a> {
+    /// Gets the position in the input text of this element
+    fn get_position(&self) -> Option<TextPosition> {
+        Some(
+            self.repository
+                .text
+                .get_position_at(self.repository.data.get().hidden[self.index].span.index)
+        )
+    }
+
+    /// Gets the span in the input text of this element
+    fn get_span(&self) -> Option<TextSpan> {
+        Some(self.repository.data.get().hidden[self.index].span)
+    }
+
+    /// Gets the context of this element in the input
+    fn get_context(&self) -> Option<TextContext> {
+        Some(
+            self.repository
+                .text
+                .get_context_of(self.repository.data.get().hidden[self.index].span)
+        )
+    }
+
+    /// Gets the grammar symbol associated to this element
+    fn get_symbol(&self) -> Symbol {
+        self.repository.terminals[self.repository.data.get().hidden[self.index].terminal].clone()
+    }
+
+    /// Gets the value of this element, if any
+    fn get_value(&self) -> Option<String> {
+        Some(
+            self.repository
+                .text
+                .get_value_for(self.repository.data.get().hidden[self.index].span)
+        )
+    }
+}
+
+/// The iterator over the hidden tokens of a channel in a repository
+pub struct TokenChannelIterator<'a> {
+    /// The repository containing the tokens
+    repository: &'a TokenRepository<'a>,
+    /// The channel to iterate over
+    channel: u16,
+    /// The current index within the hidden tokens
+    index: usize
+}
+
+/// Implementation of `Iterator` for `TokenChannelIterator`
+impl<'a> Iterator for TokenChannelIterator<'a> {
+    type Item = HiddenToken<'a>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let data = self.repository.data.get();
+        while self.index < data.hidden.len() {
+            let index = self.index;
+            self.index += 1;
+            if data.hidden[index].channel == self.channel {
+                return Some(HiddenToken {
+                    repository: self.repository,
+                    index
+                });
+            }
+        }
+        None
+    }
+}
+
 /// the iterator over the tokens in a repository
 pub struct TokenRepositoryIterator<'a> {
     /// The repository containing this token
@@ -128,7 +269,7 @@ impl<'a> Iterable<'a> for TokenRepository<'a> {
 impl<'a> TokenRepository<'a> {
     /// Creates a new repository
     pub fn new(
-        terminals: &'static [Symbol],
+        terminals: &'a [Symbol],
         text: &'a Text,
         tokens: &'a TokenRepositoryImpl
     ) -> TokenRepository<'a> {
@@ -141,7 +282,7 @@ impl<'a> TokenRepository<'a> {
 
     /// Creates a new mutable repository
     pub fn new_mut(
-        terminals: &'static [Symbol],
+        terminals: &'a [Symbol],
         text: &'a Text,
         tokens: &'a mut TokenRepositoryImpl
     ) -> TokenRepository<'a> {
@@ -153,20 +294,164 @@ impl<'a> TokenRepository<'a> {
     }
 
     /// Registers a new token in this repository
+    /// The tokens must be registered in the order of their spans:
+    /// a token cannot start before the end of the previously registered one.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the new token starts before the end of the previous token.
     pub fn add(&mut self, terminal: usize, index: usize, length: usize) -> usize {
+        let version = self.text.get_version();
+        let x = self.data.get_mut();
+        match x {
+            None => panic!("Got a mutable token repository with an immutable implementation"),
+            Some(data) => {
+                if let Some(&last) = data.lexed.last() {
+                    let last = data.cells[last].span;
+                    assert!(
+                        index >= last.index + last.length,
+                        "Token at {} registered before the end of the previous token at {}",
+                        index,
+                        last.index + last.length
+                    );
+                }
+                if terminal == 1 {
+                    // the lexer reached the end of the input, the tokens match the current text
+                    // the index of the $ symbol is always 1
+                    data.version = version;
+                }
+                let hidden = data.hidden.len();
+                let result = data.cells.push(TokenRepositoryCell {
+                    terminal,
+                    span: TextSpan { index, length },
+                    hidden
+                });
+                data.lexed.push(result);
+                result
+            }
+        }
+    }
+
+    /// Registers a new synthetic token in this repository
+    /// The span of a synthetic token is free, for example a part of a token matched by the lexer.
+    /// Synthetic tokens are given to the parser as the other tokens,
+    /// but are not considered by the lookups by position in the input.
+    pub fn add_synthetic(&mut self, terminal: usize, index: usize, length: usize) -> usize {
         let x = self.data.get_mut();
         match x {
             None => panic!("Got a mutable token repository with an immutable implementation"),
-            Some(data) => data.cells.push(TokenRepositoryCell {
+            Some(data) => {
+                let hidden = data.hidden.len();
+                data.cells.push(TokenRepositoryCell {
+                    terminal,
+                    span: TextSpan { index, length },
+                    hidden
+                })
+            }
+        }
+    }
+
+    /// Records a lexical ambiguity for a token of this repository
+    pub fn add_ambiguity(&mut self, ambiguity: LexicalAmbiguity) {
+        let x = self.data.get_mut();
+        match x {
+            None => panic!("Got a mutable token repository with an immutable implementation"),
+            Some(data) => data.ambiguities.push(ambiguity)
+        }
+    }
+
+    /// Registers a new piece of trivia (a separator) in this repository
+    /// The trivia is attached to the last registered token and to the next one.
+    pub fn add_trivia(&mut self, terminal: usize, index: usize, length: usize) -> usize {
+        self.add_hidden(terminal, index, length, CHANNEL_TRIVIA)
+    }
+
+    /// Registers a new hidden token on the specified channel in this repository
+    pub fn add_hidden(
+        &mut self,
+        terminal: usize,
+        index: usize,
+        length: usize,
+        channel: u16
+    ) -> usize {
+        let x = self.data.get_mut();
+        match x {
+            None => panic!("Got a mutable token repository with an immutable implementation"),
+            Some(data) => data.hidden.push(HiddenTokenCell {
                 terminal,
-                span: TextSpan { index, length }
+                span: TextSpan { index, length },
+                channel
+            })
+        }
+    }
+
+    /// Gets the number of retained hidden tokens, including trivia
+    pub fn get_hidden_count(&self) -> usize {
+        self.data.get().hidden.len()
+    }
+
+    /// Gets the i-th hidden token
+    pub fn get_hidden(&'a self, index: usize) -> HiddenToken<'a> {
+        HiddenToken {
+            repository: self,
+            index
+        }
+    }
+
+    /// Gets an iterator over the hidden tokens on the specified channel
+    pub fn iter_channel(&'a self, channel: u16) -> TokenChannelIterator<'a> {
+        TokenChannelIterator {
+            repository: self,
+            channel,
+            index: 0
+        }
+    }
+
+    /// Gets the hidden tokens between two tokens, on all the channels
+    pub fn get_hidden_between(&self, first: &Token, second: &Token) -> Vec<HiddenToken<'_>> {
+        let data = self.data.get();
+        let start = data.cells[first.index].hidden;
+        let end = data.cells[second.index].hidden;
+        (start..end.max(start))
+            .map(|index| HiddenToken {
+                repository: self,
+                index
             })
+            .collect()
+    }
+
+    /// Gets the range of the trivia between the i-th token and the next one
+    /// The first part of the range (up to the split) is the trailing trivia of the i-th token,
+    /// i.e. the trivia on the same line, the rest is the leading trivia of the next token.
+    fn get_trivia_after(&self, index: usize) -> (usize, usize, usize) {
+        let data = self.data.get();
+        let cell = data.cells[index];
+        let start = cell.hidden;
+        let end = if index + 1 < data.cells.len() {
+            data.cells[index + 1].hidden
+        } else {
+            data.hidden.len()
+        };
+        let line = self
+            .text
+            .get_position_at(cell.span.index + cell.span.length.max(1) - 1)
+            .line;
+        let mut split = start;
+        while split < end
+            && self
+                .text
+                .get_position_at(data.hidden[split].span.index)
+                .line
+                == line
+        {
+            split += 1;
         }
+        (start, split, end)
     }
 
     /// Gets the terminals
-    pub fn get_terminals(&self) -> &'static [Symbol] {
-        &self.terminals
+    pub fn get_terminals(&self) -> &'a [Symbol] {
+        self.terminals
     }
 
     /// Gets the input text
@@ -174,11 +459,42 @@ impl<'a> TokenRepository<'a> {
         &self.text
     }
 
+    /// Gets the version of the input text the tokens have been matched against
+    pub fn get_text_version(&self) -> usize {
+        self.data.get().version
+    }
+
+    /// Gets whether the input text has been edited since the tokens have been matched
+    pub fn is_stale(&self) -> bool {
+        self.data.get().version != self.text.get_version()
+    }
+
     /// Gets the number of tokens in this repository
     pub fn get_tokens_count(&self) -> usize {
         self.data.get().cells.len()
     }
 
+    /// Gets the lexical ambiguities recorded by the lexer
+    pub fn get_ambiguities(&self) -> &[LexicalAmbiguity] {
+        self.data.get().get_ambiguities()
+    }
+
+    /// Gets whether the i-th token is a synthetic token
+    pub fn is_synthetic(&self, index: usize) -> bool {
+        let data = self.data.get();
+        index < data.cells.len() && data.lexed.binary_search(&index).is_err()
+    }
+
+    /// Gets the number of tokens matched by the lexer, i.e. without the synthetic tokens
+    fn get_lexed_count(&self) -> usize {
+        self.data.get().lexed.len()
+    }
+
+    /// Gets the index in the repository of the i-th token matched by the lexer
+    fn get_lexed_index(&self, rank: usize) -> usize {
+        self.data.get().lexed[rank]
+    }
+
     /// Gets the terminal's identifier for the i-th token
     pub fn get_symbol_id_for(&self, index: usize) -> u32 {
         self.terminals[self.data.get().cells[index].terminal].id
@@ -198,32 +514,154 @@ impl<'a> TokenRepository<'a> {
     }
 
     /// Gets the token (if any) that contains the specified index in the input text
-    pub fn find_token_at(&self, index: usize) -> Option<Token> {
-        let data = self.data.get();
-        let count = data.cells.len();
-        if count == 0 {
+    pub fn find_token_at(&self, index: usize) -> Option<Token<'_>> {
+        let rank = self.find_first_ending_after(index);
+        if rank >= self.get_lexed_count() {
+            return None;
+        }
+        let token_index = self.get_lexed_index(rank);
+        if self.data.get().cells[token_index].span.index > index {
+            // the first token ending after the index starts after it
             return None;
         }
-        let mut l: usize = 0;
-        let mut r = count - 1;
-        while l <= r {
+        Some(Token {
+            repository: self,
+            index: token_index
+        })
+    }
+
+    /// Gets the tokens matched by the lexer that overlap the specified span, in order
+    /// For an empty span, gets the token that contains its starting index, if any.
+    pub fn find_tokens_in(&self, span: TextSpan) -> Vec<Token<'_>> {
+        let end = span.index + span.length.max(1);
+        let first = self.find_first_ending_after(span.index);
+        let last = self.find_first_starting_at(end);
+        (first..last.max(first))
+            .map(|rank| Token {
+                repository: self,
+                index: self.get_lexed_index(rank)
+            })
+            .collect()
+    }
+
+    /// Gets the tokens matched by the lexer that overlap the specified line, in order
+    /// The line number is 1-based.
+    pub fn find_tokens_on_line(&self, line: usize) -> Vec<Token<'_>> {
+        if line == 0 || line > self.text.get_line_count() {
+            return Vec::new();
+        }
+        let span = TextSpan {
+            index: self.text.get_line_index(line),
+            length: self.text.get_line_length(line)
+        };
+        if span.length == 0 {
+            return Vec::new();
+        }
+        self.find_tokens_in(span)
+    }
+
+    /// Gets the rank among the tokens matched by the lexer
+    /// of the first token that ends after the specified index in the input
+    fn find_first_ending_after(&self, index: usize) -> usize {
+        let data = self.data.get();
+        let mut l = 0;
+        let mut r = self.get_lexed_count();
+        while l < r {
             let m = (l + r) / 2;
-            let cell = data.cells[m];
-            if index < cell.span.index {
-                // look on the left
-                r = m - 1;
-            } else if index < cell.span.index + cell.span.length {
-                // within the token
-                return Some(Token {
-                    repository: self,
-                    index: m
-                });
+            let cell = data.cells[self.get_lexed_index(m)];
+            if cell.span.index + cell.span.length > index {
+                r = m;
             } else {
-                // look on the right
                 l = m + 1;
             }
         }
-        None
+        l
+    }
+
+    /// Gets the rank among the tokens matched by the lexer
+    /// of the first token that starts at or after the specified index in the input
+    fn find_first_starting_at(&self, index: usize) -> usize {
+        let data = self.data.get();
+        let mut l = 0;
+        let mut r = self.get_lexed_count();
+        while l < r {
+            let m = (l + r) / 2;
+            if data.cells[self.get_lexed_index(m)].span.index >= index {
+                r = m;
+            } else {
+                l = m + 1;
+            }
+        }
+        l
+    }
+}
+
+impl<'a> Token<'a> {
+    /// Gets the next token in the repository, if any
+    pub fn get_next(&self) -> Option<Token<'a>> {
+        if self.index + 1 >= self.repository.get_tokens_count() {
+            None
+        } else {
+            Some(Token {
+                repository: self.repository,
+                index: self.index + 1
+            })
+        }
+    }
+
+    /// Gets the previous token in the repository, if any
+    pub fn get_previous(&self) -> Option<Token<'a>> {
+        if self.index == 0 {
+            None
+        } else {
+            Some(Token {
+                repository: self.repository,
+                index: self.index - 1
+            })
+        }
+    }
+
+    /// Gets the trivia retained before this token (separators since the previous token's line)
+    /// The hidden tokens on the other channels are not included.
+    pub fn get_leading_trivia(&self) -> Vec<HiddenToken<'a>> {
+        let (start, end) = if self.index == 0 {
+            (0, self.repository.data.get().cells[0].hidden)
+        } else {
+            let (_, split, end) = self.repository.get_trivia_after(self.index - 1);
+            (split, end)
+        };
+        self.get_trivia_in(start, end)
+    }
+
+    /// Gets the trivia retained after this token on the same line
+    /// The hidden tokens on the other channels are not included.
+    pub fn get_trailing_trivia(&self) -> Vec<HiddenToken<'a>> {
+        let (start, split, _) = self.repository.get_trivia_after(self.index);
+        self.get_trivia_in(start, split)
+    }
+
+    /// Gets the trivia within the specified range of hidden tokens
+    fn get_trivia_in(&self, start: usize, end: usize) -> Vec<HiddenToken<'a>> {
+        let data = self.repository.data.get();
+        (start..end)
+            .filter(|&index| data.hidden[index].channel == CHANNEL_TRIVIA)
+            .map(|index| HiddenToken {
+                repository: self.repository,
+                index
+            })
+            .collect()
+    }
+
+    /// Gets whether the input text has been edited since this token has been matched
+    pub fn is_stale(&self) -> bool {
+        self.repository.is_stale()
+    }
+
+    /// Gets the range of this token in the UTF-8 encoding of the input text
+    pub fn get_utf8_range(&self) -> Range<usize> {
+        self.repository
+            .text
+            .get_utf8_range(self.repository.data.get().cells[self.index].span)
     }
 }
 
@@ -252,7 +690,7 @@ impl<'a> SemanticElementTrait for Token<'a> {
 
     /// Gets the grammar symbol associated to this element
     fn get_symbol(&self) -> Symbol {
-        self.repository.terminals[self.repository.data.get().cells[self.index].terminal]
+        self.repository.terminals[self.repository.data.get().cells[self.index].terminal].clone()
     }
 
     /// Gets the value of this element, if any
@@ -264,3 +702,229 @@ impl<'a> SemanticElementTrait for Token<'a> {
         )
     }
 }
+
+#[test]
+fn test_token_trivia() {
+    const TERMINALS: &[Symbol] = &[
+        Symbol::new_static(1, "ε"),
+        Symbol::new_static(2, "$"),
+        Symbol::new_static(3, "SEPARATOR"),
+        Symbol::new_static(4, "ID")
+    ];
+    let text = Text::new("a  // x\n b");
+    let mut data = TokenRepositoryImpl::new();
+    {
+        let mut repository = TokenRepository::new_mut(TERMINALS, &text, &mut data);
+        repository.add(3, 0, 1);
+        repository.add_trivia(2, 1, 2);
+        repository.add_trivia(2, 3, 5);
+        repository.add_trivia(2, 8, 1);
+        repository.add(3, 9, 1);
+        repository.add(1, 10, 0);
+    }
+    let repository = TokenRepository::new(TERMINALS, &text, &data);
+    let first = repository.get_token(0);
+    let second = repository.get_token(1);
+    assert_eq!(first.get_leading_trivia().len(), 0);
+    let trailing = first.get_trailing_trivia();
+    assert_eq!(trailing.len(), 2);
+    assert_eq!(trailing[1].get_value().unwrap(), "// x\n");
+    let leading = second.get_leading_trivia();
+    assert_eq!(leading.len(), 1);
+    assert_eq!(leading[0].get_value().unwrap(), " ");
+    assert_eq!(second.get_trailing_trivia().len(), 0);
+    assert_eq!(repository.get_hidden_between(&first, &second).len(), 3);
+    assert_eq!(repository.iter_channel(CHANNEL_TRIVIA).count(), 3);
+}
+
+#[test]
+fn test_token_navigation() {
+    const TERMINALS: &[Symbol] = &[
+        Symbol::new_static(1, "ε"),
+        Symbol::new_static(2, "$"),
+        Symbol::new_static(3, "ID")
+    ];
+    let text = Text::new("ab cd\nef");
+    let mut data = TokenRepositoryImpl::new();
+    {
+        let mut repository = TokenRepository::new_mut(TERMINALS, &text, &mut data);
+        repository.add(2, 0, 2);
+        repository.add(2, 3, 2);
+        repository.add(2, 6, 2);
+        repository.add(1, 8, 0);
+    }
+    let repository = TokenRepository::new(TERMINALS, &text, &data);
+    let first = repository.get_token(0);
+    assert!(first.get_previous().is_none());
+    assert_eq!(first.get_next().unwrap().index, 1);
+    assert!(repository.get_token(3).get_next().is_none());
+    let found = repository.find_tokens_in(TextSpan {
+        index: 1,
+        length: 3
+    });
+    assert_eq!(
+        found.iter().map(|t| t.index).collect::<Vec<_>>(),
+        vec![0, 1]
+    );
+    assert_eq!(
+        repository
+            .find_tokens_in(TextSpan {
+                index: 2,
+                length: 1
+            })
+            .len(),
+        0
+    );
+    assert_eq!(repository.find_tokens_on_line(1).len(), 2);
+    assert_eq!(repository.find_tokens_on_line(2)[0].index, 2);
+}
+
+#[test]
+fn test_token_side_channel() {
+    use super::fixtures::HimeGrammarLexer;
+    use super::lexers::run_lexer;
+    use super::lexers::Lexer;
+    const ID_TERMINAL_NAME: u32 = 0x0009;
+    let fixture = HimeGrammarLexer::load();
+    let mut result = fixture.new_result("grammar Test { }");
+    {
+        let mut lexer = fixture.new_lexer(result.get_lexing_data());
+        lexer.set_trivia_retained(true);
+        lexer.set_terminal_channel(ID_TERMINAL_NAME, 2);
+        run_lexer(&mut lexer);
+    }
+    assert!(result.is_success());
+    let repository = result.get_tokens();
+    let values: Vec<String> = repository.iter().map(|t| t.get_value().unwrap()).collect();
+    assert_eq!(values, vec!["grammar", "{", "}", ""]);
+    let first = repository.get_token(0);
+    let second = repository.get_token(1);
+    // the name on the side channel is not trivia
+    let trailing = first.get_trailing_trivia();
+    assert_eq!(trailing.len(), 2);
+    assert!(trailing.iter().all(|t| t.get_channel() == CHANNEL_TRIVIA));
+    assert_eq!(second.get_leading_trivia().len(), 0);
+    let hidden = repository.get_hidden_between(&first, &second);
+    assert_eq!(hidden.len(), 3);
+    assert_eq!(hidden[1].get_channel(), 2);
+    let names: Vec<HiddenToken> = repository.iter_channel(2).collect();
+    assert_eq!(names.len(), 1);
+    assert_eq!(names[0].get_value().unwrap(), "Test");
+    assert_eq!(names[0].get_symbol().id, ID_TERMINAL_NAME);
+}
+
+#[test]
+fn test_token_zero_length() {
+    const TERMINALS: &[Symbol] = &[
+        Symbol::new_static(1, "ε"),
+        Symbol::new_static(2, "$"),
+        Symbol::new_static(3, "ID"),
+        Symbol::new_static(4, "INDENT")
+    ];
+    let text = Text::new("ab\n  cd");
+    let mut data = TokenRepositoryImpl::new();
+    {
+        // zero-length tokens may share their index with the next token
+        let mut repository = TokenRepository::new_mut(TERMINALS, &text, &mut data);
+        repository.add(2, 0, 2);
+        repository.add(3, 5, 0);
+        repository.add(2, 5, 2);
+        repository.add(1, 7, 0);
+    }
+    let repository = TokenRepository::new(TERMINALS, &text, &data);
+    assert_eq!(repository.find_token_at(5).unwrap().index, 2);
+    let found = repository.find_tokens_on_line(2);
+    assert_eq!(
+        found.iter().map(|t| t.index).collect::<Vec<_>>(),
+        vec![1, 2]
+    );
+}
+
+#[test]
+#[should_panic]
+fn test_token_out_of_order() {
+    const TERMINALS: &[Symbol] = &[
+        Symbol::new_static(1, "ε"),
+        Symbol::new_static(2, "$"),
+        Symbol::new_static(3, "ID")
+    ];
+    let text = Text::new("ab cd");
+    let mut data = TokenRepositoryImpl::new();
+    let mut repository = TokenRepository::new_mut(TERMINALS, &text, &mut data);
+    repository.add(2, 3, 2);
+    repository.add(2, 0, 2);
+}
+
+#[test]
+fn test_token_synthetic() {
+    const TERMINALS: &[Symbol] = &[
+        Symbol::new_static(1, "ε"),
+        Symbol::new_static(2, "$"),
+        Symbol::new_static(3, "ID"),
+        Symbol::new_static(4, ">")
+    ];
+    let text = Text::new("ab>>cd");
+    let mut data = TokenRepositoryImpl::new();
+    {
+        // >> is matched as a single token, then split in two synthetic tokens
+        let mut repository = TokenRepository::new_mut(TERMINALS, &text, &mut data);
+        repository.add(2, 0, 2);
+        repository.add(3, 2, 2);
+        repository.add_synthetic(3, 2, 1);
+        repository.add_synthetic(3, 3, 1);
+        repository.add(2, 4, 2);
+        repository.add(1, 6, 0);
+    }
+    let repository = TokenRepository::new(TERMINALS, &text, &data);
+    assert!(repository.is_synthetic(2));
+    assert!(repository.is_synthetic(3));
+    assert!(!repository.is_synthetic(4));
+    assert_eq!(repository.find_token_at(3).unwrap().index, 1);
+    assert_eq!(repository.find_token_at(5).unwrap().index, 4);
+    let found = repository.find_tokens_in(TextSpan {
+        index: 1,
+        length: 4
+    });
+    assert_eq!(
+        found.iter().map(|t| t.index).collect::<Vec<_>>(),
+        vec![0, 1, 4]
+    );
+}
+
+#[test]
+fn test_token_synthetic_scaling() {
+    const TERMINALS: &[Symbol] = &[
+        Symbol::new_static(1, "ε"),
+        Symbol::new_static(2, "$"),
+        Symbol::new_static(3, "ID"),
+        Symbol::new_static(4, ">")
+    ];
+    // every matched token is followed by a synthetic one,
+    // the registration and the lookups must not rescan the synthetic tokens
+    const COUNT: usize = 100_000;
+    let input = "a".repeat(COUNT);
+    let text = Text::new(&input);
+    let mut data = TokenRepositoryImpl::new();
+    {
+        let mut repository = TokenRepository::new_mut(TERMINALS, &text, &mut data);
+        for i in 0..COUNT {
+            repository.add(2, i, 1);
+            repository.add_synthetic(3, i, 1);
+        }
+        repository.add(1, COUNT, 0);
+    }
+    let repository = TokenRepository::new(TERMINALS, &text, &data);
+    assert_eq!(repository.get_count(), 2 * COUNT + 1);
+    for i in 0..COUNT {
+        assert_eq!(repository.find_token_at(i).unwrap().index, 2 * i);
+        assert!(repository.is_synthetic(2 * i + 1));
+    }
+    let found = repository.find_tokens_in(TextSpan {
+        index: COUNT - 2,
+        length: 2
+    });
+    assert_eq!(
+        found.iter().map(|t| t.index).collect::<Vec<_>>(),
+        vec![2 * COUNT - 4, 2 * COUNT - 2]
+    );
+}
@@ -72,6 +72,28 @@ impl<T: Copy> BigList<T> {
         result
     }
 
+    /// Shortens the list, keeping the first `len` items
+    /// The allocated chunks are kept for reuse.
+    pub fn truncate(&mut self, len: usize) {
+        if len >= self.len() {
+            return;
+        }
+        if len > 0 && len & LOWER_MASK == 0 {
+            // stay at the end of the previous chunk, as after a push
+            self.chunk_index = (len >> UPPER_SHIFT) - 1;
+            self.cell_index = CHUNKS_SIZE;
+        } else {
+            self.chunk_index = len >> UPPER_SHIFT;
+            self.cell_index = len & LOWER_MASK;
+        }
+    }
+
+    /// Removes all the items in this list
+    /// The allocated chunks are kept for reuse.
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
     /// Adds a new chunk to this list
     fn add_chunk(&mut self) {
         if self.chunk_index == self.chunks.len() - 1 {
@@ -144,3 +166,17 @@ fn test_big_list() {
         assert_eq!(x, 't');
     }
 }
+
+#[test]
+fn test_big_list_truncate() {
+    let mut list = BigList::<usize>::new(0);
+    for i in 0..600 {
+        list.push(i);
+    }
+    list.truncate(512);
+    assert_eq!(list.len(), 512);
+    assert_eq!(list.push(7), 512);
+    list.truncate(3);
+    assert_eq!(list.len(), 3);
+    assert_eq!(list[2], 2);
+}
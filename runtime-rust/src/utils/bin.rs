@@ -17,6 +17,12 @@
 
 //! Module for binary manipulation APIs
 
+use std::fmt::Display;
+use std::fmt::Error;
+use std::fmt::Formatter;
+
+use super::super::errors::AutomatonError;
+
 /// reads a u16 from an array of bytes
 pub fn read_u16(buffer: &[u8], index: usize) -> u16 {
     ((buffer[index + 1] as u16) << 8 | (buffer[index] as u16))
@@ -47,3 +53,293 @@ pub fn read_table_u32(buffer: &[u8], start: usize, count: usize) -> Vec<u32> {
     }
     result
 }
+
+/// A table of u16 read in place from little-endian binary data
+#[derive(Copy, Clone)]
+pub struct TableU16<'a> {
+    /// The binary data of the table
+    data: &'a [u8]
+}
+
+impl<'a> TableU16<'a> {
+    /// Creates a view of the table of the specified number of u16 at the specified index in a byte buffer
+    pub fn new(buffer: &'a [u8], start: usize, count: usize) -> TableU16<'a> {
+        TableU16 {
+            data: &buffer[start..(start + count * 2)]
+        }
+    }
+
+    /// Gets the number of u16 in this table
+    pub fn len(&self) -> usize {
+        self.data.len() / 2
+    }
+
+    /// Gets whether this table is empty
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Gets the u16 at the specified index in this table
+    #[inline]
+    pub fn get(&self, index: usize) -> u16 {
+        read_u16(self.data, index * 2)
+    }
+
+    /// Gets the binary data of this table
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.data
+    }
+}
+
+/// Writes a table of u16 at the end of a byte buffer
+pub fn write_table_u16(buffer: &mut Vec<u8>, table: &[u16]) {
+    buffer.reserve(table.len() * 2);
+    for value in table.iter() {
+        write_u16(buffer, *value);
+    }
+}
+
+/// Writes a table of u32 at the end of a byte buffer
+pub fn write_table_u32(buffer: &mut Vec<u8>, table: &[u32]) {
+    buffer.reserve(table.len() * 4);
+    for value in table.iter() {
+        write_u32(buffer, *value);
+    }
+}
+
+/// Checks that a table of the specified length in bytes is available at the specified index in a byte buffer
+pub fn check_table(
+    buffer: &[u8],
+    table: &'static str,
+    index: usize,
+    length: usize
+) -> Result<(), AutomatonError> {
+    if index <= buffer.len() && length <= buffer.len() - index {
+        Ok(())
+    } else {
+        Err(AutomatonError::TruncatedTable {
+            table,
+            offset: index,
+            required: length,
+            available: buffer.len().saturating_sub(index)
+        })
+    }
+}
+
+/// Checks that a table of the specified number of items is available at the specified index in a byte buffer
+/// Returns the length of the table in bytes.
+pub fn check_array(
+    buffer: &[u8],
+    table: &'static str,
+    index: usize,
+    count: usize,
+    item_length: usize
+) -> Result<usize, AutomatonError> {
+    match count.checked_mul(item_length) {
+        Some(length) => {
+            check_table(buffer, table, index, length)?;
+            Ok(length)
+        }
+        None => Err(AutomatonError::TruncatedTable {
+            table,
+            offset: index,
+            required: usize::MAX,
+            available: buffer.len().saturating_sub(index)
+        })
+    }
+}
+
+/// Checks that the data in a byte buffer ends at the specified index
+pub fn check_end(buffer: &[u8], index: usize) -> Result<(), AutomatonError> {
+    if index >= buffer.len() {
+        Ok(())
+    } else {
+        Err(AutomatonError::TrailingData {
+            offset: index,
+            length: buffer.len() - index
+        })
+    }
+}
+
+/// The magic number at the start of the header of an automaton
+pub const AUTOMATON_MAGIC: [u8; 4] = [b'H', b'I', b'M', b'E'];
+/// The current version of the binary format of automata
+pub const AUTOMATON_FORMAT_VERSION: u16 = 1;
+/// The length in bytes of the header of an automaton
+/// Binary data structure of the header:
+/// u8[4]: the magic number
+/// u16: the version of the binary format
+/// u16: the kind of automaton
+/// u32: the number of states in the automaton
+/// u32: the length in bytes of the automaton's data after the header
+/// u32: the Adler-32 checksum of the automaton's data after the header
+pub const AUTOMATON_HEADER_LENGTH: usize = 20;
+
+/// The kinds of automata
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum AutomatonKind {
+    /// The DFA of a lexer
+    Lexer = 1,
+    /// The automaton of a LR(k) parser
+    LRk = 2,
+    /// The automaton of a RNGLR parser
+    Rnglr = 3
+}
+
+impl Display for AutomatonKind {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        match *self {
+            AutomatonKind::Lexer => write!(f, "lexer"),
+            AutomatonKind::LRk => write!(f, "LR(k)"),
+            AutomatonKind::Rnglr => write!(f, "RNGLR")
+        }
+    }
+}
+
+/// Represents the header of an automaton
+#[derive(Copy, Clone)]
+pub struct AutomatonHeader {
+    /// The version of the binary format
+    pub version: u16,
+    /// The kind of automaton
+    pub kind: AutomatonKind,
+    /// The number of states in the automaton
+    pub states_count: u32,
+    /// The length in bytes of the automaton's data after the header
+    pub length: u32,
+    /// The Adler-32 checksum of the automaton's data after the header
+    pub checksum: u32
+}
+
+/// Computes the Adler-32 checksum of a byte buffer
+pub fn get_checksum(buffer: &[u8]) -> u32 {
+    let mut a: u32 = 1;
+    let mut b: u32 = 0;
+    for chunk in buffer.chunks(5552) {
+        for x in chunk.iter() {
+            a += *x as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+/// Gets whether the specified data starts with the header of an automaton
+pub fn has_header(data: &[u8]) -> bool {
+    data.len() >= AUTOMATON_MAGIC.len() && data[0..AUTOMATON_MAGIC.len()] == AUTOMATON_MAGIC
+}
+
+/// Reads the header of an automaton of the specified kind
+/// Returns `None` for legacy data without a header.
+pub fn read_header(
+    data: &[u8],
+    kind: AutomatonKind
+) -> Result<Option<AutomatonHeader>, AutomatonError> {
+    if !has_header(data) {
+        return Ok(None);
+    }
+    check_table(data, "header", 0, AUTOMATON_HEADER_LENGTH)?;
+    let version = read_u16(data, 4);
+    if version != AUTOMATON_FORMAT_VERSION {
+        return Err(AutomatonError::UnsupportedVersion { version });
+    }
+    let found = read_u16(data, 6);
+    if found != kind as u16 {
+        return Err(AutomatonError::WrongKind {
+            expected: kind,
+            found
+        });
+    }
+    Ok(Some(AutomatonHeader {
+        version,
+        kind,
+        states_count: read_u32(data, 8),
+        length: read_u32(data, 12),
+        checksum: read_u32(data, 16)
+    }))
+}
+
+/// Verifies the header of an automaton of the specified kind, if any
+/// Returns the header, if any, and the automaton's data after it.
+pub fn strip_header(
+    data: &[u8],
+    kind: AutomatonKind
+) -> Result<(Option<AutomatonHeader>, &[u8]), AutomatonError> {
+    let (header, payload) = split_header(data, kind)?;
+    if let Some(header) = header {
+        let checksum = get_checksum(payload);
+        if checksum != header.checksum {
+            return Err(AutomatonError::ChecksumMismatch {
+                expected: header.checksum,
+                found: checksum
+            });
+        }
+    }
+    Ok((header, payload))
+}
+
+/// Splits the header of an automaton of the specified kind, if any, from the automaton's data
+/// Unlike `strip_header`, the checksum is not verified so that this runs in constant time.
+pub fn split_header(
+    data: &[u8],
+    kind: AutomatonKind
+) -> Result<(Option<AutomatonHeader>, &[u8]), AutomatonError> {
+    match read_header(data, kind)? {
+        None => Ok((None, data)),
+        Some(header) => {
+            check_table(
+                data,
+                "automaton",
+                AUTOMATON_HEADER_LENGTH,
+                header.length as usize
+            )?;
+            check_end(data, AUTOMATON_HEADER_LENGTH + header.length as usize)?;
+            Ok((Some(header), &data[AUTOMATON_HEADER_LENGTH..]))
+        }
+    }
+}
+
+/// Writes the header of an automaton of the specified kind, followed by the automaton's data
+pub fn write_header(payload: &[u8], kind: AutomatonKind, states_count: u32) -> Vec<u8> {
+    let mut result = Vec::<u8>::with_capacity(AUTOMATON_HEADER_LENGTH + payload.len());
+    result.extend_from_slice(&AUTOMATON_MAGIC);
+    write_u16(&mut result, AUTOMATON_FORMAT_VERSION);
+    write_u16(&mut result, kind as u16);
+    write_u32(&mut result, states_count);
+    write_u32(&mut result, payload.len() as u32);
+    write_u32(&mut result, get_checksum(payload));
+    result.extend_from_slice(payload);
+    result
+}
+
+/// Writes a u16 at the end of a byte buffer
+pub fn write_u16(buffer: &mut Vec<u8>, value: u16) {
+    buffer.push(value as u8);
+    buffer.push((value >> 8) as u8);
+}
+
+/// Writes a u32 at the end of a byte buffer
+pub fn write_u32(buffer: &mut Vec<u8>, value: u32) {
+    buffer.push(value as u8);
+    buffer.push((value >> 8) as u8);
+    buffer.push((value >> 16) as u8);
+    buffer.push((value >> 24) as u8);
+}
+
+/// Checks the number of states of an automaton against its header, if any
+pub fn check_states_count(
+    header: Option<AutomatonHeader>,
+    states_count: usize
+) -> Result<(), AutomatonError> {
+    match header {
+        Some(header) if header.states_count as usize != states_count => {
+            Err(AutomatonError::StatesCountMismatch {
+                expected: header.states_count,
+                found: states_count as u32
+            })
+        }
+        _ => Ok(())
+    }
+}
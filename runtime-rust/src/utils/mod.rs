@@ -21,6 +21,11 @@ pub mod biglist;
 pub mod bin;
 pub mod iterable;
 
+/// Escapes a string for a label in the GraphViz DOT format
+pub fn escape_dot(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
 /// Represents a reference to a structure that can be either mutable or immutable
 pub enum EitherMut<'a, T: 'a> {
     /// The immutable reference
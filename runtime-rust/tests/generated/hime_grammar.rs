@@ -0,0 +1,579 @@
+//! Module for the lexer and parser for HimeGrammar
+//! WARNING: this file has been generated by
+//! Hime Parser Generator 3.4.1
+
+use std::io::Read;
+use std::mem;
+use std::sync::OnceLock;
+
+use hime_redist::ast::AstNode;
+use hime_redist::errors::ParseErrors;
+use hime_redist::lexers::automaton::Automaton;
+use hime_redist::lexers::contexts::LayeredLexer;
+use hime_redist::lexers::filters::FilteredLexer;
+use hime_redist::lexers::impls::ContextFreeLexer;
+use hime_redist::lexers::run_lexer;
+use hime_redist::lexers::LexerOptions;
+use hime_redist::lexers::LexerSetup;
+use hime_redist::parsers::Parser;
+use hime_redist::parsers::lrk::LRkAutomaton;
+use hime_redist::parsers::lrk::LRkParser;
+use hime_redist::parsers::lrk::LRkParserBuffers;
+use hime_redist::result::ParseResult;
+use hime_redist::result::TokenizeResult;
+use hime_redist::symbols::SemanticBody;
+use hime_redist::symbols::SemanticElementTrait;
+use hime_redist::symbols::Symbol;
+use hime_redist::text::Text;
+use hime_redist::tokens::TokenRepository;
+use hime_redist::utils::iterable::Iterable;
+
+/// Static resource for the serialized lexer automaton
+const LEXER_AUTOMATON: &[u8] = include_bytes!("../../../sdk-net/Sources/Input/HimeGrammarLexer.bin");
+
+/// The lexer automaton, deserialized on first use and shared by all lexers
+static LEXER_AUTOMATON_SHARED: OnceLock<Automaton> = OnceLock::new();
+
+/// The unique identifier for terminal SEPARATOR
+pub const ID_TERMINAL_SEPARATOR: u32 = 0x0007;
+/// The unique identifier for terminal NAME
+pub const ID_TERMINAL_NAME: u32 = 0x0009;
+/// The unique identifier for terminal INTEGER
+pub const ID_TERMINAL_INTEGER: u32 = 0x000A;
+/// The unique identifier for terminal LITERAL_STRING
+pub const ID_TERMINAL_LITERAL_STRING: u32 = 0x000C;
+/// The unique identifier for terminal LITERAL_ANY
+pub const ID_TERMINAL_LITERAL_ANY: u32 = 0x000D;
+/// The unique identifier for terminal LITERAL_TEXT
+pub const ID_TERMINAL_LITERAL_TEXT: u32 = 0x000E;
+/// The unique identifier for terminal LITERAL_CLASS
+pub const ID_TERMINAL_LITERAL_CLASS: u32 = 0x000F;
+/// The unique identifier for terminal UNICODE_BLOCK
+pub const ID_TERMINAL_UNICODE_BLOCK: u32 = 0x0010;
+/// The unique identifier for terminal UNICODE_CATEGORY
+pub const ID_TERMINAL_UNICODE_CATEGORY: u32 = 0x0011;
+/// The unique identifier for terminal UNICODE_CODEPOINT
+pub const ID_TERMINAL_UNICODE_CODEPOINT: u32 = 0x0012;
+/// The unique identifier for terminal UNICODE_SPAN_MARKER
+pub const ID_TERMINAL_UNICODE_SPAN_MARKER: u32 = 0x0013;
+/// The unique identifier for terminal OPERATOR_OPTIONAL
+pub const ID_TERMINAL_OPERATOR_OPTIONAL: u32 = 0x0014;
+/// The unique identifier for terminal OPERATOR_ZEROMORE
+pub const ID_TERMINAL_OPERATOR_ZEROMORE: u32 = 0x0015;
+/// The unique identifier for terminal OPERATOR_ONEMORE
+pub const ID_TERMINAL_OPERATOR_ONEMORE: u32 = 0x0016;
+/// The unique identifier for terminal OPERATOR_UNION
+pub const ID_TERMINAL_OPERATOR_UNION: u32 = 0x0017;
+/// The unique identifier for terminal OPERATOR_DIFFERENCE
+pub const ID_TERMINAL_OPERATOR_DIFFERENCE: u32 = 0x0018;
+/// The unique identifier for terminal TREE_ACTION_PROMOTE
+pub const ID_TERMINAL_TREE_ACTION_PROMOTE: u32 = 0x0019;
+/// The unique identifier for terminal TREE_ACTION_DROP
+pub const ID_TERMINAL_TREE_ACTION_DROP: u32 = 0x001A;
+/// The unique identifier for terminal BLOCK_OPTIONS
+pub const ID_TERMINAL_BLOCK_OPTIONS: u32 = 0x001B;
+/// The unique identifier for terminal BLOCK_TERMINALS
+pub const ID_TERMINAL_BLOCK_TERMINALS: u32 = 0x001C;
+/// The unique identifier for terminal BLOCK_RULES
+pub const ID_TERMINAL_BLOCK_RULES: u32 = 0x001D;
+/// The unique identifier for terminal BLOCK_CONTEXT
+pub const ID_TERMINAL_BLOCK_CONTEXT: u32 = 0x001E;
+
+/// The unique identifier for the default context
+pub const CONTEXT_DEFAULT: u16 = 0;
+
+/// The collection of terminals matched by this lexer
+/// The terminals are in an order consistent with the automaton,
+/// so that terminal indices in the automaton can be used to retrieve the terminals in this table
+const TERMINALS: &[Symbol] = &[
+    Symbol::new_static(0x0001, "ε"),
+    Symbol::new_static(0x0002, "$"),
+    Symbol::new_static(0x0007, "SEPARATOR"),
+    Symbol::new_static(0x0009, "NAME"),
+    Symbol::new_static(0x000A, "INTEGER"),
+    Symbol::new_static(0x000C, "LITERAL_STRING"),
+    Symbol::new_static(0x000D, "LITERAL_ANY"),
+    Symbol::new_static(0x000E, "LITERAL_TEXT"),
+    Symbol::new_static(0x000F, "LITERAL_CLASS"),
+    Symbol::new_static(0x0010, "UNICODE_BLOCK"),
+    Symbol::new_static(0x0011, "UNICODE_CATEGORY"),
+    Symbol::new_static(0x0012, "UNICODE_CODEPOINT"),
+    Symbol::new_static(0x0013, "UNICODE_SPAN_MARKER"),
+    Symbol::new_static(0x0014, "OPERATOR_OPTIONAL"),
+    Symbol::new_static(0x0015, "OPERATOR_ZEROMORE"),
+    Symbol::new_static(0x0016, "OPERATOR_ONEMORE"),
+    Symbol::new_static(0x0017, "OPERATOR_UNION"),
+    Symbol::new_static(0x0018, "OPERATOR_DIFFERENCE"),
+    Symbol::new_static(0x0019, "TREE_ACTION_PROMOTE"),
+    Symbol::new_static(0x001A, "TREE_ACTION_DROP"),
+    Symbol::new_static(0x001B, "BLOCK_OPTIONS"),
+    Symbol::new_static(0x001C, "BLOCK_TERMINALS"),
+    Symbol::new_static(0x001D, "BLOCK_RULES"),
+    Symbol::new_static(0x001E, "BLOCK_CONTEXT"),
+    Symbol::new_static(0x0043, "="),
+    Symbol::new_static(0x0044, ";"),
+    Symbol::new_static(0x0045, "("),
+    Symbol::new_static(0x0046, ")"),
+    Symbol::new_static(0x0048, "{"),
+    Symbol::new_static(0x0049, ","),
+    Symbol::new_static(0x004A, "}"),
+    Symbol::new_static(0x004F, "->"),
+    Symbol::new_static(0x0050, "fragment"),
+    Symbol::new_static(0x0052, "@"),
+    Symbol::new_static(0x0053, "<"),
+    Symbol::new_static(0x0055, ">"),
+    Symbol::new_static(0x0056, "#"),
+    Symbol::new_static(0x005E, ":"),
+    Symbol::new_static(0x0060, "grammar")];
+
+/// Creates a new lexer
+fn new_lexer<'a>(
+    repository: TokenRepository<'a>,
+    errors: &'a mut ParseErrors
+) -> ContextFreeLexer<'a> {
+    let automaton = LEXER_AUTOMATON_SHARED.get_or_init(|| Automaton::from_static(LEXER_AUTOMATON));
+    ContextFreeLexer::new(repository, errors, automaton, 0x0007)
+}
+
+/// Tokenizes the specified string with this lexer, without parsing
+pub fn tokenize_string(input: &str) -> TokenizeResult {
+    let text = Text::new(input);
+    tokenize_text(text)
+}
+
+/// Tokenizes the specified stream of UTF-16 with this lexer, without parsing
+pub fn tokenize_utf16(input: &mut dyn Read, big_endian: bool) -> TokenizeResult {
+    let text = Text::from_utf16_stream(input, big_endian);
+    tokenize_text(text)
+}
+
+/// Tokenizes the specified stream of UTF-8 with this lexer, without parsing
+pub fn tokenize_utf8(input: &mut dyn Read) -> TokenizeResult {
+    let text = Text::from_utf8_stream(input);
+    tokenize_text(text)
+}
+
+/// Tokenizes the specified string with this lexer, after setting up the lexer with the specified function
+/// The setup can for example retain the separators as trivia on the tokens.
+pub fn tokenize_string_with_setup(input: &str, setup: &mut LexerSetup) -> TokenizeResult {
+    let text = Text::new(input);
+    tokenize_text_with(text, Some(setup))
+}
+
+/// Tokenizes the specified text with this lexer, without parsing
+fn tokenize_text(text: Text) -> TokenizeResult {
+    tokenize_text_with(text, None)
+}
+
+/// Tokenizes the specified text with this lexer, after the optional setup of the lexer
+fn tokenize_text_with(text: Text, setup: Option<&mut LexerSetup>) -> TokenizeResult {
+    let mut result = TokenizeResult::new(TERMINALS, text);
+    {
+        let data = result.get_lexing_data();
+        let mut lexer = new_lexer(data.0, data.1);
+        if let Some(setup) = setup {
+            setup(&mut lexer);
+        }
+        run_lexer(&mut lexer);
+    }
+    result
+}
+
+/// Static resource for the serialized parser automaton
+const PARSER_AUTOMATON: &[u8] = include_bytes!("../../../sdk-net/Sources/Input/HimeGrammarParser.bin");
+
+/// The parser automaton, deserialized on first use and shared by all parsers
+static PARSER_AUTOMATON_SHARED: OnceLock<LRkAutomaton> = OnceLock::new();
+
+/// The unique identifier for variable option
+pub const ID_VARIABLE_OPTION: u32 = 0x001F;
+/// The unique identifier for variable terminal_def_atom
+pub const ID_VARIABLE_TERMINAL_DEF_ATOM: u32 = 0x0020;
+/// The unique identifier for variable terminal_def_element
+pub const ID_VARIABLE_TERMINAL_DEF_ELEMENT: u32 = 0x0021;
+/// The unique identifier for variable terminal_def_cardinalilty
+pub const ID_VARIABLE_TERMINAL_DEF_CARDINALILTY: u32 = 0x0022;
+/// The unique identifier for variable terminal_def_repetition
+pub const ID_VARIABLE_TERMINAL_DEF_REPETITION: u32 = 0x0023;
+/// The unique identifier for variable terminal_def_fragment
+pub const ID_VARIABLE_TERMINAL_DEF_FRAGMENT: u32 = 0x0024;
+/// The unique identifier for variable terminal_def_restrict
+pub const ID_VARIABLE_TERMINAL_DEF_RESTRICT: u32 = 0x0025;
+/// The unique identifier for variable terminal_definition
+pub const ID_VARIABLE_TERMINAL_DEFINITION: u32 = 0x0026;
+/// The unique identifier for variable terminal_rule
+pub const ID_VARIABLE_TERMINAL_RULE: u32 = 0x0027;
+/// The unique identifier for variable terminal_fragment
+pub const ID_VARIABLE_TERMINAL_FRAGMENT: u32 = 0x0028;
+/// The unique identifier for variable terminal_context
+pub const ID_VARIABLE_TERMINAL_CONTEXT: u32 = 0x0029;
+/// The unique identifier for variable terminal_item
+pub const ID_VARIABLE_TERMINAL_ITEM: u32 = 0x002A;
+/// The unique identifier for variable rule_sym_action
+pub const ID_VARIABLE_RULE_SYM_ACTION: u32 = 0x002B;
+/// The unique identifier for variable rule_sym_virtual
+pub const ID_VARIABLE_RULE_SYM_VIRTUAL: u32 = 0x002C;
+/// The unique identifier for variable rule_sym_ref_params
+pub const ID_VARIABLE_RULE_SYM_REF_PARAMS: u32 = 0x002D;
+/// The unique identifier for variable rule_sym_ref_template
+pub const ID_VARIABLE_RULE_SYM_REF_TEMPLATE: u32 = 0x002E;
+/// The unique identifier for variable rule_sym_ref_simple
+pub const ID_VARIABLE_RULE_SYM_REF_SIMPLE: u32 = 0x002F;
+/// The unique identifier for variable rule_def_atom
+pub const ID_VARIABLE_RULE_DEF_ATOM: u32 = 0x0030;
+/// The unique identifier for variable rule_def_context
+pub const ID_VARIABLE_RULE_DEF_CONTEXT: u32 = 0x0031;
+/// The unique identifier for variable rule_def_sub
+pub const ID_VARIABLE_RULE_DEF_SUB: u32 = 0x0032;
+/// The unique identifier for variable rule_def_element
+pub const ID_VARIABLE_RULE_DEF_ELEMENT: u32 = 0x0033;
+/// The unique identifier for variable rule_def_tree_action
+pub const ID_VARIABLE_RULE_DEF_TREE_ACTION: u32 = 0x0034;
+/// The unique identifier for variable rule_def_repetition
+pub const ID_VARIABLE_RULE_DEF_REPETITION: u32 = 0x0035;
+/// The unique identifier for variable rule_def_fragment
+pub const ID_VARIABLE_RULE_DEF_FRAGMENT: u32 = 0x0036;
+/// The unique identifier for variable rule_def_choice
+pub const ID_VARIABLE_RULE_DEF_CHOICE: u32 = 0x0037;
+/// The unique identifier for variable rule_definition
+pub const ID_VARIABLE_RULE_DEFINITION: u32 = 0x0038;
+/// The unique identifier for variable rule_template_params
+pub const ID_VARIABLE_RULE_TEMPLATE_PARAMS: u32 = 0x0039;
+/// The unique identifier for variable cf_rule_template
+pub const ID_VARIABLE_CF_RULE_TEMPLATE: u32 = 0x003A;
+/// The unique identifier for variable cf_rule_simple
+pub const ID_VARIABLE_CF_RULE_SIMPLE: u32 = 0x003B;
+/// The unique identifier for variable cf_rule
+pub const ID_VARIABLE_CF_RULE: u32 = 0x003C;
+/// The unique identifier for variable grammar_options
+pub const ID_VARIABLE_GRAMMAR_OPTIONS: u32 = 0x003D;
+/// The unique identifier for variable grammar_terminals
+pub const ID_VARIABLE_GRAMMAR_TERMINALS: u32 = 0x003E;
+/// The unique identifier for variable grammar_cf_rules
+pub const ID_VARIABLE_GRAMMAR_CF_RULES: u32 = 0x003F;
+/// The unique identifier for variable grammar_parency
+pub const ID_VARIABLE_GRAMMAR_PARENCY: u32 = 0x0040;
+/// The unique identifier for variable cf_grammar
+pub const ID_VARIABLE_CF_GRAMMAR: u32 = 0x0041;
+/// The unique identifier for variable file
+pub const ID_VARIABLE_FILE: u32 = 0x0042;
+
+/// The unique identifier for virtual range
+pub const ID_VIRTUAL_RANGE: u32 = 0x0047;
+/// The unique identifier for virtual concat
+pub const ID_VIRTUAL_CONCAT: u32 = 0x004B;
+/// The unique identifier for virtual emptypart
+pub const ID_VIRTUAL_EMPTYPART: u32 = 0x0058;
+
+/// The collection of variables matched by this parser
+/// The variables are in an order consistent with the automaton,
+/// so that variable indices in the automaton can be used to retrieve the variables in this table
+const VARIABLES: &[Symbol] = &[
+    Symbol::new_static(0x001F, "option"),
+    Symbol::new_static(0x0020, "terminal_def_atom"),
+    Symbol::new_static(0x0021, "terminal_def_element"),
+    Symbol::new_static(0x0022, "terminal_def_cardinalilty"),
+    Symbol::new_static(0x0023, "terminal_def_repetition"),
+    Symbol::new_static(0x0024, "terminal_def_fragment"),
+    Symbol::new_static(0x0025, "terminal_def_restrict"),
+    Symbol::new_static(0x0026, "terminal_definition"),
+    Symbol::new_static(0x0027, "terminal_rule"),
+    Symbol::new_static(0x0028, "terminal_fragment"),
+    Symbol::new_static(0x0029, "terminal_context"),
+    Symbol::new_static(0x002A, "terminal_item"),
+    Symbol::new_static(0x002B, "rule_sym_action"),
+    Symbol::new_static(0x002C, "rule_sym_virtual"),
+    Symbol::new_static(0x002D, "rule_sym_ref_params"),
+    Symbol::new_static(0x002E, "rule_sym_ref_template"),
+    Symbol::new_static(0x002F, "rule_sym_ref_simple"),
+    Symbol::new_static(0x0030, "rule_def_atom"),
+    Symbol::new_static(0x0031, "rule_def_context"),
+    Symbol::new_static(0x0032, "rule_def_sub"),
+    Symbol::new_static(0x0033, "rule_def_element"),
+    Symbol::new_static(0x0034, "rule_def_tree_action"),
+    Symbol::new_static(0x0035, "rule_def_repetition"),
+    Symbol::new_static(0x0036, "rule_def_fragment"),
+    Symbol::new_static(0x0037, "rule_def_choice"),
+    Symbol::new_static(0x0038, "rule_definition"),
+    Symbol::new_static(0x0039, "rule_template_params"),
+    Symbol::new_static(0x003A, "cf_rule_template"),
+    Symbol::new_static(0x003B, "cf_rule_simple"),
+    Symbol::new_static(0x003C, "cf_rule"),
+    Symbol::new_static(0x003D, "grammar_options"),
+    Symbol::new_static(0x003E, "grammar_terminals"),
+    Symbol::new_static(0x003F, "grammar_cf_rules"),
+    Symbol::new_static(0x0040, "grammar_parency"),
+    Symbol::new_static(0x0041, "cf_grammar"),
+    Symbol::new_static(0x0042, "file"),
+    Symbol::new_static(0x004C, "__V76"),
+    Symbol::new_static(0x004D, "__V77"),
+    Symbol::new_static(0x004E, "__V78"),
+    Symbol::new_static(0x0051, "__V81"),
+    Symbol::new_static(0x0054, "__V84"),
+    Symbol::new_static(0x0057, "__V87"),
+    Symbol::new_static(0x0059, "__V89"),
+    Symbol::new_static(0x005A, "__V90"),
+    Symbol::new_static(0x005B, "__V91"),
+    Symbol::new_static(0x005C, "__V92"),
+    Symbol::new_static(0x005D, "__V93"),
+    Symbol::new_static(0x005F, "__V95"),
+    Symbol::new_static(0x0061, "__V97"),
+    Symbol::new_static(0x0062, "__VAxiom")];
+
+/// The collection of virtuals matched by this parser
+/// The virtuals are in an order consistent with the automaton,
+/// so that virtual indices in the automaton can be used to retrieve the virtuals in this table
+const VIRTUALS: &[Symbol] = &[
+    Symbol::new_static(0x0047, "range"),
+    Symbol::new_static(0x004B, "concat"),
+    Symbol::new_static(0x0058, "emptypart")];
+
+/// Parses the specified string with this parser
+pub fn parse_string(input: &str) -> ParseResult {
+    let text = Text::new(input);
+    parse_text(text, LexerOptions::default())
+}
+
+/// Parses the specified stream of UTF-16 with this parser
+pub fn parse_utf16(input: &mut dyn Read, big_endian: bool) -> ParseResult {
+    let text = Text::from_utf16_stream(input, big_endian);
+    parse_text(text, LexerOptions::default())
+}
+
+/// Parses the specified stream of UTF-16 with this parser
+pub fn parse_utf8(input: &mut dyn Read) -> ParseResult {
+    let text = Text::from_utf8_stream(input);
+    parse_text(text, LexerOptions::default())
+}
+
+/// Parses the specified string with this parser and the specified options for the lexer
+/// The options can rewrite the stream of tokens with a filter, set up the lexer or resolve the lexical contexts through layers.
+pub fn parse_string_with_options(input: &str, options: LexerOptions) -> ParseResult {
+    let text = Text::new(input);
+    parse_text(text, options)
+}
+
+/// A reusable context for parsing many inputs with this parser
+/// The context keeps the allocations of its parse result and of the parser between parses.
+pub struct ParseContext {
+    /// The result of the last parse
+    result: ParseResult,
+    /// The buffers of the parser
+    buffers: LRkParserBuffers
+}
+
+impl ParseContext {
+    /// Gets the result of the last parse
+    pub fn get_result(&self) -> &ParseResult {
+        &self.result
+    }
+
+    /// Gets the mutable result of the last parse
+    pub fn get_result_mut(&mut self) -> &mut ParseResult {
+        &mut self.result
+    }
+}
+
+/// Creates a new context for parsing many inputs with this parser while reusing allocations
+pub fn new_context() -> ParseContext {
+    ParseContext {
+        result: ParseResult::new(TERMINALS, VARIABLES, VIRTUALS, Text::new("")),
+        buffers: LRkParserBuffers::default()
+    }
+}
+
+/// Parses the specified string with this parser in a reusable context
+/// The previous result in the context is reset and replaced by the new one.
+pub fn parse_string_in<'c>(context: &'c mut ParseContext, input: &str) -> &'c ParseResult {
+    parse_string_in_with_options(context, input, LexerOptions::default())
+}
+
+/// Parses the specified string with this parser in a reusable context and the specified options for the lexer
+/// The previous result in the context is reset and replaced by the new one.
+pub fn parse_string_in_with_options<'c>(context: &'c mut ParseContext, input: &str, options: LexerOptions) -> &'c ParseResult {
+    context.result.reset(input);
+    parse_into(&mut context.result, &mut context.buffers, options);
+    &context.result
+}
+
+/// Parses the specified text with this parser
+fn parse_text(text: Text, options: LexerOptions) -> ParseResult {
+    let mut result = ParseResult::new(TERMINALS, VARIABLES, VIRTUALS, text);
+    let mut buffers = LRkParserBuffers::default();
+    parse_into(&mut result, &mut buffers, options);
+    result
+}
+
+/// Parses the text of the specified result with this parser
+fn parse_into(result: &mut ParseResult, buffers: &mut LRkParserBuffers, options: LexerOptions) {
+    let mut my_actions = |_index: usize, _head: Symbol, _body: &dyn SemanticBody| ();
+    let data = result.get_parsing_data();
+    let automaton = PARSER_AUTOMATON_SHARED.get_or_init(|| LRkAutomaton::from_static(PARSER_AUTOMATON));
+    let mut lexer = LayeredLexer::new(new_lexer(data.0, data.1));
+    for layer in options.layers {
+        lexer.add_layer(*layer);
+    }
+    match options.filter {
+        None => {
+            if let Some(setup) = options.setup {
+                setup(&mut lexer);
+            }
+            let mut parser = LRkParser::new_with_buffers(&mut lexer, automaton, data.2, &mut my_actions, mem::take(buffers));
+            parser.parse();
+            *buffers = parser.into_buffers();
+        }
+        Some(filter) => {
+            let mut lexer = FilteredLexer::new(lexer, filter);
+            if let Some(setup) = options.setup {
+                setup(&mut lexer);
+            }
+            let mut parser = LRkParser::new_with_buffers(&mut lexer, automaton, data.2, &mut my_actions, mem::take(buffers));
+            parser.parse();
+            *buffers = parser.into_buffers();
+        }
+    }
+}
+
+/// Visitor interface
+pub trait Visitor {
+    fn on_terminal_separator(&self, node: &AstNode);
+    fn on_terminal_name(&self, node: &AstNode);
+    fn on_terminal_integer(&self, node: &AstNode);
+    fn on_terminal_literal_string(&self, node: &AstNode);
+    fn on_terminal_literal_any(&self, node: &AstNode);
+    fn on_terminal_literal_text(&self, node: &AstNode);
+    fn on_terminal_literal_class(&self, node: &AstNode);
+    fn on_terminal_unicode_block(&self, node: &AstNode);
+    fn on_terminal_unicode_category(&self, node: &AstNode);
+    fn on_terminal_unicode_codepoint(&self, node: &AstNode);
+    fn on_terminal_unicode_span_marker(&self, node: &AstNode);
+    fn on_terminal_operator_optional(&self, node: &AstNode);
+    fn on_terminal_operator_zeromore(&self, node: &AstNode);
+    fn on_terminal_operator_onemore(&self, node: &AstNode);
+    fn on_terminal_operator_union(&self, node: &AstNode);
+    fn on_terminal_operator_difference(&self, node: &AstNode);
+    fn on_terminal_tree_action_promote(&self, node: &AstNode);
+    fn on_terminal_tree_action_drop(&self, node: &AstNode);
+    fn on_terminal_block_options(&self, node: &AstNode);
+    fn on_terminal_block_terminals(&self, node: &AstNode);
+    fn on_terminal_block_rules(&self, node: &AstNode);
+    fn on_terminal_block_context(&self, node: &AstNode);
+    fn on_variable_option(&self, node: &AstNode);
+    fn on_variable_terminal_def_atom(&self, node: &AstNode);
+    fn on_variable_terminal_def_element(&self, node: &AstNode);
+    fn on_variable_terminal_def_cardinalilty(&self, node: &AstNode);
+    fn on_variable_terminal_def_repetition(&self, node: &AstNode);
+    fn on_variable_terminal_def_fragment(&self, node: &AstNode);
+    fn on_variable_terminal_def_restrict(&self, node: &AstNode);
+    fn on_variable_terminal_definition(&self, node: &AstNode);
+    fn on_variable_terminal_rule(&self, node: &AstNode);
+    fn on_variable_terminal_fragment(&self, node: &AstNode);
+    fn on_variable_terminal_context(&self, node: &AstNode);
+    fn on_variable_terminal_item(&self, node: &AstNode);
+    fn on_variable_rule_sym_action(&self, node: &AstNode);
+    fn on_variable_rule_sym_virtual(&self, node: &AstNode);
+    fn on_variable_rule_sym_ref_params(&self, node: &AstNode);
+    fn on_variable_rule_sym_ref_template(&self, node: &AstNode);
+    fn on_variable_rule_sym_ref_simple(&self, node: &AstNode);
+    fn on_variable_rule_def_atom(&self, node: &AstNode);
+    fn on_variable_rule_def_context(&self, node: &AstNode);
+    fn on_variable_rule_def_sub(&self, node: &AstNode);
+    fn on_variable_rule_def_element(&self, node: &AstNode);
+    fn on_variable_rule_def_tree_action(&self, node: &AstNode);
+    fn on_variable_rule_def_repetition(&self, node: &AstNode);
+    fn on_variable_rule_def_fragment(&self, node: &AstNode);
+    fn on_variable_rule_def_choice(&self, node: &AstNode);
+    fn on_variable_rule_definition(&self, node: &AstNode);
+    fn on_variable_rule_template_params(&self, node: &AstNode);
+    fn on_variable_cf_rule_template(&self, node: &AstNode);
+    fn on_variable_cf_rule_simple(&self, node: &AstNode);
+    fn on_variable_cf_rule(&self, node: &AstNode);
+    fn on_variable_grammar_options(&self, node: &AstNode);
+    fn on_variable_grammar_terminals(&self, node: &AstNode);
+    fn on_variable_grammar_cf_rules(&self, node: &AstNode);
+    fn on_variable_grammar_parency(&self, node: &AstNode);
+    fn on_variable_cf_grammar(&self, node: &AstNode);
+    fn on_variable_file(&self, node: &AstNode);
+    fn on_virtual_range(&self, node: &AstNode);
+    fn on_virtual_concat(&self, node: &AstNode);
+    fn on_virtual_emptypart(&self, node: &AstNode);
+}
+
+/// Walk the AST of a result using a visitor
+pub fn visit(result: &ParseResult, visitor: &dyn Visitor) {
+    let ast = result.get_ast();
+    let root = ast.get_root();
+    visit_ast_node(root, visitor);
+}
+
+/// Walk the sub-AST from the specified node using a visitor
+pub fn visit_ast_node<'a>(node: AstNode<'a>, visitor: &dyn Visitor) {
+    let children = node.children();
+    for child in children.iter() {
+        visit_ast_node(child, visitor);
+    }
+    match node.get_symbol().id {
+        0x0007 => visitor.on_terminal_separator(&node),
+        0x0009 => visitor.on_terminal_name(&node),
+        0x000A => visitor.on_terminal_integer(&node),
+        0x000C => visitor.on_terminal_literal_string(&node),
+        0x000D => visitor.on_terminal_literal_any(&node),
+        0x000E => visitor.on_terminal_literal_text(&node),
+        0x000F => visitor.on_terminal_literal_class(&node),
+        0x0010 => visitor.on_terminal_unicode_block(&node),
+        0x0011 => visitor.on_terminal_unicode_category(&node),
+        0x0012 => visitor.on_terminal_unicode_codepoint(&node),
+        0x0013 => visitor.on_terminal_unicode_span_marker(&node),
+        0x0014 => visitor.on_terminal_operator_optional(&node),
+        0x0015 => visitor.on_terminal_operator_zeromore(&node),
+        0x0016 => visitor.on_terminal_operator_onemore(&node),
+        0x0017 => visitor.on_terminal_operator_union(&node),
+        0x0018 => visitor.on_terminal_operator_difference(&node),
+        0x0019 => visitor.on_terminal_tree_action_promote(&node),
+        0x001A => visitor.on_terminal_tree_action_drop(&node),
+        0x001B => visitor.on_terminal_block_options(&node),
+        0x001C => visitor.on_terminal_block_terminals(&node),
+        0x001D => visitor.on_terminal_block_rules(&node),
+        0x001E => visitor.on_terminal_block_context(&node),
+        0x001F => visitor.on_variable_option(&node),
+        0x0020 => visitor.on_variable_terminal_def_atom(&node),
+        0x0021 => visitor.on_variable_terminal_def_element(&node),
+        0x0022 => visitor.on_variable_terminal_def_cardinalilty(&node),
+        0x0023 => visitor.on_variable_terminal_def_repetition(&node),
+        0x0024 => visitor.on_variable_terminal_def_fragment(&node),
+        0x0025 => visitor.on_variable_terminal_def_restrict(&node),
+        0x0026 => visitor.on_variable_terminal_definition(&node),
+        0x0027 => visitor.on_variable_terminal_rule(&node),
+        0x0028 => visitor.on_variable_terminal_fragment(&node),
+        0x0029 => visitor.on_variable_terminal_context(&node),
+        0x002A => visitor.on_variable_terminal_item(&node),
+        0x002B => visitor.on_variable_rule_sym_action(&node),
+        0x002C => visitor.on_variable_rule_sym_virtual(&node),
+        0x002D => visitor.on_variable_rule_sym_ref_params(&node),
+        0x002E => visitor.on_variable_rule_sym_ref_template(&node),
+        0x002F => visitor.on_variable_rule_sym_ref_simple(&node),
+        0x0030 => visitor.on_variable_rule_def_atom(&node),
+        0x0031 => visitor.on_variable_rule_def_context(&node),
+        0x0032 => visitor.on_variable_rule_def_sub(&node),
+        0x0033 => visitor.on_variable_rule_def_element(&node),
+        0x0034 => visitor.on_variable_rule_def_tree_action(&node),
+        0x0035 => visitor.on_variable_rule_def_repetition(&node),
+        0x0036 => visitor.on_variable_rule_def_fragment(&node),
+        0x0037 => visitor.on_variable_rule_def_choice(&node),
+        0x0038 => visitor.on_variable_rule_definition(&node),
+        0x0039 => visitor.on_variable_rule_template_params(&node),
+        0x003A => visitor.on_variable_cf_rule_template(&node),
+        0x003B => visitor.on_variable_cf_rule_simple(&node),
+        0x003C => visitor.on_variable_cf_rule(&node),
+        0x003D => visitor.on_variable_grammar_options(&node),
+        0x003E => visitor.on_variable_grammar_terminals(&node),
+        0x003F => visitor.on_variable_grammar_cf_rules(&node),
+        0x0040 => visitor.on_variable_grammar_parency(&node),
+        0x0041 => visitor.on_variable_cf_grammar(&node),
+        0x0042 => visitor.on_variable_file(&node),
+        0x0047 => visitor.on_virtual_range(&node),
+        0x004B => visitor.on_virtual_concat(&node),
+        0x0058 => visitor.on_virtual_emptypart(&node),
+        _ => ()
+    };
+}
@@ -0,0 +1,279 @@
+/*******************************************************************************
+ * Copyright (c) 2017 Association Cénotélie (cenotelie.fr)
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Lesser General Public License as
+ * published by the Free Software Foundation, either version 3
+ * of the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General
+ * Public License along with this program.
+ * If not, see <http://www.gnu.org/licenses/>.
+ ******************************************************************************/
+
+//! Tests of the code generated by himecc for the grammar of Hime grammars
+//! The hime_grammar module is the output of himecc for HimeGrammar.gram, targeting Rust.
+
+extern crate hime_redist;
+
+#[allow(dead_code)]
+mod hime_grammar;
+
+use hime_redist::ast::AstNode;
+use hime_redist::lexers::contexts::ContextLayer;
+use hime_redist::lexers::filters::TokenFilter;
+use hime_redist::lexers::filters::TokenFilterOutput;
+use hime_redist::lexers::ContextProvider;
+use hime_redist::lexers::Lexer;
+use hime_redist::lexers::LexerOptions;
+use hime_redist::lexers::TokenKernel;
+use hime_redist::result::ParseResult;
+use hime_redist::symbols::SemanticElementTrait;
+use hime_redist::text::TextSpan;
+use hime_redist::utils::iterable::Iterable;
+
+/// A layer of contexts that denies all the contexts
+struct DenyAll {}
+
+impl ContextLayer for DenyAll {
+    fn get_context_priority(
+        &self,
+        _below: &dyn ContextProvider,
+        _token_count: usize,
+        _context: u16,
+        _terminal_id: u32
+    ) -> Option<usize> {
+        None
+    }
+}
+
+/// A filter that drops the tokens with the specified value
+struct DropValue {
+    /// The value of the dropped tokens
+    value: &'static str,
+    /// The number of dropped tokens
+    dropped: usize
+}
+
+impl TokenFilter for DropValue {
+    fn filter(&mut self, token: TokenKernel, output: &mut TokenFilterOutput) {
+        let value = output
+            .get_repository()
+            .get_token(token.index as usize)
+            .get_value();
+        if value.as_ref().map(String::as_str) == Some(self.value) {
+            self.dropped += 1;
+        } else {
+            output.push(token);
+        }
+    }
+}
+
+/// Prints the specified node and its descendants
+fn print_tree(node: AstNode, output: &mut String) {
+    output.push_str(&format!("{}(", node));
+    let children = node.children();
+    for child in children.iter() {
+        print_tree(child, output);
+    }
+    output.push(')');
+}
+
+/// Summarizes the result as its errors or its AST
+fn summarize(result: &ParseResult) -> String {
+    if !result.is_success() {
+        return format!("{} errors", result.get_errors().get_count());
+    }
+    let mut output = String::new();
+    print_tree(result.get_ast().get_root(), &mut output);
+    output
+}
+
+#[test]
+fn test_tokenize_string() {
+    let result = hime_grammar::tokenize_string("grammar Test { }");
+    assert!(result.is_success());
+    let tokens = result.get_tokens();
+    let ids: Vec<u32> = tokens.iter().map(|token| token.get_symbol().id).collect();
+    assert_eq!(ids, vec![0x0060, 0x0009, 0x0048, 0x004A, 0x0002]);
+    let name = tokens.get_token(1);
+    assert_eq!(name.get_symbol().id, hime_grammar::ID_TERMINAL_NAME);
+    assert_eq!(name.get_value(), Some(String::from("Test")));
+}
+
+#[test]
+fn test_tokenize_string_error() {
+    let result = hime_grammar::tokenize_string("grammar Test { } §");
+    assert!(!result.is_success());
+    assert_eq!(result.get_errors().get_count(), 1);
+}
+
+#[test]
+fn test_tokenize_string_with_setup() {
+    let result = hime_grammar::tokenize_string_with_setup("grammar Test { }", &mut |lexer| {
+        lexer.set_trivia_retained(true)
+    });
+    assert!(result.is_success());
+    let tokens = result.get_tokens();
+    let trivia = tokens.get_token(0).get_trailing_trivia();
+    assert_eq!(trivia.len(), 1);
+    assert_eq!(trivia[0].get_value(), Some(String::from(" ")));
+}
+
+#[test]
+fn test_parse_string() {
+    let input = "grammar Test { options { } rules { a -> 'x'; } }";
+    let result = hime_grammar::parse_string(input);
+    assert!(result.is_success());
+    let ast = result.get_ast();
+    let root = ast.get_root();
+    assert_eq!(root.get_symbol().id, hime_grammar::ID_VARIABLE_FILE);
+    // the tokens of the standalone lexer are those of the parser
+    let expected = hime_grammar::tokenize_string(input);
+    let values: Vec<Option<String>> = result
+        .get_tokens()
+        .iter()
+        .map(|token| token.get_value())
+        .collect();
+    let expected: Vec<Option<String>> = expected
+        .get_tokens()
+        .iter()
+        .map(|token| token.get_value())
+        .collect();
+    assert_eq!(values, expected);
+}
+
+#[test]
+fn test_parse_string_with_contexts() {
+    let input = "grammar Test { options { } rules { a -> 'x'; } }";
+    // the lexer for Hime grammars is context-free and is not restricted by the layers
+    let options = LexerOptions {
+        layers: &[&DenyAll {}],
+        ..LexerOptions::default()
+    };
+    let result = hime_grammar::parse_string_with_options(input, options);
+    assert!(result.is_success());
+    let ast = result.get_ast();
+    let root = ast.get_root();
+    assert_eq!(root.get_symbol().id, hime_grammar::ID_VARIABLE_FILE);
+    let expected = hime_grammar::parse_string(input);
+    assert_eq!(
+        result.get_tokens().iter().count(),
+        expected.get_tokens().iter().count()
+    );
+}
+
+#[test]
+fn test_parse_string_with_filter() {
+    let input = "grammar Test { options { } rules { a -> 'x'; } }";
+    let mut filter = DropValue {
+        value: ";",
+        dropped: 0
+    };
+    {
+        let options = LexerOptions {
+            filter: Some(&mut filter),
+            ..LexerOptions::default()
+        };
+        // the rule is no longer terminated
+        let result = hime_grammar::parse_string_with_options(input, options);
+        assert!(!result.is_success());
+    }
+    assert_eq!(filter.dropped, 1);
+}
+
+#[test]
+fn test_parse_string_with_setup() {
+    let input = "grammar Test { options { } rules { a -> 'x'; } }";
+    let mut setup = |lexer: &mut dyn Lexer| lexer.set_trivia_retained(true);
+    let mut context = hime_grammar::new_context();
+    for _ in 0..2 {
+        let options = LexerOptions {
+            setup: Some(&mut setup),
+            ..LexerOptions::default()
+        };
+        let result = hime_grammar::parse_string_in_with_options(&mut context, input, options);
+        assert!(result.is_success());
+        let tokens = result.get_tokens();
+        let first = tokens.iter().next().unwrap();
+        assert_eq!(first.get_trailing_trivia().len(), 1);
+    }
+    // without the setup, the trivia are not retained
+    let result = hime_grammar::parse_string_in(&mut context, input);
+    let tokens = result.get_tokens();
+    let first = tokens.iter().next().unwrap();
+    assert!(first.get_trailing_trivia().is_empty());
+}
+
+#[test]
+fn test_parse_string_in_context() {
+    let inputs = [
+        "grammar Test { options { } rules { a -> 'x'; } }",
+        "grammar Other { options { Axiom = \"e\"; } terminals { X -> 'x'+; } rules { e -> X e | X; } }",
+        "grammar Broken { rules { a -> ; ",
+        "grammar Test { options { } rules { a -> 'x' | 'y'; b -> a*; } }",
+        "grammar Test { options { } rules { a -> 'x'; } }"
+    ];
+    let mut context = hime_grammar::new_context();
+    for input in inputs.iter() {
+        let reused = summarize(hime_grammar::parse_string_in(&mut context, input));
+        let fresh = summarize(&hime_grammar::parse_string(input));
+        assert_eq!(reused, fresh, "input {}", input);
+        assert_eq!(context.get_result().is_success(), !input.contains("Broken"));
+    }
+}
+
+#[test]
+fn test_node_utf8_range() {
+    let input = "grammar Test { options { } rules { a -> 'é' b; } }";
+    let result = hime_grammar::parse_string(input);
+    assert!(result.is_success());
+    let ast = result.get_ast();
+    let root = ast.get_root();
+    // the keyword and the braces are dropped from the tree and the promoted BLOCK_RULES is before b
+    let range = root.get_utf8_range().unwrap();
+    assert_eq!(&input[range], "Test { options { } rules { a -> 'é' b");
+    let grammars = root.children();
+    let grammar = grammars.at(0);
+    let parts = grammar.children();
+    // the parency of the grammar is empty and covers no token
+    let parency = parts.at(1);
+    assert_eq!(
+        parency.get_symbol().id,
+        hime_grammar::ID_VARIABLE_GRAMMAR_PARENCY
+    );
+    assert_eq!(parency.get_utf8_range(), None);
+    let block = parts.at(3);
+    let rules = block.children();
+    let rule = rules.at(0);
+    let elements = rule.children();
+    let definition = elements.at(1);
+    assert_eq!(definition.get_symbol().id, hime_grammar::ID_VIRTUAL_CONCAT);
+    let range = definition.get_utf8_range().unwrap();
+    assert_eq!(&input[range], "'é' b");
+}
+
+#[test]
+fn test_parse_result_stale() {
+    let mut result = hime_grammar::parse_string("grammar Test { options { } rules { a -> 'x'; } }");
+    assert!(!result.get_tokens().is_stale());
+    assert!(!result.get_ast().get_root().is_stale());
+    result.get_input_mut().apply_edit(
+        TextSpan {
+            index: 8,
+            length: 4
+        },
+        "Other"
+    );
+    assert!(result.get_tokens().is_stale());
+    // getting the data for a parser does not refresh the tokens, only lexing again does
+    {
+        let _data = result.get_parsing_data();
+    }
+    assert!(result.get_tokens().is_stale());
+    assert!(result.get_ast().get_root().is_stale());
+}
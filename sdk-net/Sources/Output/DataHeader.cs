@@ -0,0 +1,98 @@
+/*******************************************************************************
+ * Copyright (c) 2017 Association Cénotélie (cenotelie.fr)
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Lesser General Public License as
+ * published by the Free Software Foundation, either version 3
+ * of the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General
+ * Public License along with this program.
+ * If not, see <http://www.gnu.org/licenses/>.
+ ******************************************************************************/
+
+using System.IO;
+
+namespace Hime.SDK.Output
+{
+	/// <summary>
+	/// The header of the binary data of an automaton, for the runtimes that verify it
+	/// </summary>
+	/// <remarks>
+	/// Binary data structure of the header:
+	/// u8[4]: the magic number
+	/// u16: the version of the binary format
+	/// u16: the kind of automaton
+	/// u32: the number of states in the automaton
+	/// u32: the length in bytes of the automaton's data after the header
+	/// u32: the Adler-32 checksum of the automaton's data after the header
+	/// </remarks>
+	public static class DataHeader
+	{
+		/// <summary>
+		/// The magic number at the start of the header
+		/// </summary>
+		private static readonly byte[] MAGIC = { (byte)'H', (byte)'I', (byte)'M', (byte)'E' };
+		/// <summary>
+		/// The current version of the binary format of automata
+		/// </summary>
+		public const ushort VERSION = 1;
+		/// <summary>
+		/// The kind of automaton for the DFA of a lexer
+		/// </summary>
+		public const ushort KIND_LEXER = 1;
+		/// <summary>
+		/// The kind of automaton for a LR(k) parser
+		/// </summary>
+		public const ushort KIND_LRK = 2;
+		/// <summary>
+		/// The kind of automaton for a RNGLR parser
+		/// </summary>
+		public const ushort KIND_RNGLR = 3;
+
+		/// <summary>
+		/// Exports the binary data of an automaton to the specified file
+		/// </summary>
+		/// <param name="file">The file to output to</param>
+		/// <param name="data">The binary data of the automaton</param>
+		/// <param name="withHeader">Whether to write the header before the data</param>
+		/// <param name="kind">The kind of automaton</param>
+		/// <param name="statesCount">The number of states in the automaton</param>
+		public static void Export(string file, byte[] data, bool withHeader, ushort kind, int statesCount)
+		{
+			BinaryWriter writer = new BinaryWriter(new FileStream(file, FileMode.Create));
+			if (withHeader)
+			{
+				writer.Write(MAGIC);
+				writer.Write(VERSION);
+				writer.Write(kind);
+				writer.Write((uint)statesCount);
+				writer.Write((uint)data.Length);
+				writer.Write(GetChecksum(data));
+			}
+			writer.Write(data);
+			writer.Close();
+		}
+
+		/// <summary>
+		/// Computes the Adler-32 checksum of the specified data
+		/// </summary>
+		/// <param name="data">The data</param>
+		/// <returns>The checksum</returns>
+		public static uint GetChecksum(byte[] data)
+		{
+			uint a = 1;
+			uint b = 0;
+			for (int i = 0; i != data.Length; i++)
+			{
+				a = (a + data[i]) % 65521;
+				b = (b + a) % 65521;
+			}
+			return (b << 16) | a;
+		}
+	}
+}
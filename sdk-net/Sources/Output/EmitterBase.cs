@@ -36,6 +36,10 @@ namespace Hime.SDK.Output
 		/// </summary>
 		public const string SUFFIX_PARSER_DATA = "Parser.bin";
 		/// <summary>
+		/// The suffix for the emitted symbol files
+		/// </summary>
+		public const string SUFFIX_SYMBOLS = "Symbols.txt";
+		/// <summary>
 		/// The suffix for the emitted debug grammar data
 		/// </summary>
 		public const string SUFFIX_DEBUG_GRAMMAR = "Grammar.txt";
@@ -185,6 +189,16 @@ namespace Hime.SDK.Output
 			return unit.OutputPath + unit.Name + SUFFIX_PARSER_DATA;
 		}
 
+		/// <summary>
+		/// Gets the full path and name for the symbol file artifact
+		/// </summary>
+		/// <param name="unit">The unit to emit data for</param>
+		/// <returns>The full path and name for the symbol file artifact</returns>
+		public string GetArtifactSymbols(Unit unit)
+		{
+			return unit.OutputPath + unit.Name + SUFFIX_SYMBOLS;
+		}
+
 		/// <summary>
 		/// Gets the full path and name for the assembly artifact
 		/// </summary>
@@ -271,6 +285,7 @@ namespace Hime.SDK.Output
 						File.Delete(GetArtifactLexerData(unit));
 						File.Delete(GetArtifactParserCode(unit));
 						File.Delete(GetArtifactParserData(unit));
+						File.Delete(GetArtifactSymbols(unit));
 					}
 				}
 			}
@@ -333,7 +348,7 @@ namespace Hime.SDK.Output
 		{
 			// generate the lexer's data
 			reporter.Info("Exporting lexer data at " + GetArtifactLexerData(unit) + " ...");
-			LexerDataGenerator genData = new LexerDataGenerator(unit.DFA, unit.Expected);
+			LexerDataGenerator genData = new LexerDataGenerator(unit.DFA, unit.Expected, HasDataHeaders);
 			genData.Generate(GetArtifactLexerData(unit));
 
 			// generate the lexer's code
@@ -357,11 +372,11 @@ namespace Hime.SDK.Output
 				case ParsingMethod.LR0:
 				case ParsingMethod.LR1:
 				case ParsingMethod.LALR1:
-					generator = new ParserLRkDataGenerator(unit);
+					generator = new ParserLRkDataGenerator(unit, HasDataHeaders);
 					break;
 				case ParsingMethod.RNGLR1:
 				case ParsingMethod.RNGLALR1:
-					generator = new ParserRNGLRDataGenerator(unit);
+					generator = new ParserRNGLRDataGenerator(unit, HasDataHeaders);
 					break;
 			}
 
@@ -373,9 +388,22 @@ namespace Hime.SDK.Output
 			reporter.Info("Exporting parser code at " + GetArtifactParserCode(unit) + " ...");
 			generator = GetParserCodeGenerator(unit);
 			generator.Generate(GetArtifactParserCode(unit));
+
+			// generate the symbol file, if the runtime can load parsers with it
+			generator = GetSymbolsGenerator(unit);
+			if (generator != null)
+			{
+				reporter.Info("Exporting symbols at " + GetArtifactSymbols(unit) + " ...");
+				generator.Generate(GetArtifactSymbols(unit));
+			}
 			return true;
 		}
 
+		/// <summary>
+		/// Gets whether the binary data of the automata starts with a header, if the runtime verifies it
+		/// </summary>
+		protected virtual bool HasDataHeaders { get { return false; } }
+
 		/// <summary>
 		/// Gets the runtime-specific generator of lexer code
 		/// </summary>
@@ -390,6 +418,16 @@ namespace Hime.SDK.Output
 		/// <returns>The runtime-specific generator of parser code</returns>
 		protected abstract Generator GetParserCodeGenerator(Unit unit);
 
+		/// <summary>
+		/// Gets the generator of the symbol file for loading the parser at runtime, if the runtime supports it
+		/// </summary>
+		/// <param name="unit">The unit to generate the symbols for</param>
+		/// <returns>The generator of the symbol file, or <c>null</c></returns>
+		protected virtual Generator GetSymbolsGenerator(Unit unit)
+		{
+			return null;
+		}
+
 		/// <summary>
 		/// Emits the assembly for the generated lexer and parser
 		/// </summary>
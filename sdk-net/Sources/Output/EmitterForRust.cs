@@ -116,6 +116,11 @@ namespace Hime.SDK.Output
 			this.runtime = runtime;
 		}
 
+		/// <summary>
+		/// Gets whether the binary data of the automata starts with a header, verified by the Rust runtime
+		/// </summary>
+		protected override bool HasDataHeaders { get { return true; } }
+
 		/// <summary>
 		/// Gets the runtime-specific generator of lexer code
 		/// </summary>
@@ -136,6 +141,16 @@ namespace Hime.SDK.Output
 			return new ParserRustCodeGenerator(unit, Helper.ToSnakeCase(unit.Grammar.Name), unit.Name + SUFFIX_PARSER_DATA);
 		}
 
+		/// <summary>
+		/// Gets the generator of the symbol file for loading the parser at runtime
+		/// </summary>
+		/// <param name="unit">The unit to generate the symbols for</param>
+		/// <returns>The generator of the symbol file</returns>
+		protected override Generator GetSymbolsGenerator(Unit unit)
+		{
+			return new SymbolsGenerator(unit);
+		}
+
 		/// <summary>
 		/// Emits the assembly for the generated lexer and parser
 		/// </summary>
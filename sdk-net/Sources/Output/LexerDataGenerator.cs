@@ -36,16 +36,22 @@ namespace Hime.SDK.Output
 		/// The lexer's DFA
 		/// </summary>
 		private readonly Automata.DFA dfa;
+		/// <summary>
+		/// Whether to write the header before the data
+		/// </summary>
+		private readonly bool withHeader;
 
 		/// <summary>
 		/// Initializes this generator
 		/// </summary>
 		/// <param name="dfa">The dfa to serialize</param>
 		/// <param name="expected">The terminals produced by the DFA</param>
-		public LexerDataGenerator(Automata.DFA dfa, ROList<Grammars.Terminal> expected)
+		/// <param name="withHeader">Whether to write the header before the data</param>
+		public LexerDataGenerator(Automata.DFA dfa, ROList<Grammars.Terminal> expected, bool withHeader)
 		{
 			this.dfa = dfa;
 			terminals = expected;
+			this.withHeader = withHeader;
 		}
 
 		/// <summary>
@@ -54,7 +60,8 @@ namespace Hime.SDK.Output
 		/// <param name="file">The file to output to</param>
 		public void Generate(string file)
 		{
-			BinaryWriter writer = new BinaryWriter(new FileStream(file, FileMode.Create));
+			MemoryStream stream = new MemoryStream();
+			BinaryWriter writer = new BinaryWriter(stream);
 
 			writer.Write((uint)dfa.StatesCount);
 			uint offset = 0;
@@ -81,6 +88,8 @@ namespace Hime.SDK.Output
 			foreach (Automata.DFAState state in dfa.States)
 				GenerateDataFor(writer, state);
 
+			writer.Flush();
+			DataHeader.Export(file, stream.ToArray(), withHeader, DataHeader.KIND_LEXER, dfa.StatesCount);
 			writer.Close();
 		}
 
@@ -81,23 +81,33 @@ namespace Hime.SDK.Output
 			writer.WriteLine("//! Hime Parser Generator " + CompilationTask.Version);
 			writer.WriteLine();
 			writer.WriteLine("use std::io::Read;");
+			writer.WriteLine("use std::mem;");
+			writer.WriteLine("use std::sync::OnceLock;");
 			writer.WriteLine();
 			writer.WriteLine("use hime_redist::ast::AstNode;");
 			writer.WriteLine("use hime_redist::errors::ParseErrors;");
 			writer.WriteLine("use hime_redist::lexers::automaton::Automaton;");
+			writer.WriteLine("use hime_redist::lexers::contexts::LayeredLexer;");
+			writer.WriteLine("use hime_redist::lexers::filters::FilteredLexer;");
 			writer.WriteLine("use hime_redist::lexers::impls::" + baseLexer + ";");
+			writer.WriteLine("use hime_redist::lexers::run_lexer;");
+			writer.WriteLine("use hime_redist::lexers::LexerOptions;");
+			writer.WriteLine("use hime_redist::lexers::LexerSetup;");
 			writer.WriteLine("use hime_redist::parsers::Parser;");
 			if (isParserRNGLR)
 			{
 				writer.WriteLine("use hime_redist::parsers::rnglr::RNGLRAutomaton;");
 				writer.WriteLine("use hime_redist::parsers::rnglr::RNGLRParser;");
+				writer.WriteLine("use hime_redist::parsers::rnglr::RNGLRParserBuffers;");
 			}
 			else
 			{
 				writer.WriteLine("use hime_redist::parsers::lrk::LRkAutomaton;");
 				writer.WriteLine("use hime_redist::parsers::lrk::LRkParser;");
+				writer.WriteLine("use hime_redist::parsers::lrk::LRkParserBuffers;");
 			}
 			writer.WriteLine("use hime_redist::result::ParseResult;");
+			writer.WriteLine("use hime_redist::result::TokenizeResult;");
 			writer.WriteLine("use hime_redist::symbols::SemanticBody;");
 			writer.WriteLine("use hime_redist::symbols::SemanticElementTrait;");
 			writer.WriteLine("use hime_redist::symbols::Symbol;");
@@ -107,7 +117,10 @@ namespace Hime.SDK.Output
 			writer.WriteLine();
 
 			writer.WriteLine("/// Static resource for the serialized lexer automaton");
-			writer.WriteLine("const LEXER_AUTOMATON: &'static [u8] = include_bytes!(\"" + binResource + "\");");
+			writer.WriteLine("const LEXER_AUTOMATON: &[u8] = include_bytes!(\"" + binResource + "\");");
+			writer.WriteLine();
+			writer.WriteLine("/// The lexer automaton, deserialized on first use and shared by all lexers");
+			writer.WriteLine("static LEXER_AUTOMATON_SHARED: OnceLock<Automaton> = OnceLock::new();");
 			writer.WriteLine();
 
 			for (int i = 2; i != terminals.Count; i++)
@@ -133,13 +146,13 @@ namespace Hime.SDK.Output
 			writer.WriteLine("/// The collection of terminals matched by this lexer");
 			writer.WriteLine("/// The terminals are in an order consistent with the automaton,");
 			writer.WriteLine("/// so that terminal indices in the automaton can be used to retrieve the terminals in this table");
-			writer.WriteLine("const TERMINALS: &'static [Symbol] = &[");
+			writer.WriteLine("const TERMINALS: &[Symbol] = &[");
 			bool first = true;
 			foreach (Terminal terminal in terminals)
 			{
 				if (!first)
 					writer.WriteLine(",");
-				writer.Write("    Symbol { id: 0x" + terminal.ID.ToString("X4") + ", name: \"" + terminal.ToString().Replace("\"", "\\\"") + "\" }");
+				writer.Write("    Symbol::new_static(0x" + terminal.ID.ToString("X4") + ", \"" + terminal.ToString().Replace("\"", "\\\"") + "\")");
 				first = false;
 			}
 			writer.WriteLine("];");
@@ -154,10 +167,55 @@ namespace Hime.SDK.Output
 			writer.WriteLine("    repository: TokenRepository<'a>,");
 			writer.WriteLine("    errors: &'a mut ParseErrors");
 			writer.WriteLine(") -> " + baseLexer + "<'a> {");
-			writer.WriteLine("    let automaton = Automaton::new(LEXER_AUTOMATON);");
+			writer.WriteLine("    let automaton = LEXER_AUTOMATON_SHARED.get_or_init(|| Automaton::from_static(LEXER_AUTOMATON));");
 			writer.WriteLine("    " + baseLexer + "::new(repository, errors, automaton, 0x" + sep + ")");
 			writer.WriteLine("}");
 			writer.WriteLine();
+
+			writer.WriteLine("/// Tokenizes the specified string with this lexer, without parsing");
+			writer.WriteLine("pub fn tokenize_string(input: &str) -> TokenizeResult {");
+			writer.WriteLine("    let text = Text::new(input);");
+			writer.WriteLine("    tokenize_text(text)");
+			writer.WriteLine("}");
+			writer.WriteLine();
+			writer.WriteLine("/// Tokenizes the specified stream of UTF-16 with this lexer, without parsing");
+			writer.WriteLine("pub fn tokenize_utf16(input: &mut dyn Read, big_endian: bool) -> TokenizeResult {");
+			writer.WriteLine("    let text = Text::from_utf16_stream(input, big_endian);");
+			writer.WriteLine("    tokenize_text(text)");
+			writer.WriteLine("}");
+			writer.WriteLine();
+			writer.WriteLine("/// Tokenizes the specified stream of UTF-8 with this lexer, without parsing");
+			writer.WriteLine("pub fn tokenize_utf8(input: &mut dyn Read) -> TokenizeResult {");
+			writer.WriteLine("    let text = Text::from_utf8_stream(input);");
+			writer.WriteLine("    tokenize_text(text)");
+			writer.WriteLine("}");
+			writer.WriteLine();
+			writer.WriteLine("/// Tokenizes the specified string with this lexer, after setting up the lexer with the specified function");
+			writer.WriteLine("/// The setup can for example retain the separators as trivia on the tokens.");
+			writer.WriteLine("pub fn tokenize_string_with_setup(input: &str, setup: &mut LexerSetup) -> TokenizeResult {");
+			writer.WriteLine("    let text = Text::new(input);");
+			writer.WriteLine("    tokenize_text_with(text, Some(setup))");
+			writer.WriteLine("}");
+			writer.WriteLine();
+			writer.WriteLine("/// Tokenizes the specified text with this lexer, without parsing");
+			writer.WriteLine("fn tokenize_text(text: Text) -> TokenizeResult {");
+			writer.WriteLine("    tokenize_text_with(text, None)");
+			writer.WriteLine("}");
+			writer.WriteLine();
+			writer.WriteLine("/// Tokenizes the specified text with this lexer, after the optional setup of the lexer");
+			writer.WriteLine("fn tokenize_text_with(text: Text, setup: Option<&mut LexerSetup>) -> TokenizeResult {");
+			writer.WriteLine("    let mut result = TokenizeResult::new(TERMINALS, text);");
+			writer.WriteLine("    {");
+			writer.WriteLine("        let data = result.get_lexing_data();");
+			writer.WriteLine("        let mut lexer = new_lexer(data.0, data.1);");
+			writer.WriteLine("        if let Some(setup) = setup {");
+			writer.WriteLine("            setup(&mut lexer);");
+			writer.WriteLine("        }");
+			writer.WriteLine("        run_lexer(&mut lexer);");
+			writer.WriteLine("    }");
+			writer.WriteLine("    result");
+			writer.WriteLine("}");
+			writer.WriteLine();
 			writer.Close();
 		}
 	}
@@ -51,6 +51,10 @@ namespace Hime.SDK.Output
 		/// </summary>
 		private readonly List<Action> actions;
 		/// <summary>
+		/// Whether to write the header before the data
+		/// </summary>
+		private readonly bool withHeader;
+		/// <summary>
 		/// The grammar rules
 		/// </summary>
 		private readonly List<Rule> rules;
@@ -59,7 +63,8 @@ namespace Hime.SDK.Output
 		/// Initializes this parser generator
 		/// </summary>
 		/// <param name="unit">The unit to generate a parser for</param>
-		public ParserLRkDataGenerator(Unit unit)
+		/// <param name="withHeader">Whether to write the header before the data</param>
+		public ParserLRkDataGenerator(Unit unit, bool withHeader)
 		{
 			graph = unit.Graph;
 			terminals = unit.Expected;
@@ -70,6 +75,7 @@ namespace Hime.SDK.Output
 			variables.Sort(new Grammars.Symbol.IdComparer<Variable>());
 			virtuals.Sort(new Grammars.Symbol.IdComparer<Virtual>());
 			actions.Sort(new Grammars.Symbol.IdComparer<Action>());
+			this.withHeader = withHeader;
 		}
 
 		/// <summary>
@@ -78,7 +84,8 @@ namespace Hime.SDK.Output
 		/// <param name="file">The file to output to</param>
 		public void Generate(string file)
 		{
-			BinaryWriter writer = new BinaryWriter(new FileStream(file, FileMode.Create));
+			MemoryStream stream = new MemoryStream();
+			BinaryWriter writer = new BinaryWriter(stream);
 
 			writer.Write((ushort)(terminals.Count + variables.Count));  // Nb of columns
 			writer.Write((ushort)graph.States.Count);                   // Nb or rows
@@ -119,6 +126,8 @@ namespace Hime.SDK.Output
 			foreach (Rule rule in rules)
 				GenerateDataProduction(writer, rule);
 
+			writer.Flush();
+			DataHeader.Export(file, stream.ToArray(), withHeader, DataHeader.KIND_LRK, graph.States.Count);
 			writer.Close();
 		}
 
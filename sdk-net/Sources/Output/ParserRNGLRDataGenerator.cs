@@ -54,12 +54,17 @@ namespace Hime.SDK.Output
 		/// The action symbols to be exported
 		/// </summary>
 		private readonly List<Action> actions;
+		/// <summary>
+		/// Whether to write the header before the data
+		/// </summary>
+		private readonly bool withHeader;
 
 		/// <summary>
 		/// Initializes this parser generator
 		/// </summary>
 		/// <param name="unit">The unit to generate a parser for</param>
-		public ParserRNGLRDataGenerator(Unit unit)
+		/// <param name="withHeader">Whether to write the header before the data</param>
+		public ParserRNGLRDataGenerator(Unit unit, bool withHeader)
 		{
 			grammar = unit.Grammar;
 			graph = unit.Graph;
@@ -70,6 +75,7 @@ namespace Hime.SDK.Output
 			variables.Sort(new Grammars.Symbol.IdComparer<Variable>());
 			virtuals.Sort(new Grammars.Symbol.IdComparer<Virtual>());
 			actions.Sort(new Grammars.Symbol.IdComparer<Action>());
+			this.withHeader = withHeader;
 		}
 
 		/// <summary>
@@ -138,7 +144,8 @@ namespace Hime.SDK.Output
 			foreach (State state in graph.States)
 				total = GenerateDataOffsetTable(offsets, counts, total, state);
 
-			BinaryWriter writer = new BinaryWriter(new FileStream(file, FileMode.Create));
+			MemoryStream stream = new MemoryStream();
+			BinaryWriter writer = new BinaryWriter(stream);
 
 			writer.Write((ushort)variables.IndexOf(grammar.GetVariable(grammar.GetOption(Grammar.OPTION_AXIOM))));
 			writer.Write((ushort)(terminals.Count + variables.Count));  // Nb of columns
@@ -191,6 +198,8 @@ namespace Hime.SDK.Output
 			foreach (int index in nullables)
 				writer.Write((ushort)index);
 
+			writer.Flush();
+			DataHeader.Export(file, stream.ToArray(), withHeader, DataHeader.KIND_RNGLR, graph.States.Count);
 			writer.Close();
 		}
 
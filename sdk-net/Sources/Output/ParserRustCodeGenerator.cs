@@ -114,7 +114,10 @@ namespace Hime.SDK.Output
 			StreamWriter writer = new StreamWriter(file, true, new UTF8Encoding(false));
 
 			writer.WriteLine("/// Static resource for the serialized parser automaton");
-			writer.WriteLine("const PARSER_AUTOMATON: &'static [u8] = include_bytes!(\"" + binResource + "\");");
+			writer.WriteLine("const PARSER_AUTOMATON: &[u8] = include_bytes!(\"" + binResource + "\");");
+			writer.WriteLine();
+			writer.WriteLine("/// The parser automaton, deserialized on first use and shared by all parsers");
+			writer.WriteLine("static PARSER_AUTOMATON_SHARED: OnceLock<" + automatonType + "> = OnceLock::new();");
 			writer.WriteLine();
 			GenerateCodeSymbols(writer);
 			GenerateCodeVariables(writer);
@@ -156,13 +159,13 @@ namespace Hime.SDK.Output
 			stream.WriteLine("/// The collection of variables matched by this parser");
 			stream.WriteLine("/// The variables are in an order consistent with the automaton,");
 			stream.WriteLine("/// so that variable indices in the automaton can be used to retrieve the variables in this table");
-			stream.WriteLine("const VARIABLES: &'static [Symbol] = &[");
+			stream.WriteLine("const VARIABLES: &[Symbol] = &[");
 			bool first = true;
 			foreach (Variable var in variables)
 			{
 				if (!first)
 					stream.WriteLine(",");
-				stream.Write("    Symbol { id: 0x" + var.ID.ToString("X4") + ", name: \"" + var.Name + "\" }");
+				stream.Write("    Symbol::new_static(0x" + var.ID.ToString("X4") + ", \"" + var.Name + "\")");
 				first = false;
 			}
 			stream.WriteLine("];");
@@ -178,13 +181,13 @@ namespace Hime.SDK.Output
 			stream.WriteLine("/// The collection of virtuals matched by this parser");
 			stream.WriteLine("/// The virtuals are in an order consistent with the automaton,");
 			stream.WriteLine("/// so that virtual indices in the automaton can be used to retrieve the virtuals in this table");
-			stream.WriteLine("const VIRTUALS: &'static [Symbol] = &[");
+			stream.WriteLine("const VIRTUALS: &[Symbol] = &[");
 			bool first = true;
 			foreach (Virtual v in virtuals)
 			{
 				if (!first)
 					stream.WriteLine(",");
-				stream.Write("    Symbol { id: 0x" + v.ID.ToString("X4") + ", name: \"" + v.Name + "\" }");
+				stream.Write("    Symbol::new_static(0x" + v.ID.ToString("X4") + ", \"" + v.Name + "\")");
 				first = false;
 			}
 			stream.WriteLine("];");
@@ -204,7 +207,7 @@ namespace Hime.SDK.Output
 			foreach (Action action in actions)
 			{
 				stream.WriteLine("    /// The " + action.Name + " semantic action");
-				stream.WriteLine("    fn " + Helper.ToSnakeCase(action.Name) + "(&mut self, head: Symbol, body: &SemanticBody);");
+				stream.WriteLine("    fn " + Helper.ToSnakeCase(action.Name) + "(&mut self, head: Symbol, body: &dyn SemanticBody);");
 			}
 			stream.WriteLine("}");
 			stream.WriteLine();
@@ -213,7 +216,7 @@ namespace Hime.SDK.Output
 			stream.WriteLine();
 			stream.WriteLine("impl Actions for NoActions {");
 			foreach (Action action in actions)
-				stream.WriteLine("    fn " + Helper.ToSnakeCase(action.Name) + "(&mut self, _head: Symbol, _body: &SemanticBody) {}");
+				stream.WriteLine("    fn " + Helper.ToSnakeCase(action.Name) + "(&mut self, _head: Symbol, _body: &dyn SemanticBody) {}");
 			stream.WriteLine("}");
 			stream.WriteLine();
 		}
@@ -234,7 +237,7 @@ namespace Hime.SDK.Output
 				}
 				stream.WriteLine("pub fn parse_string(input: &str) -> ParseResult {");
 				stream.WriteLine("    let text = Text::new(input);");
-				stream.WriteLine("    parse_text(text)");
+				stream.WriteLine("    parse_text(text, LexerOptions::default())");
 				stream.WriteLine("}");
 				stream.WriteLine();
 				stream.WriteLine("/// Parses the specified stream of UTF-16 with this parser");
@@ -243,9 +246,9 @@ namespace Hime.SDK.Output
 					stream.WriteLine("#[no_mangle]");
 					stream.WriteLine("#[export_name = \"" + nmespace + "_parse_utf16\"]");
 				}
-				stream.WriteLine("pub fn parse_utf16(input: &mut Read, big_endian: bool) -> ParseResult {");
+				stream.WriteLine("pub fn parse_utf16(input: &mut dyn Read, big_endian: bool) -> ParseResult {");
 				stream.WriteLine("    let text = Text::from_utf16_stream(input, big_endian);");
-				stream.WriteLine("    parse_text(text)");
+				stream.WriteLine("    parse_text(text, LexerOptions::default())");
 				stream.WriteLine("}");
 				stream.WriteLine();
 				stream.WriteLine("/// Parses the specified stream of UTF-16 with this parser");
@@ -254,24 +257,61 @@ namespace Hime.SDK.Output
 					stream.WriteLine("#[no_mangle]");
 					stream.WriteLine("#[export_name = \"" + nmespace + "_parse_utf8\"]");
 				}
-				stream.WriteLine("pub fn parse_utf8(input: &mut Read) -> ParseResult {");
+				stream.WriteLine("pub fn parse_utf8(input: &mut dyn Read) -> ParseResult {");
 				stream.WriteLine("    let text = Text::from_utf8_stream(input);");
-				stream.WriteLine("    parse_text(text)");
+				stream.WriteLine("    parse_text(text, LexerOptions::default())");
+				stream.WriteLine("}");
+				stream.WriteLine();
+				stream.WriteLine("/// Parses the specified string with this parser and the specified options for the lexer");
+				stream.WriteLine("/// The options can rewrite the stream of tokens with a filter, set up the lexer or resolve the lexical contexts through layers.");
+				if (outputAssembly)
+				{
+					stream.WriteLine("#[no_mangle]");
+					stream.WriteLine("#[export_name = \"" + nmespace + "_parse_string_with_options\"]");
+				}
+				stream.WriteLine("pub fn parse_string_with_options(input: &str, options: LexerOptions) -> ParseResult {");
+				stream.WriteLine("    let text = Text::new(input);");
+				stream.WriteLine("    parse_text(text, options)");
+				stream.WriteLine("}");
+				stream.WriteLine();
+				GenerateCodeNewContext(stream);
+				stream.WriteLine("/// Parses the specified string with this parser in a reusable context");
+				stream.WriteLine("/// The previous result in the context is reset and replaced by the new one.");
+				if (outputAssembly)
+				{
+					stream.WriteLine("#[no_mangle]");
+					stream.WriteLine("#[export_name = \"" + nmespace + "_parse_string_in\"]");
+				}
+				stream.WriteLine("pub fn parse_string_in<'c>(context: &'c mut ParseContext, input: &str) -> &'c ParseResult {");
+				stream.WriteLine("    parse_string_in_with_options(context, input, LexerOptions::default())");
+				stream.WriteLine("}");
+				stream.WriteLine();
+				stream.WriteLine("/// Parses the specified string with this parser in a reusable context and the specified options for the lexer");
+				stream.WriteLine("/// The previous result in the context is reset and replaced by the new one.");
+				if (outputAssembly)
+				{
+					stream.WriteLine("#[no_mangle]");
+					stream.WriteLine("#[export_name = \"" + nmespace + "_parse_string_in_with_options\"]");
+				}
+				stream.WriteLine("pub fn parse_string_in_with_options<'c>(context: &'c mut ParseContext, input: &str, options: LexerOptions) -> &'c ParseResult {");
+				stream.WriteLine("    context.result.reset(input);");
+				stream.WriteLine("    parse_into(&mut context.result, &mut context.buffers, options);");
+				stream.WriteLine("    &context.result");
 				stream.WriteLine("}");
 				stream.WriteLine();
 				stream.WriteLine("/// Parses the specified text with this parser");
-				stream.WriteLine("fn parse_text(text: Text) -> ParseResult {");
-				stream.WriteLine("    let mut my_actions = |_index: usize, _head: Symbol, _body: &SemanticBody| ();");
+				stream.WriteLine("fn parse_text(text: Text, options: LexerOptions) -> ParseResult {");
 				stream.WriteLine("    let mut result = ParseResult::new(TERMINALS, VARIABLES, VIRTUALS, text);");
-				stream.WriteLine("    {");
-				stream.WriteLine("        let data = result.get_parsing_data();");
-				stream.WriteLine("        let mut lexer = new_lexer(data.0, data.1);");
-				stream.WriteLine("        let automaton = " + automatonType + "::new(PARSER_AUTOMATON);");
-				stream.WriteLine("        let mut parser = " + parserType + "::new(&mut lexer, automaton, data.2, &mut my_actions);");
-				stream.WriteLine("        parser.parse();");
-				stream.WriteLine("    }");
+				stream.WriteLine("    let mut buffers = " + parserType + "Buffers::default();");
+				stream.WriteLine("    parse_into(&mut result, &mut buffers, options);");
 				stream.WriteLine("    result");
 				stream.WriteLine("}");
+				stream.WriteLine();
+				stream.WriteLine("/// Parses the text of the specified result with this parser");
+				stream.WriteLine("fn parse_into(result: &mut ParseResult, buffers: &mut " + parserType + "Buffers, options: LexerOptions) {");
+				stream.WriteLine("    let mut my_actions = |_index: usize, _head: Symbol, _body: &dyn SemanticBody| ();");
+				GenerateCodeParseBody(stream);
+				stream.WriteLine("}");
 			}
 			else
 			{
@@ -292,9 +332,9 @@ namespace Hime.SDK.Output
 					stream.WriteLine("#[no_mangle]");
 					stream.WriteLine("#[export_name = \"" + nmespace + "_parse_string_with\"]");
 				}
-				stream.WriteLine("pub fn parse_string_with(input: &str, actions: &mut Actions) -> ParseResult {");
+				stream.WriteLine("pub fn parse_string_with(input: &str, actions: &mut dyn Actions) -> ParseResult {");
 				stream.WriteLine("    let text = Text::new(input);");
-				stream.WriteLine("    parse_text(text, actions)");
+				stream.WriteLine("    parse_text(text, actions, LexerOptions::default())");
 				stream.WriteLine("}");
 				stream.WriteLine();
 				stream.WriteLine("/// Parses the specified stream of UTF-16 with this parser");
@@ -303,7 +343,7 @@ namespace Hime.SDK.Output
 					stream.WriteLine("#[no_mangle]");
 					stream.WriteLine("#[export_name = \"" + nmespace + "_parse_utf16\"]");
 				}
-				stream.WriteLine("pub fn parse_utf16(input: &mut Read, big_endian: bool) -> ParseResult {");
+				stream.WriteLine("pub fn parse_utf16(input: &mut dyn Read, big_endian: bool) -> ParseResult {");
 				stream.WriteLine("    let mut actions = NoActions {};");
 				stream.WriteLine("    parse_utf16_with(input, big_endian, &mut actions)");
 				stream.WriteLine("}");
@@ -314,9 +354,9 @@ namespace Hime.SDK.Output
 					stream.WriteLine("#[no_mangle]");
 					stream.WriteLine("#[export_name = \"" + nmespace + "_parse_utf16_with\"]");
 				}
-				stream.WriteLine("pub fn parse_utf16_with(input: &mut Read, big_endian: bool, actions: &mut Actions) -> ParseResult {");
+				stream.WriteLine("pub fn parse_utf16_with(input: &mut dyn Read, big_endian: bool, actions: &mut dyn Actions) -> ParseResult {");
 				stream.WriteLine("    let text = Text::from_utf16_stream(input, big_endian);");
-				stream.WriteLine("    parse_text(text, actions)");
+				stream.WriteLine("    parse_text(text, actions, LexerOptions::default())");
 				stream.WriteLine("}");
 				stream.WriteLine();
 				stream.WriteLine("/// Parses the specified stream of UTF-16 with this parser");
@@ -325,7 +365,7 @@ namespace Hime.SDK.Output
 					stream.WriteLine("#[no_mangle]");
 					stream.WriteLine("#[export_name = \"" + nmespace + "_parse_utf8\"]");
 				}
-				stream.WriteLine("pub fn parse_utf8(input: &mut Read) -> ParseResult {");
+				stream.WriteLine("pub fn parse_utf8(input: &mut dyn Read) -> ParseResult {");
 				stream.WriteLine("    let mut actions = NoActions {};");
 				stream.WriteLine("    parse_utf8_with(input, &mut actions)");
 				stream.WriteLine("}");
@@ -336,14 +376,71 @@ namespace Hime.SDK.Output
 					stream.WriteLine("#[no_mangle]");
 					stream.WriteLine("#[export_name = \"" + nmespace + "_parse_utf8_with\"]");
 				}
-				stream.WriteLine("pub fn parse_utf8_with(input: &mut Read, actions: &mut Actions) -> ParseResult {");
+				stream.WriteLine("pub fn parse_utf8_with(input: &mut dyn Read, actions: &mut dyn Actions) -> ParseResult {");
 				stream.WriteLine("    let text = Text::from_utf8_stream(input);");
-				stream.WriteLine("    parse_text(text, actions)");
+				stream.WriteLine("    parse_text(text, actions, LexerOptions::default())");
+				stream.WriteLine("}");
+				stream.WriteLine();
+				stream.WriteLine("/// Parses the specified string with this parser and the specified options for the lexer");
+				stream.WriteLine("/// The options can rewrite the stream of tokens with a filter, set up the lexer or resolve the lexical contexts through layers.");
+				if (outputAssembly)
+				{
+					stream.WriteLine("#[no_mangle]");
+					stream.WriteLine("#[export_name = \"" + nmespace + "_parse_string_with_options\"]");
+				}
+				stream.WriteLine("pub fn parse_string_with_options(input: &str, actions: &mut dyn Actions, options: LexerOptions) -> ParseResult {");
+				stream.WriteLine("    let text = Text::new(input);");
+				stream.WriteLine("    parse_text(text, actions, options)");
+				stream.WriteLine("}");
+				stream.WriteLine();
+				GenerateCodeNewContext(stream);
+				stream.WriteLine("/// Parses the specified string with this parser in a reusable context");
+				stream.WriteLine("/// The previous result in the context is reset and replaced by the new one.");
+				if (outputAssembly)
+				{
+					stream.WriteLine("#[no_mangle]");
+					stream.WriteLine("#[export_name = \"" + nmespace + "_parse_string_in\"]");
+				}
+				stream.WriteLine("pub fn parse_string_in<'c>(context: &'c mut ParseContext, input: &str) -> &'c ParseResult {");
+				stream.WriteLine("    let mut actions = NoActions {};");
+				stream.WriteLine("    parse_string_in_with(context, input, &mut actions)");
+				stream.WriteLine("}");
+				stream.WriteLine();
+				stream.WriteLine("/// Parses the specified string with this parser in a reusable context");
+				stream.WriteLine("/// The previous result in the context is reset and replaced by the new one.");
+				if (outputAssembly)
+				{
+					stream.WriteLine("#[no_mangle]");
+					stream.WriteLine("#[export_name = \"" + nmespace + "_parse_string_in_with\"]");
+				}
+				stream.WriteLine("pub fn parse_string_in_with<'c>(context: &'c mut ParseContext, input: &str, actions: &mut dyn Actions) -> &'c ParseResult {");
+				stream.WriteLine("    parse_string_in_with_options(context, input, actions, LexerOptions::default())");
+				stream.WriteLine("}");
+				stream.WriteLine();
+				stream.WriteLine("/// Parses the specified string with this parser in a reusable context and the specified options for the lexer");
+				stream.WriteLine("/// The previous result in the context is reset and replaced by the new one.");
+				if (outputAssembly)
+				{
+					stream.WriteLine("#[no_mangle]");
+					stream.WriteLine("#[export_name = \"" + nmespace + "_parse_string_in_with_options\"]");
+				}
+				stream.WriteLine("pub fn parse_string_in_with_options<'c>(context: &'c mut ParseContext, input: &str, actions: &mut dyn Actions, options: LexerOptions) -> &'c ParseResult {");
+				stream.WriteLine("    context.result.reset(input);");
+				stream.WriteLine("    parse_into(&mut context.result, &mut context.buffers, actions, options);");
+				stream.WriteLine("    &context.result");
 				stream.WriteLine("}");
 				stream.WriteLine();
 				stream.WriteLine("/// Parses the specified text with this parser");
-				stream.WriteLine("fn parse_text(text: Text, actions: &mut Actions) -> ParseResult {");
-				stream.WriteLine("    let mut my_actions = |index: usize, head: Symbol, body: &SemanticBody| match index {");
+				stream.WriteLine("fn parse_text(text: Text, actions: &mut dyn Actions, options: LexerOptions) -> ParseResult {");
+				stream.WriteLine("    let mut result = ParseResult::new(TERMINALS, VARIABLES, VIRTUALS, text);");
+				stream.WriteLine("    let mut buffers = " + parserType + "Buffers::default();");
+				stream.WriteLine("    parse_into(&mut result, &mut buffers, actions, options);");
+				stream.WriteLine("    result");
+				stream.WriteLine("}");
+				stream.WriteLine();
+				stream.WriteLine("/// Parses the text of the specified result with this parser");
+				stream.WriteLine("fn parse_into(result: &mut ParseResult, buffers: &mut " + parserType + "Buffers, actions: &mut dyn Actions, options: LexerOptions) {");
+				stream.WriteLine("    let mut my_actions = |index: usize, head: Symbol, body: &dyn SemanticBody| match index {");
 				int i = 0;
 				foreach (Action action in actions)
 				{
@@ -353,19 +450,86 @@ namespace Hime.SDK.Output
 				stream.WriteLine("        _ => ()");
 				stream.WriteLine("    };");
 				stream.WriteLine();
-				stream.WriteLine("    let mut result = ParseResult::new(TERMINALS, VARIABLES, VIRTUALS, text);");
-				stream.WriteLine("    {");
-				stream.WriteLine("        let data = result.get_parsing_data();");
-				stream.WriteLine("        let mut lexer = new_lexer(data.0, data.1);");
-				stream.WriteLine("        let automaton = " + automatonType + "::new(PARSER_AUTOMATON);");
-				stream.WriteLine("        let mut parser = " + parserType + "::new(&mut lexer, automaton, data.2, &mut my_actions);");
-				stream.WriteLine("        parser.parse();");
-				stream.WriteLine("    }");
-				stream.WriteLine("    result");
+				GenerateCodeParseBody(stream);
 				stream.WriteLine("}");
 			}
 		}
 
+		/// <summary>
+		/// Generates the reusable parse context and the function creating it
+		/// </summary>
+		/// <param name="stream">The output stream</param>
+		private void GenerateCodeNewContext(StreamWriter stream)
+		{
+			stream.WriteLine("/// A reusable context for parsing many inputs with this parser");
+			stream.WriteLine("/// The context keeps the allocations of its parse result and of the parser between parses.");
+			stream.WriteLine("pub struct ParseContext {");
+			stream.WriteLine("    /// The result of the last parse");
+			stream.WriteLine("    result: ParseResult,");
+			stream.WriteLine("    /// The buffers of the parser");
+			stream.WriteLine("    buffers: " + parserType + "Buffers");
+			stream.WriteLine("}");
+			stream.WriteLine();
+			stream.WriteLine("impl ParseContext {");
+			stream.WriteLine("    /// Gets the result of the last parse");
+			stream.WriteLine("    pub fn get_result(&self) -> &ParseResult {");
+			stream.WriteLine("        &self.result");
+			stream.WriteLine("    }");
+			stream.WriteLine();
+			stream.WriteLine("    /// Gets the mutable result of the last parse");
+			stream.WriteLine("    pub fn get_result_mut(&mut self) -> &mut ParseResult {");
+			stream.WriteLine("        &mut self.result");
+			stream.WriteLine("    }");
+			stream.WriteLine("}");
+			stream.WriteLine();
+			stream.WriteLine("/// Creates a new context for parsing many inputs with this parser while reusing allocations");
+			if (outputAssembly)
+			{
+				stream.WriteLine("#[no_mangle]");
+				stream.WriteLine("#[export_name = \"" + nmespace + "_new_context\"]");
+			}
+			stream.WriteLine("pub fn new_context() -> ParseContext {");
+			stream.WriteLine("    ParseContext {");
+			stream.WriteLine("        result: ParseResult::new(TERMINALS, VARIABLES, VIRTUALS, Text::new(\"\")),");
+			stream.WriteLine("        buffers: " + parserType + "Buffers::default()");
+			stream.WriteLine("    }");
+			stream.WriteLine("}");
+			stream.WriteLine();
+		}
+
+		/// <summary>
+		/// Generates the body of the parse_into function, after the definition of the semantic actions
+		/// </summary>
+		/// <param name="stream">The output stream</param>
+		private void GenerateCodeParseBody(StreamWriter stream)
+		{
+			stream.WriteLine("    let data = result.get_parsing_data();");
+			stream.WriteLine("    let automaton = PARSER_AUTOMATON_SHARED.get_or_init(|| " + automatonType + "::from_static(PARSER_AUTOMATON));");
+			stream.WriteLine("    let mut lexer = LayeredLexer::new(new_lexer(data.0, data.1));");
+			stream.WriteLine("    for layer in options.layers {");
+			stream.WriteLine("        lexer.add_layer(*layer);");
+			stream.WriteLine("    }");
+			stream.WriteLine("    match options.filter {");
+			stream.WriteLine("        None => {");
+			stream.WriteLine("            if let Some(setup) = options.setup {");
+			stream.WriteLine("                setup(&mut lexer);");
+			stream.WriteLine("            }");
+			stream.WriteLine("            let mut parser = " + parserType + "::new_with_buffers(&mut lexer, automaton, data.2, &mut my_actions, mem::take(buffers));");
+			stream.WriteLine("            parser.parse();");
+			stream.WriteLine("            *buffers = parser.into_buffers();");
+			stream.WriteLine("        }");
+			stream.WriteLine("        Some(filter) => {");
+			stream.WriteLine("            let mut lexer = FilteredLexer::new(lexer, filter);");
+			stream.WriteLine("            if let Some(setup) = options.setup {");
+			stream.WriteLine("                setup(&mut lexer);");
+			stream.WriteLine("            }");
+			stream.WriteLine("            let mut parser = " + parserType + "::new_with_buffers(&mut lexer, automaton, data.2, &mut my_actions, mem::take(buffers));");
+			stream.WriteLine("            parser.parse();");
+			stream.WriteLine("            *buffers = parser.into_buffers();");
+			stream.WriteLine("        }");
+			stream.WriteLine("    }");
+		}
+
 		/// <summary>
 		/// Generates the visitor for the parse result
 		/// </summary>
@@ -394,14 +558,14 @@ namespace Hime.SDK.Output
 			stream.WriteLine("}");
 			stream.WriteLine("");
 			stream.WriteLine("/// Walk the AST of a result using a visitor");
-			stream.WriteLine("pub fn visit(result: &ParseResult, visitor: &Visitor) {");
+			stream.WriteLine("pub fn visit(result: &ParseResult, visitor: &dyn Visitor) {");
 			stream.WriteLine("    let ast = result.get_ast();");
 			stream.WriteLine("    let root = ast.get_root();");
 			stream.WriteLine("    visit_ast_node(root, visitor);");
 			stream.WriteLine("}");
 			stream.WriteLine("");
 			stream.WriteLine("/// Walk the sub-AST from the specified node using a visitor");
-			stream.WriteLine("pub fn visit_ast_node<'a>(node: AstNode<'a>, visitor: &Visitor) {");
+			stream.WriteLine("pub fn visit_ast_node<'a>(node: AstNode<'a>, visitor: &dyn Visitor) {");
 			stream.WriteLine("    let children = node.children();");
 			stream.WriteLine("    for child in children.iter() {");
 			stream.WriteLine("        visit_ast_node(child, visitor);");
@@ -0,0 +1,101 @@
+/*******************************************************************************
+ * Copyright (c) 2017 Association Cénotélie (cenotelie.fr)
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Lesser General Public License as
+ * published by the Free Software Foundation, either version 3
+ * of the License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Lesser General Public License for more details.
+ *
+ * You should have received a copy of the GNU Lesser General
+ * Public License along with this program.
+ * If not, see <http://www.gnu.org/licenses/>.
+ ******************************************************************************/
+
+using System.Collections.Generic;
+using System.IO;
+using System.Text;
+using Hime.Redist.Utils;
+using Hime.SDK.Grammars;
+
+namespace Hime.SDK.Output
+{
+	/// <summary>
+	/// Represents a generator of the symbol file describing a grammar's symbols
+	/// The symbol file is used to load a parser at runtime from its binary data.
+	/// </summary>
+	public class SymbolsGenerator : Generator
+	{
+		/// <summary>
+		/// The name of the generated lexer and parser
+		/// </summary>
+		private readonly string name;
+		/// <summary>
+		/// Whether the lexer is context-sensitive
+		/// </summary>
+		private readonly bool isContextSensitive;
+		/// <summary>
+		/// Whether the parser is a RNGLR parser
+		/// </summary>
+		private readonly bool isParserRNGLR;
+		/// <summary>
+		/// The separator terminal, if any
+		/// </summary>
+		private readonly Terminal separator;
+		/// <summary>
+		/// The terminals matched by the lexer, in the order of the automata
+		/// </summary>
+		private readonly ROList<Terminal> terminals;
+		/// <summary>
+		/// The variables to be exported
+		/// </summary>
+		private readonly List<Variable> variables;
+		/// <summary>
+		/// The virtuals to be exported
+		/// </summary>
+		private readonly List<Virtual> virtuals;
+
+		/// <summary>
+		/// Initializes this generator
+		/// </summary>
+		/// <param name="unit">The unit to generate the symbols for</param>
+		public SymbolsGenerator(Unit unit)
+		{
+			this.name = unit.Name;
+			this.isContextSensitive = unit.Grammar.Contexts.Count > 1;
+			this.isParserRNGLR = (unit.Method == ParsingMethod.RNGLR1 || unit.Method == ParsingMethod.RNGLALR1);
+			this.separator = unit.Separator;
+			this.terminals = unit.Expected;
+			this.variables = new List<Variable>(unit.Grammar.Variables);
+			this.virtuals = new List<Virtual>(unit.Grammar.Virtuals);
+			this.variables.Sort(new Grammars.Symbol.IdComparer<Variable>());
+			this.virtuals.Sort(new Grammars.Symbol.IdComparer<Virtual>());
+		}
+
+		/// <summary>
+		/// Generates the symbol file
+		/// </summary>
+		/// <param name="file">The target file to generate the symbols in</param>
+		public void Generate(string file)
+		{
+			StreamWriter writer = new StreamWriter(file, false, new UTF8Encoding(false));
+			writer.WriteLine("# Symbols for the lexer and parser for " + name);
+			writer.WriteLine("# WARNING: this file has been generated by");
+			writer.WriteLine("# Hime Parser Generator " + CompilationTask.Version);
+			writer.WriteLine("lexer " + (isContextSensitive ? "context-sensitive" : "context-free"));
+			writer.WriteLine("parser " + (isParserRNGLR ? "rnglr" : "lrk"));
+			if (separator != null)
+				writer.WriteLine("separator 0x" + separator.ID.ToString("X4"));
+			foreach (Terminal terminal in terminals)
+				writer.WriteLine("terminal 0x" + terminal.ID.ToString("X4") + " " + terminal.ToString());
+			foreach (Variable variable in variables)
+				writer.WriteLine("variable 0x" + variable.ID.ToString("X4") + " " + variable.Name);
+			foreach (Virtual v in virtuals)
+				writer.WriteLine("virtual 0x" + v.ID.ToString("X4") + " " + v.Name);
+			writer.Close();
+		}
+	}
+}